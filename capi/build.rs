@@ -0,0 +1,16 @@
+// Generates rusty8.h from the API in src/lib.rs, with the settings in cbindgen.toml. It goes to OUT_DIR,
+// as builds mustn't write to the source tree: tests/header.rs checks include/rusty8.h is the same.
+
+use std::path::Path;
+
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("cargo sets CARGO_MANIFEST_DIR");
+    let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(Path::new(&crate_dir).join("cbindgen.toml")).expect("cbindgen.toml is valid");
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("the C API has a C header")
+        .write_to_file(Path::new(&out_dir).join("rusty8.h"));
+}
@@ -0,0 +1,114 @@
+#ifndef RUSTY8_H
+#define RUSTY8_H
+
+/* Generated by build.rs from src/lib.rs with cbindgen, don't edit it by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// What a call did.
+typedef enum Rusty8Status {
+  // Ran as asked.
+  RUSTY8_STATUS_OK = 0,
+  // The ROM ended, by running into empty memory or exiting. Nothing runs anymore.
+  RUSTY8_STATUS_FINISHED = 1,
+  // The ROM failed (e.g. an unknown instruction), see rusty8_last_error.
+  RUSTY8_STATUS_ERROR = 2,
+  // A null pointer, a buffer too small, a ROM too large, an unknown key or not a save state,
+  // see rusty8_last_error.
+  RUSTY8_STATUS_INVALID_ARGUMENT = 3,
+} Rusty8Status;
+
+// A CHIP-8 interpreter, with its ROM.
+typedef struct Rusty8 Rusty8;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create an interpreter, with no ROM loaded. `quirks` names a quirks preset (rusty-8, vip or
+// chip48), or is NULL for the default one. Returns NULL if the preset is unknown.
+//
+// # Safety
+// `quirks` must be NULL or a valid, NUL-terminated string.
+struct Rusty8 *rusty8_new(const char *quirks);
+
+// Destroy an interpreter. Does nothing if `handle` is NULL.
+//
+// # Safety
+// `handle` must be NULL or come from `rusty8_new`, and not be used afterwards.
+void rusty8_free(struct Rusty8 *handle);
+
+// Boot a ROM, `len` bytes at `rom`, replacing the one loaded before if any.
+// Fails if it's empty or doesn't fit in memory, keeping the ROM loaded before.
+//
+// # Safety
+// `handle` must be NULL or valid, and `rom` point to at least `len` readable bytes.
+enum Rusty8Status rusty8_load_rom(struct Rusty8 *handle, const uint8_t *rom, size_t len);
+
+// Run `cycles` clock cycles, without ticking the timers. Stops early if the ROM ends or fails.
+//
+// # Safety
+// `handle` must be NULL or valid.
+enum Rusty8Status rusty8_step(struct Rusty8 *handle, size_t cycles);
+
+// Run a 60Hz frame: `ticks` clock cycles (8 is the usual ~500Hz clock), then tick the timers.
+//
+// # Safety
+// `handle` must be NULL or valid.
+enum Rusty8Status rusty8_run_frame(struct Rusty8 *handle, size_t ticks);
+
+// Press key [0..F]. Does nothing if it's already down.
+//
+// # Safety
+// `handle` must be NULL or valid.
+enum Rusty8Status rusty8_key_down(struct Rusty8 *handle, uint8_t key);
+
+// Release key [0..F]. Does nothing if it's already up.
+//
+// # Safety
+// `handle` must be NULL or valid.
+enum Rusty8Status rusty8_key_up(struct Rusty8 *handle, uint8_t key);
+
+// The screen, a byte per pixel (0 or 255) row after row, and its `width` and `height`
+// (either can be NULL). The pointer stays valid until the next call taking `handle`.
+// Returns NULL if `handle` is NULL.
+//
+// # Safety
+// `handle` must be NULL or valid, and `width` and `height` NULL or writable.
+const uint8_t *rusty8_framebuffer(const struct Rusty8 *handle, size_t *width, size_t *height);
+
+// Wether the beep sounded during the last frame run.
+//
+// # Safety
+// `handle` must be NULL or valid.
+bool rusty8_is_beeping(const struct Rusty8 *handle);
+
+// Size of a save state, in bytes.
+size_t rusty8_state_size(void);
+
+// Save the machine state into `buffer`, which must hold `rusty8_state_size()` bytes.
+//
+// # Safety
+// `handle` must be NULL or valid, and `buffer` point to at least `len` writable bytes.
+enum Rusty8Status rusty8_save_state(struct Rusty8 *handle, uint8_t *buffer, size_t len);
+
+// Restore a state saved by `rusty8_save_state`, `len` bytes at `buffer`.
+//
+// # Safety
+// `handle` must be NULL or valid, and `buffer` point to at least `len` readable bytes.
+enum Rusty8Status rusty8_load_state(struct Rusty8 *handle, const uint8_t *buffer, size_t len);
+
+// Why the last call that failed did, or NULL if none did.
+// The string stays valid until the next call taking `handle`.
+//
+// # Safety
+// `handle` must be NULL or valid.
+const char *rusty8_last_error(const struct Rusty8 *handle);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* RUSTY8_H */
@@ -0,0 +1,272 @@
+//! C API to the rusty-8 interpreter core, to embed it in C and C++ programs.
+//!
+//! Everything goes through an opaque `Rusty8` handle, from `rusty8_new` until `rusty8_free`.
+//! Functions that can fail return a `Rusty8Status`, and `rusty8_last_error` tells what went wrong.
+//! The header, `include/rusty8.h`, is generated from this file by the build script.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use rusty_8::{Chip8, Quirks};
+use rusty_8::chip8::{SCREEN_WIDTH, SCREEN_HEIGHT, STATE_SIZE};
+
+/// What a call did.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rusty8Status {
+    /// Ran as asked.
+    Ok = 0,
+    /// The ROM ended, by running into empty memory or exiting. Nothing runs anymore.
+    Finished = 1,
+    /// The ROM failed (e.g. an unknown instruction), see rusty8_last_error.
+    Error = 2,
+    /// A null pointer, a buffer too small, a ROM too large, an unknown key or not a save state,
+    /// see rusty8_last_error.
+    InvalidArgument = 3,
+}
+
+/// A CHIP-8 interpreter, with its ROM.
+pub struct Rusty8 {
+    chip8: Chip8,
+    quirks: Quirks,             // Quirks a newly loaded ROM runs with
+    is_beeping: bool,           // Wether the last frame beeped
+    error: Option<CString>,     // Why the last failed call failed
+}
+
+impl Rusty8 {
+    fn fail(&mut self, status: Rusty8Status, message: String) -> Rusty8Status {
+        self.error = CString::new(message).ok();
+        status
+    }
+}
+
+/// Create an interpreter, with no ROM loaded. `quirks` names a quirks preset (rusty-8, vip or
+/// chip48), or is NULL for the default one. Returns NULL if the preset is unknown.
+///
+/// # Safety
+/// `quirks` must be NULL or a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_new(quirks: *const c_char) -> *mut Rusty8 {
+    let quirks = match quirks.is_null() {
+        true => Some(Quirks::default()),
+        false => CStr::from_ptr(quirks).to_str().ok().and_then(Quirks::from_name),
+    };
+
+    match quirks {
+        Some(quirks) => Box::into_raw(Box::new(Rusty8 { chip8: Chip8::with_quirks(quirks), quirks, is_beeping: false, error: None })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Destroy an interpreter. Does nothing if `handle` is NULL.
+///
+/// # Safety
+/// `handle` must be NULL or come from `rusty8_new`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_free(handle: *mut Rusty8) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Boot a ROM, `len` bytes at `rom`, replacing the one loaded before if any.
+/// Fails if it's empty or doesn't fit in memory, keeping the ROM loaded before.
+///
+/// # Safety
+/// `handle` must be NULL or valid, and `rom` point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_load_rom(handle: *mut Rusty8, rom: *const u8, len: usize) -> Rusty8Status {
+    let handle = match handle.as_mut() {
+        Some(handle) => handle,
+        None => return Rusty8Status::InvalidArgument,
+    };
+    if rom.is_null() {
+        return handle.fail(Rusty8Status::InvalidArgument, "ROM is NULL".to_string());
+    }
+
+    let mut chip8 = Chip8::with_quirks(handle.quirks);
+    if let Err(e) = chip8.load_rom_bytes(std::slice::from_raw_parts(rom, len)) {
+        return handle.fail(Rusty8Status::InvalidArgument, e.to_string());
+    }
+    handle.chip8 = chip8;
+    handle.is_beeping = false;
+    Rusty8Status::Ok
+}
+
+/// Run `cycles` clock cycles, without ticking the timers. Stops early if the ROM ends or fails.
+///
+/// # Safety
+/// `handle` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_step(handle: *mut Rusty8, cycles: usize) -> Rusty8Status {
+    let handle = match handle.as_mut() {
+        Some(handle) => handle,
+        None => return Rusty8Status::InvalidArgument,
+    };
+
+    for _ in 0..cycles {
+        if handle.chip8.finished_running() {
+            break;
+        }
+        if let Err(e) = handle.chip8.tick() {
+            return handle.fail(Rusty8Status::Error, e.to_string());
+        }
+    }
+    status(&handle.chip8)
+}
+
+/// Run a 60Hz frame: `ticks` clock cycles (8 is the usual ~500Hz clock), then tick the timers.
+///
+/// # Safety
+/// `handle` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_run_frame(handle: *mut Rusty8, ticks: usize) -> Rusty8Status {
+    let handle = match handle.as_mut() {
+        Some(handle) => handle,
+        None => return Rusty8Status::InvalidArgument,
+    };
+
+    match handle.chip8.run_frame(ticks) {
+        Ok(is_beeping) => {
+            handle.is_beeping = is_beeping;
+            status(&handle.chip8)
+        },
+        Err(e) => {
+            handle.is_beeping = false;
+            handle.fail(Rusty8Status::Error, e.to_string())
+        },
+    }
+}
+
+/// Press key [0..F]. Does nothing if it's already down.
+///
+/// # Safety
+/// `handle` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_key_down(handle: *mut Rusty8, key: u8) -> Rusty8Status {
+    set_key(handle, key, true)
+}
+
+/// Release key [0..F]. Does nothing if it's already up.
+///
+/// # Safety
+/// `handle` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_key_up(handle: *mut Rusty8, key: u8) -> Rusty8Status {
+    set_key(handle, key, false)
+}
+
+/// The screen, a byte per pixel (0 or 255) row after row, and its `width` and `height`
+/// (either can be NULL). The pointer stays valid until the next call taking `handle`.
+/// Returns NULL if `handle` is NULL.
+///
+/// # Safety
+/// `handle` must be NULL or valid, and `width` and `height` NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_framebuffer(handle: *const Rusty8, width: *mut usize, height: *mut usize) -> *const u8 {
+    if let Some(width) = width.as_mut() {
+        *width = SCREEN_WIDTH;
+    }
+    if let Some(height) = height.as_mut() {
+        *height = SCREEN_HEIGHT;
+    }
+
+    match handle.as_ref() {
+        Some(handle) => handle.chip8.screen.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Wether the beep sounded during the last frame run.
+///
+/// # Safety
+/// `handle` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_is_beeping(handle: *const Rusty8) -> bool {
+    handle.as_ref().is_some_and(|handle| handle.is_beeping)
+}
+
+/// Size of a save state, in bytes.
+#[no_mangle]
+pub extern "C" fn rusty8_state_size() -> usize {
+    STATE_SIZE
+}
+
+/// Save the machine state into `buffer`, which must hold `rusty8_state_size()` bytes.
+///
+/// # Safety
+/// `handle` must be NULL or valid, and `buffer` point to at least `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_save_state(handle: *mut Rusty8, buffer: *mut u8, len: usize) -> Rusty8Status {
+    let handle = match handle.as_mut() {
+        Some(handle) => handle,
+        None => return Rusty8Status::InvalidArgument,
+    };
+    if buffer.is_null() || len < STATE_SIZE {
+        return handle.fail(Rusty8Status::InvalidArgument, format!("A save state needs a buffer of {} bytes", STATE_SIZE));
+    }
+
+    let state = handle.chip8.save_state();
+    ptr::copy_nonoverlapping(state.as_ptr(), buffer, state.len());
+    Rusty8Status::Ok
+}
+
+/// Restore a state saved by `rusty8_save_state`, `len` bytes at `buffer`.
+///
+/// # Safety
+/// `handle` must be NULL or valid, and `buffer` point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_load_state(handle: *mut Rusty8, buffer: *const u8, len: usize) -> Rusty8Status {
+    let handle = match handle.as_mut() {
+        Some(handle) => handle,
+        None => return Rusty8Status::InvalidArgument,
+    };
+    if buffer.is_null() {
+        return handle.fail(Rusty8Status::InvalidArgument, "Save state is NULL".to_string());
+    }
+
+    match handle.chip8.load_state(std::slice::from_raw_parts(buffer, len)) {
+        Ok(()) => {
+            handle.is_beeping = false;
+            Rusty8Status::Ok
+        },
+        Err(e) => handle.fail(Rusty8Status::InvalidArgument, e.to_string()),
+    }
+}
+
+/// Why the last call that failed did, or NULL if none did.
+/// The string stays valid until the next call taking `handle`.
+///
+/// # Safety
+/// `handle` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rusty8_last_error(handle: *const Rusty8) -> *const c_char {
+    match handle.as_ref().and_then(|handle| handle.error.as_ref()) {
+        Some(error) => error.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+fn status(chip8: &Chip8) -> Rusty8Status {
+    match chip8.finished_running() {
+        true => Rusty8Status::Finished,
+        false => Rusty8Status::Ok,
+    }
+}
+
+unsafe fn set_key(handle: *mut Rusty8, key: u8, is_down: bool) -> Rusty8Status {
+    let handle = match handle.as_mut() {
+        Some(handle) => handle,
+        None => return Rusty8Status::InvalidArgument,
+    };
+    if key > 0xF {
+        return handle.fail(Rusty8Status::InvalidArgument, format!("No key {}, keys go from 0 to 0xF", key));
+    }
+
+    match is_down {
+        true => handle.chip8.press_key(key),
+        false => handle.chip8.release_key(key),
+    }
+    Rusty8Status::Ok
+}
@@ -0,0 +1,138 @@
+/*
+ * Exercises the rusty-8 C API: runs small ROMs through every function and checks what comes out.
+ * Prints each check, and exits with 1 if any failed.
+ *
+ * `cargo test -p rusty-8-capi` builds and runs it (tests/c_test.rs), or by hand (from the repository root):
+ *   cargo build --release -p rusty-8-capi
+ *   cc -o target/capi-test capi/test/test.c -Icapi/include target/release/librusty8_c.a -lpthread -ldl -lm
+ *   ./target/capi-test
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "rusty8.h"
+
+static int failures = 0;
+
+#define CHECK(condition) do { \
+    int passed = (condition); \
+    printf("%s %s\n", passed ? "ok  " : "FAIL", #condition); \
+    failures += !passed; \
+} while (0)
+
+/* Draw the font's 5 at the top-left corner, then loop forever */
+static const uint8_t DRAW_FIVE[] = {
+    0x60, 0x05, /* V0 = 5 */
+    0xF0, 0x29, /* I = font sprite of V0 */
+    0x61, 0x00, /* V1 = 0 */
+    0xD1, 0x15, /* Draw 5 rows at (V1, V1) */
+    0x12, 0x08, /* Jump to itself */
+};
+
+/* Beep for a few frames, then wait for a key into V3 and end */
+static const uint8_t BEEP_AND_WAIT[] = {
+    0x60, 0x04, /* V0 = 4 */
+    0xF0, 0x18, /* ST = V0 */
+    0xF3, 0x0A, /* V3 = next key */
+    0x00, 0x00, /* End */
+};
+
+static const uint8_t UNKNOWN_INSTRUCTION[] = { 0xE0, 0x00 };
+
+static size_t lit_pixels(const Rusty8 *chip8) {
+    size_t width, height, lit = 0;
+    const uint8_t *screen = rusty8_framebuffer(chip8, &width, &height);
+    for (size_t i = 0; i < width * height; i++) {
+        lit += screen[i] != 0;
+    }
+    return lit;
+}
+
+static void test_screen(void) {
+    Rusty8 *chip8 = rusty8_new(NULL);
+    CHECK(rusty8_load_rom(chip8, DRAW_FIVE, sizeof DRAW_FIVE) == RUSTY8_STATUS_OK);
+
+    size_t width = 0, height = 0;
+    const uint8_t *screen = rusty8_framebuffer(chip8, &width, &height);
+    CHECK(width == 64 && height == 32);
+    CHECK(lit_pixels(chip8) == 0);
+
+    CHECK(rusty8_step(chip8, 4) == RUSTY8_STATUS_OK);
+    CHECK(memcmp(screen, "\xFF\xFF\xFF\xFF\x00", 5) == 0);
+    CHECK(lit_pixels(chip8) == 14);
+
+    rusty8_free(chip8);
+}
+
+static void test_keys_and_sound(void) {
+    Rusty8 *chip8 = rusty8_new("vip");
+    CHECK(chip8 != NULL);
+    CHECK(rusty8_load_rom(chip8, BEEP_AND_WAIT, sizeof BEEP_AND_WAIT) == RUSTY8_STATUS_OK);
+
+    CHECK(rusty8_run_frame(chip8, 8) == RUSTY8_STATUS_OK);
+    CHECK(rusty8_is_beeping(chip8));
+    for (int frame = 0; frame < 10; frame++) {
+        CHECK(rusty8_run_frame(chip8, 8) == RUSTY8_STATUS_OK);
+    }
+    CHECK(!rusty8_is_beeping(chip8));
+
+    CHECK(rusty8_key_down(chip8, 0x7) == RUSTY8_STATUS_OK);
+    CHECK(rusty8_run_frame(chip8, 8) == RUSTY8_STATUS_OK);
+    CHECK(rusty8_key_up(chip8, 0x7) == RUSTY8_STATUS_OK);
+    CHECK(rusty8_run_frame(chip8, 8) == RUSTY8_STATUS_FINISHED);
+    CHECK(rusty8_step(chip8, 1) == RUSTY8_STATUS_FINISHED);
+
+    CHECK(rusty8_key_down(chip8, 0x10) == RUSTY8_STATUS_INVALID_ARGUMENT);
+    CHECK(rusty8_last_error(chip8) != NULL);
+
+    rusty8_free(chip8);
+}
+
+static void test_save_states(void) {
+    Rusty8 *chip8 = rusty8_new(NULL);
+    rusty8_load_rom(chip8, DRAW_FIVE, sizeof DRAW_FIVE);
+
+    size_t size = rusty8_state_size();
+    uint8_t *state = (uint8_t *)malloc(size);
+    CHECK(rusty8_save_state(chip8, state, size - 1) == RUSTY8_STATUS_INVALID_ARGUMENT);
+    CHECK(rusty8_save_state(chip8, state, size) == RUSTY8_STATUS_OK);
+
+    rusty8_run_frame(chip8, 8);
+    CHECK(lit_pixels(chip8) == 14);
+    CHECK(rusty8_load_state(chip8, state, size) == RUSTY8_STATUS_OK);
+    CHECK(lit_pixels(chip8) == 0);
+
+    CHECK(rusty8_load_state(chip8, (const uint8_t *)"nope", 4) == RUSTY8_STATUS_INVALID_ARGUMENT);
+    free(state);
+    rusty8_free(chip8);
+}
+
+static void test_errors(void) {
+    CHECK(rusty8_new("nope") == NULL);
+    CHECK(rusty8_run_frame(NULL, 8) == RUSTY8_STATUS_INVALID_ARGUMENT);
+    CHECK(rusty8_framebuffer(NULL, NULL, NULL) == NULL);
+    rusty8_free(NULL);
+
+    Rusty8 *chip8 = rusty8_new(NULL);
+    CHECK(rusty8_last_error(chip8) == NULL);
+    static const uint8_t TOO_LARGE[4096];
+    CHECK(rusty8_load_rom(chip8, TOO_LARGE, sizeof TOO_LARGE) == RUSTY8_STATUS_INVALID_ARGUMENT);
+    CHECK(rusty8_last_error(chip8) != NULL && strstr(rusty8_last_error(chip8), "too large") != NULL);
+    rusty8_load_rom(chip8, UNKNOWN_INSTRUCTION, sizeof UNKNOWN_INSTRUCTION);
+    CHECK(rusty8_run_frame(chip8, 8) == RUSTY8_STATUS_ERROR);
+    CHECK(rusty8_last_error(chip8) != NULL && strstr(rusty8_last_error(chip8), "Unknown instruction") != NULL);
+    printf("     last error: %s\n", rusty8_last_error(chip8));
+
+    rusty8_free(chip8);
+}
+
+int main(void) {
+    test_screen();
+    test_keys_and_sound();
+    test_save_states();
+    test_errors();
+
+    printf("%d failure(s)\n", failures);
+    return failures == 0 ? 0 : 1;
+}
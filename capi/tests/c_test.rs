@@ -0,0 +1,48 @@
+// Builds test/test.c against the static library and runs it, to check the API works from C.
+// Skipped when there's no C compiler (`cc`).
+
+#![cfg(unix)]
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_test_passes() {
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("cc not found, skipping the C test");
+        return;
+    }
+
+    // Testing builds the library for Rust only, so build the static library itself, with the same
+    // profile and target directory: the test runs from target/<profile>/deps
+    let test_exe = std::env::current_exe().unwrap();
+    let profile_dir = test_exe.parent().and_then(Path::parent).unwrap();
+    let target_dir = profile_dir.parent().unwrap();
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo.arg("build").arg("--lib")
+        .arg("--manifest-path").arg(crate_dir.join("Cargo.toml"))
+        .arg("--target-dir").arg(target_dir);
+    match profile_dir.file_name().and_then(|name| name.to_str()) {
+        Some("debug") => {},
+        Some(profile) => { cargo.args(["--profile", profile]); },
+        None => panic!("no profile directory in {}", test_exe.display()),
+    }
+    assert!(cargo.status().unwrap().success(), "the static library doesn't build");
+    let library = profile_dir.join("librusty8_c.a");
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi-test");
+
+    let status = Command::new("cc")
+        .arg("-o").arg(&exe)
+        .arg(crate_dir.join("test/test.c"))
+        .arg("-I").arg(env!("OUT_DIR"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm"])
+        .status()
+        .unwrap();
+    assert!(status.success(), "test.c doesn't build against {}", library.display());
+
+    let output = Command::new(&exe).output().unwrap();
+    print!("{}", String::from_utf8_lossy(&output.stdout));
+    assert!(output.status.success(), "test.c failed:\n{}", String::from_utf8_lossy(&output.stdout));
+}
@@ -0,0 +1,15 @@
+// include/rusty8.h is committed for C programs to use without building, so it must match the API.
+// The build generates the header anew in OUT_DIR, which it's compared to.
+
+use std::path::Path;
+
+#[test]
+fn committed_header_is_up_to_date() {
+    let generated_path = Path::new(env!("OUT_DIR")).join("rusty8.h");
+    let committed_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/rusty8.h");
+    let generated = std::fs::read_to_string(&generated_path).unwrap();
+    let committed = std::fs::read_to_string(&committed_path).unwrap();
+
+    assert!(generated == committed, "{} is out of date, update it with:\n\tcp {} {}",
+        committed_path.display(), generated_path.display(), committed_path.display());
+}
@@ -0,0 +1,30 @@
+// Restores corrupted save states and runs them: loading may fail, running may fail, nothing may panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_8::Chip8;
+use rusty_8::chip8::MEM_SIZE;
+
+const MAX_FRAMES: usize = 10;
+
+fuzz_target!(|data: &[u8]| {
+    // Random bytes are almost never a save state, so corrupt a real one with them instead.
+    // Memory is skipped, run_image already throws anything at it: registers and the rest are what's left
+    let mut chip8 = Chip8::new();
+    let mut state = chip8.save_state();
+    for (byte, corruption) in state.iter_mut().skip(5 + MEM_SIZE).zip(data) {
+        *byte ^= corruption;
+    }
+
+    if chip8.load_state(&state).is_err() {
+        return;
+    }
+    for _ in 0..MAX_FRAMES {
+        if chip8.run_frame(16).is_err() {
+            break;
+        }
+    }
+
+    chip8.reboot();
+    let _ = chip8.run_frame(16);
+});
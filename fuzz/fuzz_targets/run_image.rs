@@ -0,0 +1,51 @@
+// Runs arbitrary memory images: the core may fail with a `Chip8Error`, but never panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_8::{Chip8, MemoryMap, Quirks, StackConfig, Timing};
+
+const MAX_FRAMES: usize = 60;   // Most images loop forever, a second is plenty to go through them
+const TICKS_PER_FRAME: usize = 32;
+
+fuzz_target!(|data: &[u8]| {
+    // The first 2 bytes pick the configuration, the rest is loaded as the ROM
+    let (config, image) = match data {
+        [quirks, config, image @ ..] => ((*quirks, *config), image),
+        _ => return,
+    };
+
+    let mut chip8 = Chip8::with_quirks(Quirks {
+        shift_uses_vy: config.0 & 0x01 != 0,
+        memory_increments_i: config.0 & 0x02 != 0,
+        jump_uses_vx: config.0 & 0x04 != 0,
+        logic_resets_vf: config.0 & 0x08 != 0,
+        key_wait_release: config.0 & 0x10 != 0,
+        display_wait: config.0 & 0x20 != 0,
+        wrap_addresses: config.0 & 0x40 != 0,
+    });
+    chip8.timing = if config.1 & 0x01 != 0 { Timing::CosmacVip } else { Timing::Fixed };
+    chip8.stack_config = StackConfig {
+        max_depth: if config.1 & 0x02 != 0 { Some(12) } else { None },
+        in_memory: config.1 & 0x04 == 0,
+    };
+    if config.1 & 0x08 != 0 {
+        chip8.set_memory_map(MemoryMap::ETI_660).unwrap();
+    }
+
+    let program_start = chip8.memory_map().program_start as usize;
+    let memory = &mut chip8.memory_mut()[program_start..];
+    let len = image.len().min(memory.len());
+    memory[..len].copy_from_slice(&image[..len]);
+
+    for frame in 0..MAX_FRAMES {
+        // Keep pressing and releasing keys, so key waits and skips get answered
+        let mut keys = [false; 16];
+        keys[frame % 16] = frame % 3 != 0;
+        chip8.set_keys(&keys);
+
+        match chip8.run_frame(TICKS_PER_FRAME) {
+            Ok(_) if !chip8.finished_running() => {},
+            _ => break,
+        }
+    }
+});
@@ -0,0 +1,464 @@
+//! rusty-8 as a libretro core, so CHIP-8 ROMs can run inside RetroArch and other frontends.
+//!
+//! The frontend calls `retro_run` once per 60Hz frame. Every frame runs a configurable
+//! number of CHIP-8 cycles, ticks the timers once, then hands over the 64x32 screen and
+//! a frame worth of (square wave) beep samples.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_uint, c_void};
+use std::ptr;
+use std::sync::Mutex;
+
+use rusty_8::{Chip8, MemoryMap, Quirks, Timing};
+use rusty_8::chip8::{SCREEN_WIDTH, SCREEN_HEIGHT, STATE_SIZE};
+
+pub mod sys;
+use sys::*;
+
+const FPS: f64 = 60.0;
+const SAMPLE_RATE: f64 = 44100.0;
+const SAMPLES_PER_FRAME: usize = (SAMPLE_RATE / FPS) as usize;
+
+const BEEP_FREQUENCY: f32 = 815.0;  // Same pitch as the desktop frontend
+const BEEP_AMPLITUDE: i16 = 9830;   // 30% of full volume
+
+const PIXEL_ON:  u32 = 0x00FF_FFFF; // XRGB8888 white
+const PIXEL_OFF: u32 = 0x0000_0000; // XRGB8888 black
+
+const DEFAULT_TICKS_PER_FRAME: usize = 8;   // ~500Hz clock, like the desktop frontend
+
+// Keyboard layout is the same as the desktop frontend: 1234/QWER/ASDF/ZXCV
+const CHIP8_RETRO_KEYS: [c_uint; 16] = [
+    RETROK_X,   // 0
+    RETROK_1,   // 1
+    RETROK_2,   // 2
+    RETROK_3,   // 3
+    RETROK_Q,   // 4
+    RETROK_W,   // 5
+    RETROK_E,   // 6
+    RETROK_A,   // 7
+    RETROK_S,   // 8
+    RETROK_D,   // 9
+    RETROK_Z,   // A
+    RETROK_C,   // B
+    RETROK_4,   // C
+    RETROK_R,   // D
+    RETROK_F,   // E
+    RETROK_V    // F
+];
+
+// Joypad button -> CHIP-8 key. The d-pad sits on 2/4/6/8, which most games move with.
+const CHIP8_JOYPAD_KEYS: [(c_uint, usize); 16] = [
+    (RETRO_DEVICE_ID_JOYPAD_UP,     0x2),
+    (RETRO_DEVICE_ID_JOYPAD_DOWN,   0x8),
+    (RETRO_DEVICE_ID_JOYPAD_LEFT,   0x4),
+    (RETRO_DEVICE_ID_JOYPAD_RIGHT,  0x6),
+    (RETRO_DEVICE_ID_JOYPAD_A,      0x5),
+    (RETRO_DEVICE_ID_JOYPAD_B,      0x0),
+    (RETRO_DEVICE_ID_JOYPAD_X,      0x1),
+    (RETRO_DEVICE_ID_JOYPAD_Y,      0x3),
+    (RETRO_DEVICE_ID_JOYPAD_L,      0x7),
+    (RETRO_DEVICE_ID_JOYPAD_R,      0x9),
+    (RETRO_DEVICE_ID_JOYPAD_L2,     0xA),
+    (RETRO_DEVICE_ID_JOYPAD_R2,     0xB),
+    (RETRO_DEVICE_ID_JOYPAD_L3,     0xC),
+    (RETRO_DEVICE_ID_JOYPAD_R3,     0xD),
+    (RETRO_DEVICE_ID_JOYPAD_SELECT, 0xE),
+    (RETRO_DEVICE_ID_JOYPAD_START,  0xF),
+];
+
+// Core options, shown by the frontend as "Description; default|other|values"
+const QUIRKS_KEY: &[u8] = b"rusty8_quirks\0";
+const QUIRKS_VALUES: &[u8] = b"Quirks; rusty-8|vip|chip48\0";
+const TIMING_KEY: &[u8] = b"rusty8_timing\0";
+const TIMING_VALUES: &[u8] = b"Instruction timing; fixed|vip\0";
+const MEMORY_MAP_KEY: &[u8] = b"rusty8_memory_map\0";
+const MEMORY_MAP_VALUES: &[u8] = b"Memory layout (restart to apply); rusty-8|standard|eti660\0";
+const SPEED_KEY: &[u8] = b"rusty8_speed\0";
+const SPEED_VALUES: &[u8] = b"Instructions per frame (fixed timing); 8|10|15|20|30|50|100|200\0";
+
+/// Callbacks handed to us by the frontend. Some are set before `retro_init`.
+///
+/// They're copied out before being called, so no lock is held while the frontend runs.
+#[derive(Clone, Copy)]
+struct Callbacks {
+    environment: Option<RetroEnvironmentFn>,
+    video_refresh: Option<RetroVideoRefreshFn>,
+    audio_sample_batch: Option<RetroAudioSampleBatchFn>,
+    input_poll: Option<RetroInputPollFn>,
+    input_state: Option<RetroInputStateFn>,
+}
+
+/// Everything that lives while a game is loaded.
+struct Core {
+    chip8: Chip8,
+    ticks_per_frame: usize,
+    beep_phase: f32,        // Position in the beep's period, between [0, 1)
+    is_halted: bool,        // ROM stopped on an error, until reset
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+
+static CORE: Mutex<Option<Core>> = Mutex::new(None);
+
+fn callbacks() -> std::sync::MutexGuard<'static, Callbacks> {
+    CALLBACKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn core() -> std::sync::MutexGuard<'static, Option<Core>> {
+    CORE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Ask the frontend for the current value of a core option.
+fn get_variable(environment: RetroEnvironmentFn, key: &[u8]) -> Option<String> {
+    let mut variable = RetroVariable { key: key.as_ptr() as *const c_char, value: ptr::null() };
+    let found = environment(RETRO_ENVIRONMENT_GET_VARIABLE, &mut variable as *mut _ as *mut c_void);
+    if !found || variable.value.is_null() {
+        return None;
+    }
+
+    let value = unsafe { CStr::from_ptr(variable.value) };
+    Some(value.to_string_lossy().into_owned())
+}
+
+/// The core options (quirks, timing and speed) the frontend has set, `None` for unset ones.
+#[derive(Default)]
+struct Options {
+    quirks: Option<Quirks>,
+    timing: Option<Timing>,
+    ticks_per_frame: Option<usize>,
+}
+
+impl Options {
+    fn read(environment: Option<RetroEnvironmentFn>) -> Options {
+        let environment = match environment {
+            Some(environment) => environment,
+            None => return Options::default(),
+        };
+
+        Options {
+            quirks: get_variable(environment, QUIRKS_KEY).and_then(|name| Quirks::from_name(&name)),
+            timing: get_variable(environment, TIMING_KEY).and_then(|name| Timing::from_name(&name)),
+            ticks_per_frame: get_variable(environment, SPEED_KEY).and_then(|ticks| ticks.parse().ok()),
+        }
+    }
+
+    /// Apply the options to a loaded game.
+    fn apply(self, core: &mut Core) {
+        if let Some(quirks) = self.quirks {
+            core.chip8.quirks = quirks;
+        }
+        if let Some(timing) = self.timing {
+            core.chip8.timing = timing;
+        }
+        if let Some(ticks) = self.ticks_per_frame {
+            core.ticks_per_frame = ticks;
+        }
+    }
+}
+
+/// Poll the frontend for the held keys, or `None` if it can't tell.
+fn poll_keyboard(callbacks: &Callbacks) -> Option<[bool; 16]> {
+    if let Some(input_poll) = callbacks.input_poll {
+        input_poll();
+    }
+    let input_state = callbacks.input_state?;
+
+    let mut keyboard = [false; 16];
+    for (key_pos, &retro_key) in CHIP8_RETRO_KEYS.iter().enumerate() {
+        keyboard[key_pos] = input_state(0, RETRO_DEVICE_KEYBOARD, 0, retro_key) != 0;
+    }
+    for &(button, key_pos) in CHIP8_JOYPAD_KEYS.iter() {
+        keyboard[key_pos] |= input_state(0, RETRO_DEVICE_JOYPAD, 0, button) != 0;
+    }
+    Some(keyboard)
+}
+
+/// One frame of interleaved stereo samples: a square wave, or silence if not beeping.
+fn render_beep(core: &mut Core, is_beeping: bool) -> Vec<i16> {
+    let mut samples = Vec::with_capacity(SAMPLES_PER_FRAME * 2);
+    for _ in 0..SAMPLES_PER_FRAME {
+        let sample = match is_beeping {
+            true if core.beep_phase < 0.5 => BEEP_AMPLITUDE,
+            true => -BEEP_AMPLITUDE,
+            false => 0,
+        };
+        samples.push(sample); // Left
+        samples.push(sample); // Right
+
+        core.beep_phase = (core.beep_phase + BEEP_FREQUENCY / SAMPLE_RATE as f32).fract();
+    }
+    samples
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(environment: RetroEnvironmentFn) {
+    callbacks().environment = Some(environment);
+
+    let mut variables = [
+        RetroVariable { key: QUIRKS_KEY.as_ptr() as *const c_char, value: QUIRKS_VALUES.as_ptr() as *const c_char },
+        RetroVariable { key: TIMING_KEY.as_ptr() as *const c_char, value: TIMING_VALUES.as_ptr() as *const c_char },
+        RetroVariable { key: MEMORY_MAP_KEY.as_ptr() as *const c_char, value: MEMORY_MAP_VALUES.as_ptr() as *const c_char },
+        RetroVariable { key: SPEED_KEY.as_ptr() as *const c_char, value: SPEED_VALUES.as_ptr() as *const c_char },
+        RetroVariable { key: ptr::null(), value: ptr::null() },
+    ];
+    environment(RETRO_ENVIRONMENT_SET_VARIABLES, variables.as_mut_ptr() as *mut c_void);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(video_refresh: RetroVideoRefreshFn) {
+    callbacks().video_refresh = Some(video_refresh);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_audio_sample: RetroAudioSampleFn) {
+    // Unused: audio is sent a whole frame at a time with `retro_set_audio_sample_batch`
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(audio_sample_batch: RetroAudioSampleBatchFn) {
+    callbacks().audio_sample_batch = Some(audio_sample_batch);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(input_poll: RetroInputPollFn) {
+    callbacks().input_poll = Some(input_poll);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(input_state: RetroInputStateFn) {
+    callbacks().input_state = Some(input_state);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *core() = None;
+}
+
+/// # Safety
+/// `info` must point to a valid, writable `retro_system_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    *info = RetroSystemInfo {
+        library_name: b"rusty-8\0".as_ptr() as *const c_char,
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char,
+        valid_extensions: b"ch8|c8|rom\0".as_ptr() as *const c_char,
+        need_fullpath: true,
+        block_extract: false,
+    };
+}
+
+/// # Safety
+/// `info` must point to a valid, writable `retro_system_av_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: SCREEN_WIDTH as c_uint,
+            base_height: SCREEN_HEIGHT as c_uint,
+            max_width: SCREEN_WIDTH as c_uint,
+            max_height: SCREEN_HEIGHT as c_uint,
+            aspect_ratio: SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32,
+        },
+        timing: RetroSystemTiming { fps: FPS, sample_rate: SAMPLE_RATE },
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(core) = core().as_mut() {
+        core.chip8.reboot();
+        core.is_halted = false;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    // The frontend may call back into the core, so it's only called with no lock held
+    let callbacks = *callbacks();
+    if core().is_none() {
+        return;
+    }
+
+    let mut options_changed = false;
+    if let Some(environment) = callbacks.environment {
+        environment(RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE, &mut options_changed as *mut bool as *mut c_void);
+    }
+    let options = match options_changed {
+        true => Options::read(callbacks.environment),
+        false => Options::default(),
+    };
+    let keyboard = poll_keyboard(&callbacks);
+
+    let (framebuffer, samples) = {
+        let mut core = core();
+        let core = match core.as_mut() {
+            Some(core) => core,
+            None => return,
+        };
+        run_frame(core, options, keyboard)
+    };
+
+    if let Some(video_refresh) = callbacks.video_refresh {
+        let pitch = SCREEN_WIDTH * std::mem::size_of::<u32>();
+        video_refresh(framebuffer.as_ptr() as *const c_void, SCREEN_WIDTH as c_uint, SCREEN_HEIGHT as c_uint, pitch);
+    }
+    if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+        audio_sample_batch(samples.as_ptr(), SAMPLES_PER_FRAME);
+    }
+}
+
+/// Run a frame of the loaded game, returning its screen as XRGB8888 and its samples.
+fn run_frame(core: &mut Core, options: Options, keyboard: Option<[bool; 16]>) -> (Vec<u32>, Vec<i16>) {
+    options.apply(core);
+    if let Some(keyboard) = keyboard {
+        core.chip8.set_keys(&keyboard);
+    }
+
+    let is_beeping = match core.is_halted {
+        true => false,
+        false => core.chip8.run_frame(core.ticks_per_frame).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            core.is_halted = true;
+            false
+        }),
+    };
+
+    let framebuffer = core.chip8.screen.iter()
+        .map(|&value| if value != 0 { PIXEL_ON } else { PIXEL_OFF })
+        .collect();
+    (framebuffer, render_beep(core, is_beeping))
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    STATE_SIZE
+}
+
+/// # Safety
+/// `data` must point to at least `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let core = core();
+    let core = match core.as_ref() {
+        Some(core) if size >= STATE_SIZE => core,
+        _ => return false,
+    };
+
+    let state = core.chip8.save_state();
+    ptr::copy_nonoverlapping(state.as_ptr(), data as *mut u8, state.len());
+    true
+}
+
+/// # Safety
+/// `data` must point to at least `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let mut core = core();
+    let core = match core.as_mut() {
+        Some(core) => core,
+        None => return false,
+    };
+
+    let state = std::slice::from_raw_parts(data as *const u8, size);
+    core.chip8.load_state(state).is_ok()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+/// # Safety
+/// `game` must be null or point to a valid `retro_game_info` with a valid `path`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    if game.is_null() || (*game).path.is_null() {
+        return false;
+    }
+
+    let mut pixel_format = RETRO_PIXEL_FORMAT_XRGB8888;
+    let environment = callbacks().environment;
+    if let Some(environment) = environment {
+        if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, &mut pixel_format as *mut c_uint as *mut c_void) {
+            return false;
+        }
+    }
+
+    // Memory can only be laid out before the ROM is loaded
+    let memory_map = environment
+        .and_then(|environment| get_variable(environment, MEMORY_MAP_KEY))
+        .and_then(|name| MemoryMap::from_name(&name))
+        .unwrap_or_default();
+
+    let path = CStr::from_ptr((*game).path).to_string_lossy().into_owned();
+    let mut chip8 = Chip8::new();
+    if let Err(e) = chip8.set_memory_map(memory_map) {
+        eprintln!("{}", e);
+        return false;
+    }
+    if let Err(e) = chip8.load_rom(&path) {
+        eprintln!("Failure during ROM open/read\n{}", e);
+        return false;
+    }
+
+    let mut core = Core {
+        chip8,
+        ticks_per_frame: DEFAULT_TICKS_PER_FRAME,
+        beep_phase: 0.0,
+        is_halted: false,
+    };
+    Options::read(environment).apply(&mut core);
+    *self::core() = Some(core);
+
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_game_type: c_uint, _info: *const RetroGameInfo, _num_info: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *core() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(id: c_uint) -> *mut c_void {
+    match core().as_mut() {
+        Some(core) if id == RETRO_MEMORY_SYSTEM_RAM => core.chip8.memory_mut().as_mut_ptr() as *mut c_void,
+        _ => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(id: c_uint) -> usize {
+    match core().as_ref() {
+        Some(core) if id == RETRO_MEMORY_SYSTEM_RAM => core.chip8.memory().len(),
+        _ => 0,
+    }
+}
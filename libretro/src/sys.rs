@@ -0,0 +1,107 @@
+// The subset of libretro.h this core needs.
+// Reference -> https://github.com/libretro/libretro-common/blob/master/include/libretro.h
+#![allow(dead_code)]
+
+use std::os::raw::{c_char, c_uint, c_void};
+
+pub const RETRO_API_VERSION: c_uint = 1;
+
+pub const RETRO_DEVICE_JOYPAD:   c_uint = 1;
+pub const RETRO_DEVICE_KEYBOARD: c_uint = 3;
+
+pub const RETRO_DEVICE_ID_JOYPAD_B:      c_uint = 0;
+pub const RETRO_DEVICE_ID_JOYPAD_Y:      c_uint = 1;
+pub const RETRO_DEVICE_ID_JOYPAD_SELECT: c_uint = 2;
+pub const RETRO_DEVICE_ID_JOYPAD_START:  c_uint = 3;
+pub const RETRO_DEVICE_ID_JOYPAD_UP:     c_uint = 4;
+pub const RETRO_DEVICE_ID_JOYPAD_DOWN:   c_uint = 5;
+pub const RETRO_DEVICE_ID_JOYPAD_LEFT:   c_uint = 6;
+pub const RETRO_DEVICE_ID_JOYPAD_RIGHT:  c_uint = 7;
+pub const RETRO_DEVICE_ID_JOYPAD_A:      c_uint = 8;
+pub const RETRO_DEVICE_ID_JOYPAD_X:      c_uint = 9;
+pub const RETRO_DEVICE_ID_JOYPAD_L:      c_uint = 10;
+pub const RETRO_DEVICE_ID_JOYPAD_R:      c_uint = 11;
+pub const RETRO_DEVICE_ID_JOYPAD_L2:     c_uint = 12;
+pub const RETRO_DEVICE_ID_JOYPAD_R2:     c_uint = 13;
+pub const RETRO_DEVICE_ID_JOYPAD_L3:     c_uint = 14;
+pub const RETRO_DEVICE_ID_JOYPAD_R3:     c_uint = 15;
+
+// RETROK_* key codes are plain ASCII for letters and digits
+pub const RETROK_1: c_uint = b'1' as c_uint;
+pub const RETROK_2: c_uint = b'2' as c_uint;
+pub const RETROK_3: c_uint = b'3' as c_uint;
+pub const RETROK_4: c_uint = b'4' as c_uint;
+pub const RETROK_Q: c_uint = b'q' as c_uint;
+pub const RETROK_W: c_uint = b'w' as c_uint;
+pub const RETROK_E: c_uint = b'e' as c_uint;
+pub const RETROK_R: c_uint = b'r' as c_uint;
+pub const RETROK_A: c_uint = b'a' as c_uint;
+pub const RETROK_S: c_uint = b's' as c_uint;
+pub const RETROK_D: c_uint = b'd' as c_uint;
+pub const RETROK_F: c_uint = b'f' as c_uint;
+pub const RETROK_Z: c_uint = b'z' as c_uint;
+pub const RETROK_X: c_uint = b'x' as c_uint;
+pub const RETROK_C: c_uint = b'c' as c_uint;
+pub const RETROK_V: c_uint = b'v' as c_uint;
+
+pub const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT:   c_uint = 10;
+pub const RETRO_ENVIRONMENT_GET_VARIABLE:       c_uint = 15;
+pub const RETRO_ENVIRONMENT_SET_VARIABLES:      c_uint = 16;
+pub const RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE: c_uint = 17;
+
+pub const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+
+pub const RETRO_MEMORY_SYSTEM_RAM: c_uint = 2;
+
+pub const RETRO_REGION_NTSC: c_uint = 0;
+
+pub type RetroEnvironmentFn = extern "C" fn(cmd: c_uint, data: *mut c_void) -> bool;
+pub type RetroVideoRefreshFn = extern "C" fn(data: *const c_void, width: c_uint, height: c_uint, pitch: usize);
+pub type RetroAudioSampleFn = extern "C" fn(left: i16, right: i16);
+pub type RetroAudioSampleBatchFn = extern "C" fn(data: *const i16, frames: usize) -> usize;
+pub type RetroInputPollFn = extern "C" fn();
+pub type RetroInputStateFn = extern "C" fn(port: c_uint, device: c_uint, index: c_uint, id: c_uint) -> i16;
+
+#[repr(C)]
+pub struct RetroSystemInfo {
+    pub library_name: *const c_char,
+    pub library_version: *const c_char,
+    pub valid_extensions: *const c_char,
+    pub need_fullpath: bool,
+    pub block_extract: bool,
+}
+
+#[repr(C)]
+pub struct RetroGameGeometry {
+    pub base_width: c_uint,
+    pub base_height: c_uint,
+    pub max_width: c_uint,
+    pub max_height: c_uint,
+    pub aspect_ratio: f32,
+}
+
+#[repr(C)]
+pub struct RetroSystemTiming {
+    pub fps: f64,
+    pub sample_rate: f64,
+}
+
+#[repr(C)]
+pub struct RetroSystemAvInfo {
+    pub geometry: RetroGameGeometry,
+    pub timing: RetroSystemTiming,
+}
+
+#[repr(C)]
+pub struct RetroGameInfo {
+    pub path: *const c_char,
+    pub data: *const c_void,
+    pub size: usize,
+    pub meta: *const c_char,
+}
+
+#[repr(C)]
+pub struct RetroVariable {
+    pub key: *const c_char,
+    pub value: *const c_char,
+}
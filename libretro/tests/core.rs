@@ -0,0 +1,109 @@
+// The core through its exported functions, as a frontend drives it: a frame's video and audio, and save states.
+
+use std::ffi::CString;
+use std::os::raw::{c_uint, c_void};
+use std::path::Path;
+use std::ptr;
+use std::sync::Mutex;
+
+use rusty8_libretro::*;
+use rusty8_libretro::sys::*;
+
+// 6010  V0 = 0x10
+// F018  ST = V0
+// F129  I = sprite of the digit in V1, a 0
+// D225  Draw it at (0, 0)
+// 7301  V3 += 1
+// 1208  Loop
+const ROM: [u8; 12] = [0x60, 0x10, 0xF0, 0x18, 0xF1, 0x29, 0xD2, 0x25, 0x73, 0x01, 0x12, 0x08];
+
+/// A screen as the frontend was handed it.
+struct Video {
+    pixels: Vec<u32>,
+    width: c_uint,
+    height: c_uint,
+    pitch: usize,
+}
+
+/// What the frontend was last handed.
+static VIDEO: Mutex<Option<Video>> = Mutex::new(None);
+static AUDIO: Mutex<Vec<i16>> = Mutex::new(Vec::new());
+
+extern "C" fn environment(cmd: c_uint, _data: *mut c_void) -> bool {
+    // Options are left unset, at their defaults
+    matches!(cmd, RETRO_ENVIRONMENT_SET_PIXEL_FORMAT | RETRO_ENVIRONMENT_SET_VARIABLES)
+}
+
+extern "C" fn video_refresh(data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    // Calling back into the core would deadlock if it was still locked
+    assert_eq!(retro_get_memory_size(RETRO_MEMORY_SYSTEM_RAM), 4096);
+
+    let pixels = unsafe { std::slice::from_raw_parts(data as *const u32, (height as usize) * pitch / 4) };
+    *VIDEO.lock().unwrap() = Some(Video { pixels: pixels.to_vec(), width, height, pitch });
+}
+
+extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    assert_eq!(retro_get_memory_size(RETRO_MEMORY_SYSTEM_RAM), 4096);
+
+    let samples = unsafe { std::slice::from_raw_parts(data, frames * 2) };
+    *AUDIO.lock().unwrap() = samples.to_vec();
+    frames
+}
+
+fn serialize() -> Vec<u8> {
+    let mut state = vec![0; retro_serialize_size()];
+    assert!(unsafe { retro_serialize(state.as_mut_ptr() as *mut c_void, state.len()) });
+    state
+}
+
+// The core is global, so everything runs in a single test
+#[test]
+fn runs_frames_and_save_states() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("libretro.ch8");
+    std::fs::write(&path, ROM).unwrap();
+    let path = CString::new(path.to_str().unwrap()).unwrap();
+
+    retro_set_environment(environment);
+    retro_set_video_refresh(video_refresh);
+    retro_set_audio_sample_batch(audio_sample_batch);
+    retro_init();
+    let game = RetroGameInfo { path: path.as_ptr(), data: ptr::null(), size: 0, meta: ptr::null() };
+    assert!(unsafe { retro_load_game(&game) });
+
+    retro_run();
+    let Video { pixels, width, height, pitch } = VIDEO.lock().unwrap().take().expect("no video");
+    assert_eq!((width, height, pitch, pixels.len()), (64, 32, 64 * 4, 64 * 32));
+    // The top row of the 0 is lit, and nothing after it
+    assert_eq!(&pixels[..5], &[0x00FF_FFFF, 0x00FF_FFFF, 0x00FF_FFFF, 0x00FF_FFFF, 0]);
+    assert_eq!(pixels.iter().filter(|&&pixel| pixel != 0).count(), 14);
+
+    // A frame of 44.1kHz stereo, beeping as ST is set
+    let samples = std::mem::take(&mut *AUDIO.lock().unwrap());
+    assert_eq!(samples.len(), 735 * 2);
+    assert!(samples.iter().any(|&sample| sample != 0));
+    assert!(samples.chunks(2).all(|pair| pair[0] == pair[1]));
+
+    // Save, run on, and go back
+    let saved = serialize();
+    retro_run();
+    let next_frame = serialize();
+    retro_run();
+    assert_ne!(serialize(), saved);
+
+    assert!(unsafe { retro_unserialize(saved.as_ptr() as *const c_void, saved.len()) });
+    assert_eq!(serialize(), saved);
+    retro_run();
+    assert_eq!(serialize(), next_frame);
+
+    // Too small to save to, or not a state to load
+    let mut small = vec![0u8; retro_serialize_size() - 1];
+    assert!(!unsafe { retro_serialize(small.as_mut_ptr() as *mut c_void, small.len()) });
+    assert!(!unsafe { retro_unserialize(small.as_ptr() as *const c_void, small.len()) });
+    assert_eq!(serialize(), next_frame);
+
+    // Nor without a game
+    retro_unload_game();
+    let mut state = vec![0u8; retro_serialize_size()];
+    assert!(!unsafe { retro_serialize(state.as_mut_ptr() as *mut c_void, state.len()) });
+    retro_deinit();
+}
@@ -0,0 +1,154 @@
+//! Python bindings for the rusty-8 interpreter core, so analysis tooling can drive a `Chip8`.
+//!
+//! ```python
+//! import numpy as np
+//! import rusty8
+//!
+//! chip8 = rusty8.Chip8(quirks="vip")
+//! chip8.load_rom(open("roms/BRIX", "rb").read())
+//! for _ in range(60):
+//!     chip8.run_frame()
+//! screen = np.frombuffer(chip8.screen(), dtype=np.uint8).reshape(rusty8.SCREEN_HEIGHT, rusty8.SCREEN_WIDTH)
+//! ```
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use rusty_8::{Chip8, Quirks, Timing};
+use rusty_8::chip8::{SCREEN_WIDTH, SCREEN_HEIGHT};
+
+/// A CHIP-8 interpreter. Errors the ROM runs into raise RuntimeError.
+#[pyclass(name = "Chip8")]
+struct PyChip8 {
+    chip8: Chip8,
+}
+
+#[pymethods]
+impl PyChip8 {
+    /// Quirks preset: rusty-8, vip or chip48. Timing model: fixed or vip.
+    #[new]
+    #[pyo3(signature = (quirks = "rusty-8", timing = "fixed"))]
+    fn new(quirks: &str, timing: &str) -> PyResult<Self> {
+        let quirks = Quirks::from_name(quirks).ok_or_else(|| PyValueError::new_err(format!("Unknown quirks preset '{}'", quirks)))?;
+        let mut chip8 = Chip8::with_quirks(quirks);
+        chip8.timing = Timing::from_name(timing).ok_or_else(|| PyValueError::new_err(format!("Unknown timing model '{}'", timing)))?;
+
+        Ok(PyChip8 { chip8 })
+    }
+
+    /// Copy a ROM into memory, where programs start. Raises ValueError if it's empty or doesn't fit.
+    fn load_rom(&mut self, rom: &[u8]) -> PyResult<()> {
+        self.chip8.load_rom_bytes(rom).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Restart the loaded ROM, from a cleared machine.
+    fn reboot(&mut self) {
+        self.chip8.reboot();
+    }
+
+    /// Run a single clock cycle.
+    fn tick(&mut self) -> PyResult<()> {
+        self.chip8.tick().map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    /// Run a 60Hz frame: `ticks` clock cycles (with fixed timing), then the timers.
+    /// Returns whether the beep sounds.
+    #[pyo3(signature = (ticks = 8))]
+    fn run_frame(&mut self, ticks: usize) -> PyResult<bool> {
+        self.chip8.run_frame(ticks).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    /// Set the state of all 16 keys at once, key 0 first.
+    fn set_keys(&mut self, keys: [bool; 16]) {
+        self.chip8.set_keys(&keys);
+    }
+
+    fn press_key(&mut self, key: u8) -> PyResult<()> {
+        self.chip8.press_key(key_index(key)?);
+        Ok(())
+    }
+
+    fn release_key(&mut self, key: u8) -> PyResult<()> {
+        self.chip8.release_key(key_index(key)?);
+        Ok(())
+    }
+
+    /// The screen, a byte per pixel (0 or 255), SCREEN_WIDTH pixels per row.
+    fn screen<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.chip8.screen)
+    }
+
+    /// The whole 4Kb of memory.
+    fn memory<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.chip8.memory())
+    }
+
+    /// The machine state, to restore with load_state.
+    fn save_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.chip8.save_state())
+    }
+
+    /// Restore a state from save_state. Raises ValueError if it isn't one.
+    fn load_state(&mut self, state: &[u8]) -> PyResult<()> {
+        self.chip8.load_state(state).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn v(&self) -> [u8; 16] {
+        self.chip8.registers().v
+    }
+
+    #[getter]
+    fn i(&self) -> u16 {
+        self.chip8.registers().i
+    }
+
+    #[getter]
+    fn pc(&self) -> u16 {
+        self.chip8.registers().pc
+    }
+
+    /// Stack depth, in return addresses.
+    #[getter]
+    fn sp(&self) -> usize {
+        self.chip8.stack_depth()
+    }
+
+    #[getter]
+    fn dt(&self) -> u8 {
+        self.chip8.registers().dt
+    }
+
+    #[getter]
+    fn st(&self) -> u8 {
+        self.chip8.registers().st
+    }
+
+    /// Clock cycles since boot.
+    #[getter]
+    fn cycles(&self) -> u64 {
+        self.chip8.cycles()
+    }
+
+    /// Whether the program ended, by running into empty memory.
+    #[getter]
+    fn finished(&self) -> bool {
+        self.chip8.finished_running()
+    }
+}
+
+fn key_index(key: u8) -> PyResult<u8> {
+    if key > 0xF {
+        return Err(PyValueError::new_err(format!("No key {}, keys go from 0 to 0xF", key)));
+    }
+    Ok(key)
+}
+
+#[pymodule]
+fn rusty8(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyChip8>()?;
+    module.add("SCREEN_WIDTH", SCREEN_WIDTH)?;
+    module.add("SCREEN_HEIGHT", SCREEN_HEIGHT)?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+import pathlib
+
+import pytest
+
+import rusty8
+
+ROMS = pathlib.Path(__file__).resolve().parents[2] / "roms"
+
+# Draw the font's 5 at the top-left corner, then loop forever
+DRAW_FIVE = bytes([
+    0x60, 0x05,  # V0 = 5
+    0xF0, 0x29,  # I = font sprite of V0
+    0x61, 0x00,  # V1 = 0
+    0xD1, 0x15,  # Draw 5 rows at (V1, V1)
+    0x12, 0x08,  # Jump to itself
+])
+
+# Wait for a key into V3, then loop forever
+WAIT_KEY = bytes([
+    0xF3, 0x0A,  # V3 = next key
+    0x12, 0x02,  # Jump to itself
+])
+
+
+def booted(rom, **kwargs):
+    chip8 = rusty8.Chip8(**kwargs)
+    chip8.load_rom(rom)
+    return chip8
+
+
+def test_runs_rom_from_bytes():
+    chip8 = booted(DRAW_FIVE)
+    chip8.run_frame()
+
+    screen = chip8.screen()
+    assert len(screen) == rusty8.SCREEN_WIDTH * rusty8.SCREEN_HEIGHT
+    # The 5's top row is 4 lit pixels, its second row only the first
+    assert screen[0:5] == bytes([255, 255, 255, 255, 0])
+    assert screen[rusty8.SCREEN_WIDTH:rusty8.SCREEN_WIDTH + 2] == bytes([255, 0])
+    assert sum(screen) == 255 * 14
+    assert chip8.pc == 0x208
+    assert chip8.v[0] == 5
+
+
+def test_ticks_one_instruction_at_a_time():
+    chip8 = booted(DRAW_FIVE)
+    chip8.tick()
+    chip8.tick()
+
+    assert chip8.pc == 0x204
+    assert chip8.cycles == 2
+    assert not any(chip8.screen())
+
+
+def test_screen_is_numpy_compatible():
+    np = pytest.importorskip("numpy")
+    chip8 = booted(DRAW_FIVE)
+    chip8.run_frame()
+
+    screen = np.frombuffer(chip8.screen(), dtype=np.uint8).reshape(rusty8.SCREEN_HEIGHT, rusty8.SCREEN_WIDTH)
+    assert screen[0, :4].tolist() == [255] * 4
+    assert screen[4, :4].tolist() == [255] * 4
+    assert screen[1, 3] == 0
+
+
+def test_keys():
+    chip8 = booted(WAIT_KEY)
+    for _ in range(3):
+        chip8.run_frame()
+    assert chip8.v[3] == 0
+
+    chip8.press_key(7)
+    chip8.run_frame()
+    chip8.release_key(7)
+    chip8.run_frame()
+    assert chip8.v[3] == 7
+
+    chip8.reboot()
+    chip8.run_frame()  # Keys already down as the wait starts don't count
+    keys = [False] * 16
+    keys[0xA] = True
+    chip8.set_keys(keys)
+    chip8.run_frame()
+    chip8.set_keys([False] * 16)
+    chip8.run_frame()
+    assert chip8.v[3] == 0xA
+
+
+def test_save_and_load_state():
+    chip8 = booted(DRAW_FIVE)
+    state = chip8.save_state()
+    chip8.run_frame()
+    drawn = chip8.screen()
+
+    chip8.load_state(state)
+    assert chip8.pc == 0x200
+    assert not any(chip8.screen())
+
+    chip8.run_frame()
+    assert chip8.screen() == drawn
+
+
+def test_memory():
+    memory = booted(DRAW_FIVE).memory()
+    assert len(memory) == 4096
+    assert memory[0x200:0x200 + len(DRAW_FIVE)] == DRAW_FIVE
+
+
+def test_finishes_on_empty_memory():
+    chip8 = booted(bytes([0x60, 0x01]))
+    chip8.run_frame()
+    assert chip8.finished
+    assert chip8.pc == 0x202
+
+
+def test_errors():
+    with pytest.raises(ValueError):
+        rusty8.Chip8(quirks="nope")
+    with pytest.raises(ValueError):
+        rusty8.Chip8(timing="nope")
+
+    chip8 = booted(bytes([0x00, 0xEE]))  # RET with an empty stack
+    with pytest.raises(RuntimeError, match="Stack underflow"):
+        chip8.tick()
+
+    with pytest.raises(ValueError):
+        chip8.press_key(16)
+    with pytest.raises(ValueError):
+        chip8.load_state(b"not a state")
+    with pytest.raises(ValueError, match="too large"):
+        chip8.load_rom(bytes(4096))
+    with pytest.raises(ValueError, match="empty"):
+        chip8.load_rom(b"")
+
+
+@pytest.mark.skipif(not (ROMS / "BRIX").exists(), reason="needs the repository's roms")
+def test_plays_brix():
+    chip8 = booted((ROMS / "BRIX").read_bytes(), quirks="vip", timing="vip")
+    for _ in range(120):
+        chip8.run_frame()
+
+    assert any(chip8.screen())
+    assert not chip8.finished
@@ -0,0 +1,302 @@
+use rodio::{OutputStream, Sink, Source};
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use rusty_8::{Chip8, TickObserver, Timing};
+
+const SAMPLE_RATE: u32 = 44100;
+const RAMP_SAMPLES: f32 = 44.0;     // ~1ms fade when the tone is gated on/off, avoids clicks
+const FRAME_SAMPLES: u64 = SAMPLE_RATE as u64 / 60;     // Samples per 60Hz chip8 frame
+const FRAME_LEAD: u64 = 2;          // Frames queued ahead of the samples generated, to absorb jitter
+const MAX_FRAME_LEAD: u64 = 6;      // Queued further ahead than this, frames start over from FRAME_LEAD
+
+/// Where the sound timer starts or stops within a frame: the fraction of the frame elapsed, between
+/// [0, 1], and wether the tone sounds from there on.
+pub type GateEdge = (f32, bool);
+
+/// Somewhere the chip8 beep can be played.
+pub trait AudioBackend {
+    /// Open (or close) the beep's gate right away, dropping the edges queued by `play_frame`,
+    /// e.g. when the chip8 pauses or reboots.
+    fn set_gate(&self, is_open: bool);
+
+    /// Gate the beep like the sound timer did during a frame just run, on the samples its edges fall on.
+    fn play_frame(&self, edges: &[GateEdge]);
+
+    /// Toggle muting, returns true if the beep is now muted.
+    fn toggle_mute(&self) -> bool;
+}
+
+/// Pick an audio backend: rodio on the default output device, or silence if `no_audio`
+/// is set or there's no usable device (containers, CI, headless servers...).
+pub fn open_backend(no_audio: bool, waveform: Waveform, frequency: f32, volume: f32) -> Box<dyn AudioBackend> {
+    if no_audio {
+        return Box::new(NullBackend);
+    }
+
+    match RodioBackend::new(waveform, frequency, volume) {
+        Ok(backend) => Box::new(backend),
+        Err(e) => {
+            eprintln!("Warning: no audio device available, running without sound ({})", e);
+            Box::new(NullBackend)
+        }
+    }
+}
+
+/// Plays a gated `Tone` on the default output device.
+pub struct RodioBackend {
+    _stream: OutputStream,  // Audio stops when the stream is dropped
+    _sink: Sink,
+    control: ToneControl,
+}
+
+impl RodioBackend {
+    pub fn new(waveform: Waveform, frequency: f32, volume: f32) -> Result<Self, String> {
+        let (stream, handle) = OutputStream::try_default().map_err(|e| e.to_string())?;
+        let sink = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+
+        let (tone, control) = Tone::new(waveform, frequency, volume);
+        sink.append(tone);
+
+        Ok(RodioBackend { _stream: stream, _sink: sink, control })
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn set_gate(&self, is_open: bool) {
+        self.control.set_gate(is_open);
+    }
+
+    fn play_frame(&self, edges: &[GateEdge]) {
+        self.control.play_frame(edges);
+    }
+
+    fn toggle_mute(&self) -> bool {
+        self.control.toggle_mute()
+    }
+}
+
+/// Silent backend, for machines without sound.
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn set_gate(&self, _is_open: bool) {}
+
+    fn play_frame(&self, _edges: &[GateEdge]) {}
+
+    fn toggle_mute(&self) -> bool {
+        true // Can't be anything but muted
+    }
+}
+
+/// Shape of the generated tone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+}
+
+impl FromStr for Waveform {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "square" => Ok(Waveform::Square),
+            "sine" => Ok(Waveform::Sine),
+            _ => Err(format!("Unknown waveform '{}', expected 'square' or 'sine'", name))
+        }
+    }
+}
+
+/// Watches the sound timer tick by tick while frames run, for the tone to start and stop where it
+/// changes within the frame, rather than on whole frames.
+pub struct SoundTimerObserver {
+    ticks_per_frame: usize,     // Frame length in ticks, with fixed timing
+    ticks: usize,               // Ticks run this frame
+    is_active: bool,            // Wether the sound timer was active after the last tick
+    tick_edges: Vec<(usize, bool)>, // Tick the sound timer started or stopped after, this frame
+    edges: Vec<GateEdge>,       // Edges of the last frame run
+}
+
+impl SoundTimerObserver {
+    pub fn new(ticks_per_frame: usize) -> Self {
+        SoundTimerObserver { ticks_per_frame, ticks: 0, is_active: false, tick_edges: Vec::new(), edges: Vec::new() }
+    }
+
+    /// Where the sound timer started or stopped during the last frame run, for `AudioBackend::play_frame`.
+    pub fn edges(&self) -> &[GateEdge] {
+        &self.edges
+    }
+
+    fn update(&mut self, chip8: &Chip8) {
+        let is_active = chip8.registers().st > 0;
+        if is_active != self.is_active {
+            self.is_active = is_active;
+            self.tick_edges.push((self.ticks, is_active));
+        }
+    }
+}
+
+impl TickObserver for SoundTimerObserver {
+    fn before_frame(&mut self, chip8: &mut Chip8) {
+        // Frames start with the state they start in, in case the gate was set since
+        self.ticks = 0;
+        self.is_active = chip8.registers().st > 0;
+        self.tick_edges.clear();
+        self.tick_edges.push((0, self.is_active));
+    }
+
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        self.ticks += 1;
+        self.update(chip8);
+    }
+
+    fn after_frame(&mut self, chip8: &mut Chip8) {
+        // With fixed timing, a frame cut short (e.g. by a key wait) idles for the ticks it didn't run
+        let frame_ticks = match chip8.timing {
+            Timing::Fixed => self.ticks.max(self.ticks_per_frame),
+            Timing::CosmacVip => self.ticks,
+        }.max(1);
+
+        self.edges = self.tick_edges.iter()
+            .map(|&(tick, is_active)| (tick as f32 / frame_ticks as f32, is_active))
+            .collect();
+
+        // The timers tick once the frame is over
+        let is_active = chip8.registers().st > 0;
+        if is_active != self.is_active {
+            self.is_active = is_active;
+            self.edges.push((1.0, is_active));
+        }
+    }
+}
+
+/// Handle the event loop uses to control a playing `Tone`.
+#[derive(Clone, Default)]
+pub struct ToneControl {
+    gate: Arc<AtomicBool>,  // Tone is audible while the chip8 sound timer is active
+    muted: Arc<AtomicBool>,
+    edges: Arc<Mutex<VecDeque<(u64, bool)>>>,   // Gate changes to come, and the sample they fall on
+    generated: Arc<AtomicU64>,      // Samples the tone generated so far
+    frame_start: Arc<AtomicU64>,    // Sample the next frame played starts on
+}
+
+impl ToneControl {
+    /// Open (or close) the gate right away, dropping the edges queued.
+    pub fn set_gate(&self, is_open: bool) {
+        self.edges.lock().unwrap().clear();
+        self.gate.store(is_open, Ordering::Relaxed);
+    }
+
+    /// Queue the gate edges of a frame, each on the sample it falls on. Frames are laid back to back,
+    /// a little ahead of the samples generated: the audio device pulls samples in bursts, and frames
+    /// don't run exactly every 1/60s. Frames start over from there when they fall behind or run too far ahead.
+    pub fn play_frame(&self, edges: &[GateEdge]) {
+        let mut queue = self.edges.lock().unwrap();
+        let generated = self.generated.load(Ordering::Relaxed);
+        let mut frame_start = self.frame_start.load(Ordering::Relaxed);
+        if frame_start < generated || frame_start > generated + MAX_FRAME_LEAD * FRAME_SAMPLES {
+            frame_start = generated + FRAME_LEAD * FRAME_SAMPLES;
+            // Edges from there on were queued by frames this one starts over from, and would come before its own
+            queue.retain(|&(at, _)| at < frame_start);
+        }
+
+        for &(fraction, is_open) in edges {
+            let offset = (fraction.clamp(0.0, 1.0) * FRAME_SAMPLES as f32) as u64;
+            queue.push_back((frame_start + offset, is_open));
+        }
+        self.frame_start.store(frame_start + FRAME_SAMPLES, Ordering::Relaxed);
+    }
+
+    /// Toggle muting, returns true if the tone is now muted.
+    pub fn toggle_mute(&self) -> bool {
+        !self.muted.fetch_xor(true, Ordering::Relaxed)
+    }
+}
+
+/// An endless tone whose amplitude is gated by the chip8 sound timer.
+/// Unlike appending short clips, the wave never stops, so there are no gaps or phase jumps.
+pub struct Tone {
+    waveform: Waveform,
+    frequency: f32,     // Hz
+    volume: f32,        // Peak amplitude, between [0, 1]
+    phase: f32,         // Position in the wave's period, between [0, 1)
+    level: f32,         // Current amplitude, ramps towards `volume` or 0
+    control: ToneControl,
+}
+
+impl Tone {
+    pub fn new(waveform: Waveform, frequency: f32, volume: f32) -> (Tone, ToneControl) {
+        let control = ToneControl::default();
+        let tone = Tone {
+            waveform,
+            frequency,
+            volume: volume.clamp(0.0, 1.0),
+            phase: 0.0,
+            level: 0.0,
+            control: control.clone(),
+        };
+
+        (tone, control)
+    }
+}
+
+impl Iterator for Tone {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Apply the gate edges falling on this sample. If the event loop is queuing some, they'll
+        // be a sample late rather than blocking the audio thread
+        let sample = self.control.generated.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut edges) = self.control.edges.try_lock() {
+            while let Some(&(at, is_open)) = edges.front() {
+                if at > sample {
+                    break;
+                }
+                self.control.gate.store(is_open, Ordering::Relaxed);
+                edges.pop_front();
+            }
+        }
+
+        let is_audible = self.control.gate.load(Ordering::Relaxed) && !self.control.muted.load(Ordering::Relaxed);
+        let target = if is_audible { self.volume } else { 0.0 };
+
+        // Ramp the amplitude instead of jumping to it
+        let step = self.volume / RAMP_SAMPLES;
+        self.level = if self.level < target {
+            (self.level + step).min(target)
+        } else {
+            (self.level - step).max(target)
+        };
+
+        let wave = match self.waveform {
+            Waveform::Square => if self.phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (self.phase * TAU).sin(),
+        };
+        self.phase = (self.phase + self.frequency / SAMPLE_RATE as f32).fract();
+
+        Some(wave * self.level)
+    }
+}
+
+impl Source for Tone {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
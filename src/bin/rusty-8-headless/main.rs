@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use rusty_8::profiler::REPORT_TOP_ADDRESSES;
+use rusty_8::{Chip8, Coverage, DiffTester, ExitReason, InputScript, Profiler, Tracer, Watchdog};
+use rusty_8::rom;
+use rusty_8::wav::WavRecorder;
+#[cfg(feature = "scripting")]
+use rusty_8::Script;
+
+mod options;
+
+use options::Options;
+
+fn main() {
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        },
+    };
+
+    let mut chip8 = Chip8::with_quirks(options.quirks);
+    chip8.timing = options.timing;
+    chip8.stack_config = options.stack_config;
+    chip8.set_memory_map(options.memory_map).expect("memory map is validated with the options");
+    if let Err(e) = rom::read_rom_interactive(&options.rom, options.memory_map.rom_capacity()).and_then(|(rom, _)| chip8.load_rom_bytes(&rom)) {
+        eprintln!("Failure during ROM open/read\n{}", e);
+        std::process::exit(1);
+    }
+
+    let mut recorder = options.wav.as_ref()
+        .map(|_| WavRecorder::new(options.sample_rate, options.tone_frequency));
+
+    let mut tracer = match &options.trace {
+        Some(path) => match Tracer::create(path, options.trace_format, options.trace_filter) {
+            Ok(tracer) => Some(tracer),
+            Err(e) => {
+                eprintln!("Failure creating {}\n{}", path, e);
+                std::process::exit(1);
+            },
+        },
+        None => None,
+    };
+
+    let input = options.input.as_ref().map(|path| InputScript::load(path).unwrap_or_else(|e| {
+        eprintln!("Failure reading input script {}\n{}", path, e);
+        std::process::exit(1);
+    }));
+    let mut differ = options.reference.as_ref().map(|path| DiffTester::load(path).unwrap_or_else(|e| {
+        eprintln!("Failure reading reference trace {}\n{}", path, e);
+        std::process::exit(1);
+    }));
+
+    #[cfg(feature = "scripting")]
+    let mut script = options.script.as_ref().map(|path| Script::load(path).unwrap_or_else(|e| {
+        eprintln!("Failure reading script {}\n{}", path, e);
+        std::process::exit(1);
+    }));
+    #[cfg(not(feature = "scripting"))]
+    let mut script: Option<()> = None;  // --script is refused without the scripting feature
+
+    let mut profiler = options.profile.as_ref().map(|_| Profiler::new());
+    let mut coverage = (options.coverage.is_some() || options.coverage_report.is_some()).then(|| Coverage::for_rom(&chip8.memory()[chip8.rom_range()]));
+    let mut watchdog = Watchdog::new(options.limits);
+    let exit_reason = loop {
+        if let Some(input) = &input {
+            input.apply(watchdog.frames(), &mut chip8);
+        }
+
+        let mut observers = (&mut tracer, &mut differ, &mut profiler, &mut coverage, &mut script);
+        let is_beeping = match chip8.run_frame_observed(options.ticks_per_frame, &mut observers) {
+            Ok(is_beeping) => is_beeping,
+            Err(e) => break ExitReason::Error(e),
+        };
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record_frame(is_beeping);
+        }
+
+        let watchdog_reason = watchdog.check(&chip8);
+        #[cfg(feature = "scripting")]
+        if let Some(script) = script.as_ref() {
+            if let Some(e) = script.error() {
+                break ExitReason::ScriptFailed(e.to_string());
+            }
+            if script.is_stopped() {
+                break ExitReason::Stopped;
+            }
+        }
+
+        if let (Some(differ), Some(ExitReason::Halted | ExitReason::SelfLoop { .. })) = (differ.as_mut(), &watchdog_reason) {
+            differ.stopped(&chip8);
+        }
+        if let Some(differ) = differ.as_ref().filter(|differ| differ.is_done()) {
+            break match differ.divergence() {
+                Some(divergence) => {
+                    print!("{}", divergence);
+                    ExitReason::Diverged { step: divergence.step }
+                },
+                None => ExitReason::Matched { steps: differ.steps() },
+            };
+        }
+
+        if let Some(exit_reason) = watchdog_reason {
+            break exit_reason;
+        }
+    };
+
+    if let (Some(path), Some(recorder)) = (&options.wav, &recorder) {
+        for (start, len) in recorder.beeps() {
+            println!("beep at frame {} for {} frames", start, len);
+        }
+
+        if let Err(e) = recorder.save(path) {
+            eprintln!("Failure writing {}\n{}", path, e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(coverage) = coverage.as_mut() {
+        if let Some(path) = options.coverage.as_ref() {
+            // Merge with earlier runs, if any
+            if Path::new(path).exists() {
+                if let Err(e) = Coverage::load(path).and_then(|earlier| coverage.merge(&earlier)) {
+                    eprintln!("Failure merging coverage {}\n{}", path, e);
+                    std::process::exit(1);
+                }
+            }
+            if let Err(e) = coverage.save(path) {
+                eprintln!("Failure writing {}\n{}", path, e);
+                std::process::exit(1);
+            }
+        }
+
+        if let Some(path) = options.coverage_report.as_ref() {
+            if let Err(e) = coverage.save_report(path, chip8.memory(), chip8.rom_range()) {
+                eprintln!("Failure writing {}\n{}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let (Some(path), Some(profiler)) = (&options.profile, &profiler) {
+        if let Err(e) = profiler.save_report(path, REPORT_TOP_ADDRESSES) {
+            eprintln!("Failure writing {}\n{}", path, e);
+            std::process::exit(1);
+        }
+    }
+
+    if let (Some(path), Some(tracer)) = (&options.trace, tracer) {
+        let records = tracer.records();
+        if let Err(e) = tracer.finish() {
+            eprintln!("Failure writing {}\n{}", path, e);
+            std::process::exit(1);
+        }
+        println!("traced {} instructions into {}", records, path);
+    }
+
+    println!("{} after {} frames ({} cycles)", exit_reason, watchdog.frames(), chip8.cycles());
+    std::process::exit(exit_reason.exit_code());
+}
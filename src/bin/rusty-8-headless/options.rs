@@ -0,0 +1,183 @@
+use rusty_8::{Limits, MemoryMap, Quirks, StackConfig, Timing, TraceFilter, TraceFormat};
+
+const USAGE: &str = "\
+Usage: rusty-8-headless [options] <rom_path>
+
+Runs a rom without a window or audio device, as fast as possible, until it ends or hits a limit.
+Prints why it stopped, and exits with 0 if the rom ended by itself (halted or self-loop),
+1 on error, 2 if idle for too long, 3 if it ran out of frames/cycles, 4 if it diverged
+from the --reference trace (0 if it matched all of it), and 5 if the --script failed
+(0 if it stopped the run).
+
+rom_path is a ROM file, - to read it from stdin, or a zip archive (asks which ROM if it holds several).
+
+Options:
+    --frames <n>            Number of 60Hz frames to run at most (default 600)
+    --max-cycles <n>        Stop at the end of the frame that runs the nth clock cycle
+    --idle-frames <n>       Stop once the screen hasn't changed for this many frames
+    --ignore-self-loop      Keep running when the rom jumps to itself, instead of stopping
+    --ipf <n>               Instructions per frame (default 8, ~500Hz)
+    --quirks <preset>       Quirks preset: rusty-8, vip or chip48 (default rusty-8)
+    --timing <model>        Instruction timing: fixed (--ipf per frame) or vip (default fixed)
+    --stack-depth <n>       Nested calls allowed, e.g. 12 (VIP) or 16 (CHIP-48) (default unlimited)
+    --stack-outside-memory  Keep the stack apart from RAM, like modern interpreters
+    --memory-map <preset>   Memory layout: rusty-8, standard (font at 0x050) or eti660 (load at 0x600)
+    --font-address <addr>   Override where the font is copied, e.g. 0x050
+    --stack-address <addr>  Override where the stack lives, when in memory
+    --load-address <addr>   Override where the ROM is loaded and execution starts, e.g. 0x600
+    --wav <path>            Render the beep into a WAV file
+    --sample-rate <hz>      Sample rate of the WAV file (default 44100)
+    --tone <hz>             Beep frequency in the WAV file (default 815)
+    --input <path>          Press and release keys as scripted in a file, see README
+    --script <path>         Run a Rhai script hooked into the rom, see README (needs the scripting feature)
+    --reference <path>      Compare every step against a trace from another emulator, see README
+    --coverage <path>       Count what the rom executed, read and wrote, adding to the file if it exists
+    --coverage-report <path>  Write a coverage heatmap and annotated disassembly, as HTML if path ends in .html
+    --profile <path>        Write a report of the rom's hot spots, calls and frame load at exit
+    --trace <path>          Log every executed instruction, and what it changed, into a file
+    --trace-format <fmt>    Trace format: text or binary (default text), see rusty-8-trace
+    --trace-pc <range>      Only trace instructions at these addresses, e.g. 0x200-0x2FF
+    --trace-ops <classes>   Only trace these opcode classes (first hex digit), e.g. D,F
+    --trace-cycles <n>      Only trace the first n clock cycles";
+
+/// Command line options of the headless runner.
+pub struct Options {
+    pub rom: String,
+    pub limits: Limits,
+    pub ticks_per_frame: usize,
+    pub quirks: Quirks,
+    pub timing: Timing,
+    pub stack_config: StackConfig,
+    pub memory_map: MemoryMap,
+    pub wav: Option<String>,
+    pub sample_rate: u32,
+    pub tone_frequency: f32,
+    pub input: Option<String>,
+    pub script: Option<String>,
+    pub reference: Option<String>,
+    pub coverage: Option<String>,
+    pub coverage_report: Option<String>,
+    pub profile: Option<String>,
+    pub trace: Option<String>,
+    pub trace_format: TraceFormat,
+    pub trace_filter: TraceFilter,
+}
+
+impl Options {
+    /// Parse options from the process arguments.
+    /// On failure, returns a message explaining what's wrong, followed by the usage.
+    pub fn from_args() -> Result<Options, String> {
+        let mut rom = None;
+        let mut options = Options {
+            rom: String::new(),
+            limits: Limits {
+                max_frames: Some(600),
+                max_cycles: None,
+                idle_frames: None,
+                stop_on_self_loop: true,
+            },
+            ticks_per_frame: 8,
+            quirks: Quirks::default(),
+            timing: Timing::default(),
+            stack_config: StackConfig::default(),
+            memory_map: MemoryMap::default(),
+            wav: None,
+            sample_rate: 44100,
+            tone_frequency: 815.0,
+            input: None,
+            script: None,
+            reference: None,
+            coverage: None,
+            coverage_report: None,
+            profile: None,
+            trace: None,
+            trace_format: TraceFormat::default(),
+            trace_filter: TraceFilter::default(),
+        };
+
+        let mut address_overrides = (None, None, None); // Font, stack and load address, over the preset
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("Missing value for {}\n\n{}", name, USAGE));
+
+            match arg.as_str() {
+                "--frames" => options.limits.max_frames = Some(parse(&arg, &value("--frames")?)?),
+                "--max-cycles" => options.limits.max_cycles = Some(parse(&arg, &value("--max-cycles")?)?),
+                "--idle-frames" => options.limits.idle_frames = Some(parse(&arg, &value("--idle-frames")?)?),
+                "--ignore-self-loop" => options.limits.stop_on_self_loop = false,
+                "--ipf" => options.ticks_per_frame = parse(&arg, &value("--ipf")?)?,
+                "--quirks" => {
+                    let name = value("--quirks")?;
+                    options.quirks = Quirks::from_name(&name)
+                        .ok_or(format!("Unknown quirks preset '{}'\n\n{}", name, USAGE))?;
+                },
+                "--timing" => {
+                    let name = value("--timing")?;
+                    options.timing = Timing::from_name(&name)
+                        .ok_or(format!("Unknown timing model '{}'\n\n{}", name, USAGE))?;
+                },
+                "--stack-depth" => {
+                    let depth = value("--stack-depth")?;
+                    options.stack_config.max_depth = StackConfig::parse_depth(&depth)
+                        .ok_or(format!("Invalid value '{}' for --stack-depth\n\n{}", depth, USAGE))?;
+                },
+                "--stack-outside-memory" => options.stack_config.in_memory = false,
+                "--memory-map" => {
+                    let name = value("--memory-map")?;
+                    options.memory_map = MemoryMap::from_name(&name)
+                        .ok_or(format!("Unknown memory map preset '{}'\n\n{}", name, USAGE))?;
+                },
+                "--font-address" => address_overrides.0 = Some(parse_address(&arg, &value("--font-address")?)?),
+                "--stack-address" => address_overrides.1 = Some(parse_address(&arg, &value("--stack-address")?)?),
+                "--load-address" => address_overrides.2 = Some(parse_address(&arg, &value("--load-address")?)?),
+                "--wav" => options.wav = Some(value("--wav")?),
+                "--sample-rate" => options.sample_rate = parse(&arg, &value("--sample-rate")?)?,
+                "--tone" => options.tone_frequency = parse(&arg, &value("--tone")?)?,
+                "--input" => options.input = Some(value("--input")?),
+                "--script" if cfg!(feature = "scripting") => options.script = Some(value("--script")?),
+                "--script" => return Err("--script needs rusty-8 built with the scripting feature, e.g. cargo build --features scripting".to_string()),
+                "--reference" => options.reference = Some(value("--reference")?),
+                "--coverage" => options.coverage = Some(value("--coverage")?),
+                "--coverage-report" => options.coverage_report = Some(value("--coverage-report")?),
+                "--profile" => options.profile = Some(value("--profile")?),
+                "--trace" => options.trace = Some(value("--trace")?),
+                "--trace-format" => {
+                    let name = value("--trace-format")?;
+                    options.trace_format = TraceFormat::from_name(&name)
+                        .ok_or(format!("Unknown trace format '{}'\n\n{}", name, USAGE))?;
+                },
+                "--trace-pc" => {
+                    let range = value("--trace-pc")?;
+                    options.trace_filter.pc_range = Some(TraceFilter::parse_pc_range(&range)
+                        .ok_or(format!("Invalid address range '{}' for --trace-pc\n\n{}", range, USAGE))?);
+                },
+                "--trace-ops" => {
+                    let classes = value("--trace-ops")?;
+                    options.trace_filter.opcode_classes = TraceFilter::parse_opcode_classes(&classes)
+                        .ok_or(format!("Invalid opcode classes '{}' for --trace-ops\n\n{}", classes, USAGE))?;
+                },
+                "--trace-cycles" => options.trace_filter.max_cycles = Some(parse(&arg, &value("--trace-cycles")?)?),
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n\n{}", arg, USAGE)),
+                _ => rom = Some(arg),
+            }
+        }
+
+        let (font_start, stack_start, program_start) = address_overrides;
+        options.memory_map.font_start = font_start.unwrap_or(options.memory_map.font_start);
+        options.memory_map.stack_start = stack_start.unwrap_or(options.memory_map.stack_start);
+        options.memory_map.program_start = program_start.unwrap_or(options.memory_map.program_start);
+        options.memory_map.validate().map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+
+        options.rom = rom.ok_or(format!("Missing rom file path.\n\n{}", USAGE))?;
+        Ok(options)
+    }
+}
+
+fn parse_address(name: &str, value: &str) -> Result<u16, String> {
+    MemoryMap::parse_address(value).ok_or(format!("Invalid address '{}' for {}\n\n{}", value, name, USAGE))
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value '{}' for {}\n\n{}", value, name, USAGE))
+}
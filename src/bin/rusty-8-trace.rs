@@ -0,0 +1,49 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use rusty_8::trace;
+
+const USAGE: &str = "\
+Usage: rusty-8-trace <trace_path> [output_path]
+
+Converts a binary trace, made with rusty-8-headless --trace-format binary, into the text format.
+Writes it to output_path, or prints it if missing.";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (input_path, output_path) = match args.as_slice() {
+        [input] if input != "-h" && input != "--help" => (input, None),
+        [input, output] => (input, Some(output)),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        },
+    };
+
+    let input = match File::open(input_path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Failure opening {}\n{}", input_path, e);
+            std::process::exit(1);
+        },
+    };
+    let output: Box<dyn Write> = match output_path.map(File::create) {
+        Some(Ok(file)) => Box::new(file),
+        Some(Err(e)) => {
+            eprintln!("Failure creating {}\n{}", output_path.unwrap(), e);
+            std::process::exit(1);
+        },
+        None => Box::new(io::stdout()),
+    };
+
+    let mut output = BufWriter::new(output);
+    let result = trace::convert_to_text(&mut BufReader::new(input), &mut output)
+        .and_then(|records| output.flush().map(|_| records));
+    match result {
+        Ok(records) => eprintln!("converted {} instructions", records),
+        Err(e) => {
+            eprintln!("Failure converting {}\n{}", input_path, e);
+            std::process::exit(1);
+        },
+    }
+}
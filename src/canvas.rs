@@ -0,0 +1,195 @@
+// A grayscale pixel buffer to draw the frontend's own screens on (like the debugger), with a tiny
+// 3x5 pixel font in the spirit of the CHIP-8 one, extended with letters and some punctuation.
+// The launcher writes with the CHIP-8 font itself instead: its 4x5 hex digits, and letters and
+// punctuation drawn the same way.
+
+use std::convert::TryInto;
+
+use rusty_8::chip8::CHIP8_FONT;
+
+pub const GLYPH_WIDTH:  usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+pub const CHAR_WIDTH:   usize = GLYPH_WIDTH + 1;    // Glyph and the space after it
+pub const LINE_HEIGHT:  usize = GLYPH_HEIGHT + 2;   // Glyph and the space below it
+
+pub const CHIP8_GLYPH_WIDTH: usize = 4;
+pub const CHIP8_CHAR_WIDTH:  usize = CHIP8_GLYPH_WIDTH + 1;
+
+/// A glyph's 5 rows, 3 pixels each, leftmost pixel in bit 2 (bit 3 for 4 pixels).
+type Glyph = [u8; GLYPH_HEIGHT];
+
+fn glyph(c: char) -> Glyph {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '&' => [0b010, 0b101, 0b010, 0b101, 0b011],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],   // ?
+    }
+}
+
+/// A glyph of the CHIP-8 font: its 5 rows, 4 pixels each, leftmost pixel in bit 3.
+fn chip8_glyph(c: char) -> Glyph {
+    let sprite = match c.to_ascii_uppercase() {
+        c @ ('0'..='9' | 'A'..='F') => {
+            let digit = c.to_digit(16).unwrap() as usize;
+            CHIP8_FONT[digit * GLYPH_HEIGHT..(digit + 1) * GLYPH_HEIGHT].try_into().unwrap()
+        },
+        'G' => [0xF0, 0x80, 0xB0, 0x90, 0xF0],
+        'H' => [0x90, 0x90, 0xF0, 0x90, 0x90],
+        'I' => [0x70, 0x20, 0x20, 0x20, 0x70],
+        'J' => [0x10, 0x10, 0x10, 0x90, 0xF0],
+        'K' => [0x90, 0xA0, 0xC0, 0xA0, 0x90],
+        'L' => [0x80, 0x80, 0x80, 0x80, 0xF0],
+        'M' => [0x90, 0xF0, 0xF0, 0x90, 0x90],
+        'N' => [0x90, 0xD0, 0xB0, 0x90, 0x90],
+        'O' => [0x60, 0x90, 0x90, 0x90, 0x60],  // Rounded, not to read as a 0
+        'P' => [0xF0, 0x90, 0xF0, 0x80, 0x80],
+        'Q' => [0x60, 0x90, 0x90, 0xA0, 0x50],
+        'R' => [0xF0, 0x90, 0xF0, 0xA0, 0x90],
+        'S' => [0x70, 0x80, 0x60, 0x10, 0xE0],  // Not to read as a 5
+        'T' => [0x70, 0x20, 0x20, 0x20, 0x20],
+        'U' => [0x90, 0x90, 0x90, 0x90, 0xF0],
+        'V' => [0x90, 0x90, 0x90, 0x90, 0x60],
+        'W' => [0x90, 0x90, 0xF0, 0xF0, 0x90],
+        'X' => [0x90, 0x90, 0x60, 0x90, 0x90],
+        'Y' => [0xA0, 0xA0, 0x40, 0x40, 0x40],
+        'Z' => [0xF0, 0x10, 0x20, 0x40, 0xF0],
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x40],
+        ',' => [0x00, 0x00, 0x00, 0x40, 0x80],
+        ':' => [0x00, 0x40, 0x00, 0x40, 0x00],
+        '-' => [0x00, 0x00, 0xF0, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0xF0],
+        '+' => [0x00, 0x40, 0xE0, 0x40, 0x00],
+        '=' => [0x00, 0xF0, 0x00, 0xF0, 0x00],
+        '<' => [0x20, 0x40, 0x80, 0x40, 0x20],
+        '>' => [0x80, 0x40, 0x20, 0x40, 0x80],
+        '(' => [0x40, 0x80, 0x80, 0x80, 0x40],
+        ')' => [0x80, 0x40, 0x40, 0x40, 0x80],
+        '[' => [0xC0, 0x80, 0x80, 0x80, 0xC0],
+        ']' => [0xC0, 0x40, 0x40, 0x40, 0xC0],
+        '/' => [0x10, 0x20, 0x20, 0x40, 0x80],
+        '!' => [0x40, 0x40, 0x40, 0x00, 0x40],
+        '\'' => [0x40, 0x40, 0x00, 0x00, 0x00],
+        '#' => [0x50, 0xF0, 0x50, 0xF0, 0x50],
+        '&' => [0x40, 0xA0, 0x40, 0xA0, 0x50],
+        _ => [0xE0, 0x10, 0x60, 0x00, 0x40],    // ?
+    };
+
+    sprite.map(|row| row >> 4)
+}
+
+/// A grayscale image, a byte per pixel, rows top to bottom.
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas { width, height, pixels: vec![0; width * height] }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    /// Fill a rectangle, clipped to the canvas.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, shade: u8) {
+        let (x_end, y_end) = ((x + width).min(self.width), (y + height).min(self.height));
+        for row in y.min(y_end)..y_end {
+            self.pixels[row * self.width + x.min(x_end)..row * self.width + x_end].fill(shade);
+        }
+    }
+
+    /// Draw `text` with its top-left corner at (x, y), clipped to the canvas.
+    /// Returns the x position right after it.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, shade: u8) -> usize {
+        self.draw_glyphs(x, y, text, shade, glyph, GLYPH_WIDTH)
+    }
+
+    /// Same as `draw_text`, with the CHIP-8 font: characters are `CHIP8_CHAR_WIDTH` wide.
+    pub fn draw_chip8_text(&mut self, x: usize, y: usize, text: &str, shade: u8) -> usize {
+        self.draw_glyphs(x, y, text, shade, chip8_glyph, CHIP8_GLYPH_WIDTH)
+    }
+
+    fn draw_glyphs(&mut self, x: usize, y: usize, text: &str, shade: u8, glyph: fn(char) -> Glyph, glyph_width: usize) -> usize {
+        let mut x = x;
+        for c in text.chars() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..glyph_width {
+                    if bits & (1 << (glyph_width - 1 - column)) != 0 {
+                        self.fill_rect(x + column, y + row, 1, 1, shade);
+                    }
+                }
+            }
+            x += glyph_width + 1;
+        }
+
+        x
+    }
+
+    /// Draw a 1bpp image (like the chip8 screen, a byte per pixel, lit if not 0), each pixel
+    /// scaled to a `scale` sized square.
+    pub fn draw_image(&mut self, x: usize, y: usize, image: &[u8], image_width: usize, scale: usize, shade: u8) {
+        for (pos, &pixel) in image.iter().enumerate() {
+            if pixel != 0 {
+                let (column, row) = (pos % image_width, pos / image_width);
+                self.fill_rect(x + column * scale, y + row * scale, scale, scale, shade);
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// Cheats, to playtest hard ROMs: a RAM search to find where a ROM keeps things like lives or its
+// timer, then cheats that freeze those bytes (or registers) every frame.
+//
+// A RAM search starts from every address and a snapshot of memory. Each step compares memory
+// against the snapshot, keeps the addresses that changed the way asked (e.g. increased after
+// scoring), and snapshots again. A few steps usually narrow it down to a handful of addresses.
+//
+// Cheat files are text, one cheat per line: what to freeze, the value in hex, then its name.
+// Blank lines and lines starting with # are ignored, e.g.
+//
+//   0x2F0 09 Infinite lives
+//   VE 05 Max ammo
+//   DT 00 No waiting
+//
+// Targets are a memory address (0x...), a V register, DT or ST. Frontends keep a file per ROM,
+// named after its hash (see `cheat_file_name`), so cheats follow a ROM whatever its file is called.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::chip8::{Chip8, TickObserver, MEM_SIZE};
+use crate::hash::sha1_hex;
+use crate::memory_map::MemoryMap;
+
+/// How memory must have changed since the last snapshot, to keep an address in the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    Equals(u8),
+}
+
+impl SearchFilter {
+    fn accepts(&self, before: u8, now: u8) -> bool {
+        match self {
+            SearchFilter::Changed => now != before,
+            SearchFilter::Unchanged => now == before,
+            SearchFilter::Increased => now > before,
+            SearchFilter::Decreased => now < before,
+            SearchFilter::Equals(value) => now == *value,
+        }
+    }
+}
+
+/// A RAM search, narrowing down addresses by how their bytes change between snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamSearch {
+    candidates: Vec<u16>,   // Addresses still matching, in order
+    snapshot: Vec<u8>,      // Memory when last filtered
+}
+
+impl RamSearch {
+    /// Start a search over all of memory, from its current contents.
+    pub fn new(chip8: &Chip8) -> Self {
+        RamSearch { candidates: (0..MEM_SIZE as u16).collect(), snapshot: chip8.memory().to_vec() }
+    }
+
+    /// Keep the addresses whose byte changed as `filter` says since the last snapshot,
+    /// then snapshot memory again. Returns how many are left.
+    pub fn filter(&mut self, chip8: &Chip8, filter: SearchFilter) -> usize {
+        let memory = chip8.memory();
+        let snapshot = &self.snapshot;
+        self.candidates.retain(|&addr| filter.accepts(snapshot[addr as usize], memory[addr as usize]));
+        self.snapshot.copy_from_slice(memory);
+
+        self.candidates.len()
+    }
+
+    /// Addresses still matching every filter so far.
+    pub fn candidates(&self) -> &[u16] {
+        &self.candidates
+    }
+}
+
+/// What a cheat freezes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatTarget {
+    Memory(u16),
+    V(u8),      // V register [0..F]
+    DelayTimer,
+    SoundTimer,
+}
+
+impl CheatTarget {
+    /// Parse a target as written in cheat files: `0x2F0`, `VE`, `DT` or `ST`.
+    pub fn parse(target: &str) -> Option<CheatTarget> {
+        match target {
+            "DT" => Some(CheatTarget::DelayTimer),
+            "ST" => Some(CheatTarget::SoundTimer),
+            _ if target.len() == 2 && target.starts_with('V') => u8::from_str_radix(&target[1..], 16).ok().map(CheatTarget::V),
+            _ => MemoryMap::parse_address(target).filter(|&addr| (addr as usize) < MEM_SIZE).map(CheatTarget::Memory),
+        }
+    }
+
+    /// Current value of the target.
+    pub fn read(&self, chip8: &Chip8) -> u8 {
+        let regs = chip8.registers();
+        match *self {
+            CheatTarget::Memory(addr) => chip8.memory()[addr as usize],
+            CheatTarget::V(x) => regs.v[x as usize],
+            CheatTarget::DelayTimer => regs.dt,
+            CheatTarget::SoundTimer => regs.st,
+        }
+    }
+
+    /// Overwrite the target with `value`.
+    pub fn write(&self, chip8: &mut Chip8, value: u8) {
+        if let CheatTarget::Memory(addr) = *self {
+            chip8.memory_mut()[addr as usize] = value;
+            return;
+        }
+
+        let mut regs = chip8.registers();
+        match *self {
+            CheatTarget::V(x) => regs.v[x as usize] = value,
+            CheatTarget::DelayTimer => regs.dt = value,
+            _ => regs.st = value,
+        }
+        chip8.set_registers(regs);
+    }
+}
+
+impl fmt::Display for CheatTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheatTarget::Memory(addr) => write!(f, "0x{:03X}", addr),
+            CheatTarget::V(x) => write!(f, "V{:X}", x),
+            CheatTarget::DelayTimer => write!(f, "DT"),
+            CheatTarget::SoundTimer => write!(f, "ST"),
+        }
+    }
+}
+
+/// A named value kept in place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+    pub name: String,
+    pub target: CheatTarget,
+    pub value: u8,
+}
+
+/// The cheats of a ROM. Freezes their targets every frame, when passed to `Chip8::run_frame_observed`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+    pub fn new() -> Self {
+        Cheats::default()
+    }
+
+    /// Parse a cheat file. On failure, returns a message pointing at the faulty line.
+    pub fn parse(text: &str) -> Result<Cheats, String> {
+        let mut cheats = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let target = fields.next().and_then(CheatTarget::parse);
+            let value = fields.next().and_then(|value| u8::from_str_radix(value, 16).ok());
+            let name = fields.collect::<Vec<_>>().join(" ");
+
+            let cheat = target.zip(value).map(|(target, value)| Cheat { name, target, value });
+            cheats.push(cheat.ok_or(format!("line {}: expected '<target> <value> [name]', got '{}'", line_index + 1, line))?);
+        }
+
+        Ok(Cheats { cheats })
+    }
+
+    /// Read and parse the cheat file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Cheats, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Cheats::parse(&text)
+    }
+
+    /// Write the cheats as a cheat file at `path`, creating its directory if needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    /// The cheat freezing `target`, if any.
+    pub fn get(&self, target: CheatTarget) -> Option<&Cheat> {
+        self.cheats.iter().find(|cheat| cheat.target == target)
+    }
+
+    /// Add a cheat, replacing the one freezing the same target.
+    pub fn add(&mut self, cheat: Cheat) {
+        self.remove(cheat.target);
+        self.cheats.push(cheat);
+    }
+
+    /// Stop freezing `target`. Returns the cheat that did.
+    pub fn remove(&mut self, target: CheatTarget) -> Option<Cheat> {
+        let pos = self.cheats.iter().position(|cheat| cheat.target == target)?;
+        Some(self.cheats.remove(pos))
+    }
+
+    /// Write every cheat's value into its target.
+    pub fn apply(&self, chip8: &mut Chip8) {
+        for cheat in &self.cheats {
+            cheat.target.write(chip8, cheat.value);
+        }
+    }
+}
+
+impl fmt::Display for Cheats {
+    /// Cheat file contents.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for cheat in &self.cheats {
+            let line = format!("{} {:02X} {}", cheat.target, cheat.value, cheat.name);
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TickObserver for Cheats {
+    fn before_frame(&mut self, chip8: &mut Chip8) {
+        self.apply(chip8);
+    }
+}
+
+/// Name of the cheat file for `rom`: the hash of its contents.
+pub fn cheat_file_name(rom: &[u8]) -> String {
+    format!("{}.cht", sha1_hex(rom))
+}
@@ -1,27 +1,39 @@
+use std::collections::VecDeque;
 use std::convert::TryInto;
-use std::fs::File;
-use std::io::Read;
+use std::ops::Range;
 use nanorand::Rng;
 use std::path::Path;
 
+use crate::error::{Chip8Error, RomError};
+use crate::memory_map::MemoryMap;
+use crate::quirks::{Quirks, StackConfig};
+use crate::rom;
+use crate::timing::{self, Timing};
+
 // The original CHIP-8 interpreter occupies the first 512 bytes.
-// That way, every program starts at byte offset 0x200 (512).
+// That way, every program starts at byte offset 0x200 (512), unless the `MemoryMap` says otherwise.
 // All instructions are 2 bytes long and are stored most-significant-byte first (BIG ENDIAN);
 // instructions must be even aligned, so sprites may need to pad the RAM to guarantee this
 
-const MEM_SIZE:    usize = 4096;    // 4Kb of RAM (address range = 0x000 to 0xFFF).
-const PROG_OFFSET: usize   = 0x200;   // ROM's are loaded on addr. 0x200.
-
-const STACK_START: u8    = 0x000;   // Stack is the first 0x100 bytes of memory.
-const STACK_END:   u8    = 0x0FF;
+pub const MEM_SIZE:  usize = 4096;  // 4Kb of RAM (address range = 0x000 to 0xFFF).
+pub const FONT_SIZE: usize = 80;    // 16 hex digit sprites, 5 bytes each
 
-const SPRITES_START: usize = 0x0FF;   // Sprites start right after stack.
-const SPRITES_END:   usize = 0x14F;   // Sprites end right before program offset.
+const STACK_CAPACITY: usize = 127;  // Return addresses that fit in the stack
+pub const STACK_SIZE: usize = STACK_CAPACITY * 2;   // Bytes the stack takes, when in memory
 
 pub const SCREEN_WIDTH:  usize = 64;    // Internal Chip-8 Screen Width
 pub const SCREEN_HEIGHT: usize = 32;    // Internal Chip-8 Screen Height
 
-const CHIP8_FONT: [u8; 80] = [
+const KEY_EVENTS_LEN: usize = 16;  // Older key events are dropped once the queue is full
+
+// Save states are a fixed-size snapshot of everything that changes while a ROM runs.
+const STATE_MAGIC: &[u8; 4] = b"R8ST";
+const STATE_VERSION: u8 = 6;
+pub const STATE_SIZE: usize = 5 + MEM_SIZE + 2 + 16 + 2 + 2 + 1 + 2 * STACK_CAPACITY + 1 + 1
+    + 16 + 1 + 1 + 8 + 2 + 4 + 1 + SCREEN_WIDTH * SCREEN_HEIGHT;
+
+/// Sprites of the hex digits 0-F, 4 pixels wide (the high nibble of each byte) and 5 rows tall.
+pub const CHIP8_FONT: [u8; FONT_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0,   // 0
     0x20, 0x60, 0x20, 0x20, 0x70,   // 1
     0xF0, 0x10, 0xF0, 0x80, 0xF0,   // 2
@@ -46,52 +58,240 @@ fn decode(opcode: u16) -> Nibbles {
     let nibble_1 = ((opcode & 0xF000) >> 12) as usize;
     let nibble_2 = ((opcode & 0x0F00) >>  8) as usize;
     let nibble_3 = ((opcode & 0x00F0) >>  4) as usize;
-    let nibble_4 = ((opcode & 0x000F) >>  0) as usize;
+    let nibble_4 =  (opcode & 0x000F)        as usize;
+
+    (nibble_1, nibble_2, nibble_3, nibble_4)
+}
+
+/// A key of the 16-key pad going down (pressed) or up (released).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: u8,        // Key [0..F]
+    pub is_down: bool,  // Pressed or released
+    pub cycle: u64,     // Chip8 cycle the event happened on
+}
+
+/// Snapshot of the chip8 registers, as seen by the running program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Registers {
+    pub v:  [u8; 16],
+    pub i:  u16,
+    pub pc: u16,
+    pub sp: u8,     // Offset from the stack's start, 2 bytes per return address
+    pub dt: u8,
+    pub st: u8,
+}
+
+/// Gets a look at the chip8 around every frame and tick `run_frame_observed` runs,
+/// e.g. to trace or profile the running ROM. Observers may also change the chip8 as it goes.
+pub trait TickObserver {
+    /// Called when the frame starts, before any of its ticks.
+    fn before_frame(&mut self, _chip8: &mut Chip8) {}
+
+    /// Called when the frame is over, after the timers update, unless a tick failed.
+    fn after_frame(&mut self, _chip8: &mut Chip8) {}
+
+    /// Called right before the tick runs.
+    fn before_tick(&mut self, _chip8: &mut Chip8) {}
+
+    /// Called right after the tick ran, unless it failed.
+    fn after_tick(&mut self, _chip8: &mut Chip8) {}
+}
+
+/// Observes nothing, for running frames unobserved.
+impl TickObserver for () {}
+
+/// An observer that may not be there.
+impl<O: TickObserver> TickObserver for Option<O> {
+    fn before_frame(&mut self, chip8: &mut Chip8) {
+        if let Some(observer) = self { observer.before_frame(chip8); }
+    }
+
+    fn after_frame(&mut self, chip8: &mut Chip8) {
+        if let Some(observer) = self { observer.after_frame(chip8); }
+    }
+
+    fn before_tick(&mut self, chip8: &mut Chip8) {
+        if let Some(observer) = self { observer.before_tick(chip8); }
+    }
+
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        if let Some(observer) = self { observer.after_tick(chip8); }
+    }
+}
+
+/// An observer borrowed from elsewhere, e.g. to combine observers that outlive a frame.
+impl<O: TickObserver + ?Sized> TickObserver for &mut O {
+    fn before_frame(&mut self, chip8: &mut Chip8) { (**self).before_frame(chip8); }
+    fn after_frame(&mut self, chip8: &mut Chip8) { (**self).after_frame(chip8); }
+    fn before_tick(&mut self, chip8: &mut Chip8) { (**self).before_tick(chip8); }
+    fn after_tick(&mut self, chip8: &mut Chip8) { (**self).after_tick(chip8); }
+}
 
-    return (nibble_1, nibble_2, nibble_3, nibble_4);
+/// Several observers, called in order.
+macro_rules! tuple_observer {
+    ($($index:tt: $observer:ident),+) => {
+        impl<$($observer: TickObserver),+> TickObserver for ($($observer,)+) {
+            fn before_frame(&mut self, chip8: &mut Chip8) { $(self.$index.before_frame(chip8);)+ }
+            fn after_frame(&mut self, chip8: &mut Chip8) { $(self.$index.after_frame(chip8);)+ }
+            fn before_tick(&mut self, chip8: &mut Chip8) { $(self.$index.before_tick(chip8);)+ }
+            fn after_tick(&mut self, chip8: &mut Chip8) { $(self.$index.after_tick(chip8);)+ }
+        }
+    };
 }
 
+tuple_observer!(0: A, 1: B);
+tuple_observer!(0: A, 1: B, 2: C);
+tuple_observer!(0: A, 1: B, 2: C, 3: D);
+tuple_observer!(0: A, 1: B, 2: C, 3: D, 4: E);
+
+#[derive(Clone)]
 pub struct Chip8 {
     memory: [u8; MEM_SIZE],
     memory_end: usize,
     v:  [u8; 16],   // General purpose Vx registers. VF is special flag register.
     i:  u16,        // Index register
     pc: u16,        // Program-counter
-    sp: u8,         // Stack pointer
+    sp: u8,         // Stack pointer, offset from the stack's start
+    stack: [u16; STACK_CAPACITY],   // Return addresses, when the stack is kept outside of memory
     dt: u8,         // Delay timer register
     st: u8,         // Sound timer register
     pub keyboard: [bool; 16],   // Keyboard with keys' state (up | down) -> keys from 0x0 to 0xF
     pub waiting: Option<u8>,    // Index [0..F] of register waiting for a keypress
+    key_wait_pressed: Option<u8>,   // Key pressed during the wait, answered once released
+    key_events: VecDeque<KeyEvent>, // Key presses/releases not yet consumed by FX0A
+    cycles: u64,                // Clock cycles since boot
+    vblank_wait: bool,          // DXYN is stalled until the next frame starts (display wait quirk)
+    vblank: bool,               // A frame started while DXYN was stalled, so it may draw now
+    cycle_budget: i32,          // VIP machine cycles left this frame, negative if overspent
+    halted: bool,               // Program ended, by running into empty memory
+    last_instruction: Option<(u16, u16)>,   // Address and opcode executed by the last tick
+    memory_reads: Vec<u16>,                 // Address of bytes the last tick read, besides the instruction
+    memory_writes: Vec<(u16, u8)>,          // Address and new value of bytes the last tick wrote
     pub screen: Vec<u8>,        // Internal screen buffer
-    pub screen_updated: bool    // Screen was updated in last tick 
+    pub screen_updated: bool,   // Screen was updated in last tick 
+    pub quirks: Quirks,         // Interpreter behaviour the loaded ROM expects
+    pub timing: Timing,         // How long instructions take, and so how many run each frame
+    pub stack_config: StackConfig,  // Where the stack lives and how deep it can get
+    memory_map: MemoryMap       // Where the font, stack and program are in memory
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Chip8::new()
+    }
 }
 
 impl Chip8 {
     pub fn new() -> Self {
+        Chip8::with_quirks(Quirks::default())
+    }
+
+    pub fn with_quirks(quirks: Quirks) -> Self {
         // Initializes the whole memory to 0, then the font area
+        let memory_map = MemoryMap::default();
         let mut memory = [0; MEM_SIZE]; // Init the whole memory to 0
-        memory[SPRITES_START..SPRITES_END].copy_from_slice(&CHIP8_FONT);
+        let font_start = memory_map.font_start as usize;
+        memory[font_start..font_start + FONT_SIZE].copy_from_slice(&CHIP8_FONT);
 
         Chip8 {
-            memory,      
-            memory_end: PROG_OFFSET,    // Marks the end of CHIP-8's loaded ROM
+            memory,
+            memory_end: memory_map.program_start as usize,  // Marks the end of CHIP-8's loaded ROM
             v:      [0; 16],            // Init registers to 0
             i:      0,
-            pc:     PROG_OFFSET as u16, // Program ROM offset in CHIP-8 RAM
-            sp:     STACK_START,
+            pc:     memory_map.program_start,   // Program ROM offset in CHIP-8 RAM
+            sp:     0,
+            stack:  [0; STACK_CAPACITY],
             dt:     0,
             st:     0,
             keyboard: [false; 16],
             waiting: None,
+            key_wait_pressed: None,
+            key_events: VecDeque::with_capacity(KEY_EVENTS_LEN),
+            cycles: 0,
+            vblank_wait: false,
+            vblank: false,
+            cycle_budget: 0,
+            halted: false,
+            last_instruction: None,
+            memory_reads: Vec::new(),
+            memory_writes: Vec::new(),
             screen: vec![0u8; SCREEN_WIDTH * SCREEN_HEIGHT],
-            screen_updated: false
+            screen_updated: false,
+            quirks,
+            timing: Timing::default(),
+            stack_config: StackConfig::default(),
+            memory_map
         }
     }
+
+    /// Lay memory out differently: moves the font and restarts the program at its new address.
+    /// Meant to be called before `load_rom`. Fails if something wouldn't fit in memory, or overlaps something else.
+    pub fn set_memory_map(&mut self, memory_map: MemoryMap) -> Result<(), String> {
+        memory_map.validate()?;
+
+        let old_font_start = self.memory_map.font_start as usize;
+        self.memory[old_font_start..old_font_start + FONT_SIZE].fill(0);
+        let font_start = memory_map.font_start as usize;
+        self.memory[font_start..font_start + FONT_SIZE].copy_from_slice(&CHIP8_FONT);
+
+        self.memory_map = memory_map;
+        self.memory_end = memory_map.program_start as usize;
+        self.pc = memory_map.program_start;
+        self.sp = 0;
+
+        Ok(())
+    }
+
+    /// Where the font, stack and program are in memory.
+    pub fn memory_map(&self) -> MemoryMap {
+        self.memory_map
+    }
+
+    /// Where the loaded ROM is in memory.
+    pub fn rom_range(&self) -> Range<usize> {
+        self.memory_map.program_start as usize..self.memory_end
+    }
     
-    pub fn load_rom<P: AsRef<Path>>(&mut self,rom: P) -> std::io::Result<()> {
-        let mut rom = File::open(rom)?;
-        self.memory_end += rom.read(&mut self.memory[(PROG_OFFSET as usize)..MEM_SIZE])?;
+    /// Load the ROM at `rom`: a file, `-` for stdin, or a zip archive holding a single ROM.
+    /// See `rom::read_rom` to pick one from an archive holding several.
+    pub fn load_rom<P: AsRef<Path>>(&mut self, rom: P) -> Result<(), RomError> {
+        let rom = rom::read_rom(rom, None, self.memory_map.rom_capacity())?;
+        self.load_rom_bytes(&rom)
+    }
+
+    /// Copy `rom` into memory, where programs start. Fails if it's empty, or doesn't fit.
+    pub fn load_rom_bytes(&mut self, rom: &[u8]) -> Result<(), RomError> {
+        self.check_rom_size(rom)?;
 
+        let program_start = self.memory_map.program_start as usize;
+        self.memory[program_start..program_start + rom.len()].copy_from_slice(rom);
+        self.memory_end = program_start + rom.len();
+        Ok(())
+    }
+
+    /// Replace the loaded ROM with `rom` and reboot into it, as if it was loaded into a new machine:
+    /// unlike `reboot`, nothing of the old image (or what the program wrote over it) is left in memory.
+    /// Fails if `rom` is empty or doesn't fit, leaving the machine as it was.
+    pub fn reload_rom(&mut self, rom: &[u8]) -> Result<(), RomError> {
+        self.check_rom_size(rom)?;
+
+        self.memory.fill(0);
+        let font_start = self.memory_map.font_start as usize;
+        self.memory[font_start..font_start + FONT_SIZE].copy_from_slice(&CHIP8_FONT);
+        self.load_rom_bytes(rom)?;
+        self.reboot();
+        Ok(())
+    }
+
+    fn check_rom_size(&self, rom: &[u8]) -> Result<(), RomError> {
+        let capacity = self.memory_map.rom_capacity();
+        if rom.is_empty() {
+            return Err(RomError::Empty);
+        }
+        if rom.len() > capacity {
+            return Err(RomError::TooLarge { size: rom.len(), capacity });
+        }
         Ok(())
     }
     
@@ -105,21 +305,190 @@ impl Chip8 {
         // Reset peripherals
         self.keyboard.fill(false);
         self.waiting = None;
+        self.key_wait_pressed = None;
+        self.key_events.clear();
+        self.cycles = 0;
+        self.vblank_wait = false;
+        self.vblank = false;
+        self.cycle_budget = 0;
+        self.halted = false;
+        self.last_instruction = None;
+        self.memory_reads.clear();
+        self.memory_writes.clear();
         self.screen.fill(0);
         self.screen_updated = false;
 
         // Reset memory (stack and ram) and reboot program (program counter)
-        self.memory[(STACK_START as usize)..(STACK_END as usize)].fill(0);
-        self.sp = STACK_START;
+        if self.stack_config.in_memory {
+            let stack_start = self.memory_map.stack_start as usize;
+            self.memory[stack_start..stack_start + STACK_SIZE].fill(0);
+        }
+        self.sp = 0;
+        self.stack.fill(0);
         self.memory[self.memory_end..MEM_SIZE].fill(0);
-        self.pc = PROG_OFFSET as u16;
+        self.pc = self.memory_map.program_start;
+    }
+
+    /// Read-only view of the whole 4Kb RAM.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Mutable view of the whole 4Kb RAM, for frontends that expose it (e.g. to cheats).
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Snapshot of the registers.
+    pub fn registers(&self) -> Registers {
+        Registers { v: self.v, i: self.i, pc: self.pc, sp: self.sp, dt: self.dt, st: self.st }
+    }
+
+    /// Overwrite the registers, e.g. to poke at a paused ROM.
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.v = registers.v;
+        self.i = registers.i;
+        self.pc = registers.pc;
+        self.sp = registers.sp.min(STACK_SIZE as u8) & !1;
+        self.dt = registers.dt;
+        self.st = registers.st;
+    }
+
+    /// Address and opcode of the instruction the last tick executed.
+    /// None if it didn't execute one: waiting for a key or the display, or failing.
+    pub fn last_instruction(&self) -> Option<(u16, u16)> {
+        self.last_instruction
+    }
+
+    /// Bytes the last tick read from memory (sprites, registers, return addresses), in the order read.
+    /// Doesn't include the instruction itself, see `last_instruction`.
+    pub fn memory_reads(&self) -> &[u16] {
+        &self.memory_reads
+    }
+
+    /// Bytes the last tick wrote to memory, as (address, new value), in the order written.
+    pub fn memory_writes(&self) -> &[(u16, u8)] {
+        &self.memory_writes
+    }
+
+    /// Where the instruction at `pc` accessing `addr` ends up in memory.
+    /// With the wraparound quirk, addresses past 0xFFF wrap back to 0x000, otherwise they're an error.
+    fn memory_address(&self, addr: usize, pc: u16) -> Result<usize, Chip8Error> {
+        if self.quirks.wrap_addresses {
+            Ok(addr & 0xFFF)
+        } else if addr < MEM_SIZE {
+            Ok(addr)
+        } else {
+            Err(Chip8Error::MemoryOutOfBounds { pc, address: addr })
+        }
+    }
+
+    /// Read a byte of memory on behalf of the instruction at `pc`, keeping track of it.
+    fn read_memory(&mut self, addr: usize, pc: u16) -> Result<u8, Chip8Error> {
+        let addr = self.memory_address(addr, pc)?;
+        self.memory_reads.push(addr as u16);
+
+        Ok(self.memory[addr])
+    }
+
+    /// Write a byte of memory on behalf of the instruction at `pc`, keeping track of it.
+    fn write_memory(&mut self, addr: usize, value: u8, pc: u16) -> Result<(), Chip8Error> {
+        let addr = self.memory_address(addr, pc)?;
+        self.memory[addr] = value;
+        self.memory_writes.push((addr as u16, value));
+
+        Ok(())
+    }
+
+    /// Snapshot the running machine into a `STATE_SIZE` long buffer.
+    /// Quirks are configuration, not state, so they are not saved.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(STATE_SIZE);
+        state.extend_from_slice(STATE_MAGIC);
+        state.push(STATE_VERSION);
+
+        state.extend_from_slice(&self.memory);
+        state.extend_from_slice(&(self.memory_end as u16).to_le_bytes());
+        state.extend_from_slice(&self.v);
+        state.extend_from_slice(&self.i.to_le_bytes());
+        state.extend_from_slice(&self.pc.to_le_bytes());
+        state.push(self.sp);
+        for return_addr in &self.stack {
+            state.extend_from_slice(&return_addr.to_le_bytes());
+        }
+        state.push(self.dt);
+        state.push(self.st);
+        state.extend(self.keyboard.iter().map(|&key_down| key_down as u8));
+        state.push(self.waiting.unwrap_or(0xFF));
+        state.push(self.key_wait_pressed.unwrap_or(0xFF));
+        state.extend_from_slice(&self.cycles.to_le_bytes());
+        state.push(self.vblank_wait as u8);
+        state.push(self.vblank as u8);
+        state.extend_from_slice(&self.cycle_budget.to_le_bytes());
+        state.push(self.halted as u8);
+        state.extend_from_slice(&self.screen);
+
+        state
+    }
+
+    /// Restore a snapshot made by `save_state`.
+    /// Fails without touching the machine if `state` isn't a valid snapshot.
+    pub fn load_state(&mut self, state: &[u8]) -> std::io::Result<()> {
+        use std::io::{Error, ErrorKind};
+
+        if state.len() != STATE_SIZE || &state[0..4] != STATE_MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a rusty-8 save state"));
+        }
+        if state[4] != STATE_VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, format!("unsupported save state version {}", state[4])));
+        }
+
+        let mut cursor = &state[5..];
+        let mut take = |len: usize| {
+            let (head, tail) = cursor.split_at(len);
+            cursor = tail;
+            head
+        };
+
+        self.memory.copy_from_slice(take(MEM_SIZE));
+        self.memory_end = (u16::from_le_bytes(take(2).try_into().unwrap()) as usize).min(MEM_SIZE);
+        self.v.copy_from_slice(take(16));
+        self.i = u16::from_le_bytes(take(2).try_into().unwrap());
+        self.pc = u16::from_le_bytes(take(2).try_into().unwrap());
+        self.sp = take(1)[0].min(STACK_SIZE as u8) & !1;
+        for (return_addr, bytes) in self.stack.iter_mut().zip(take(2 * STACK_CAPACITY).chunks(2)) {
+            *return_addr = u16::from_le_bytes(bytes.try_into().unwrap());
+        }
+        self.dt = take(1)[0];
+        self.st = take(1)[0];
+        for (key, &state) in self.keyboard.iter_mut().zip(take(16)) {
+            *key = state != 0;
+        }
+        self.waiting = match take(1)[0] {
+            0xFF => None,
+            reg_index => Some(reg_index & 0xF),
+        };
+        self.key_wait_pressed = match take(1)[0] {
+            0xFF => None,
+            key => Some(key & 0xF),
+        };
+        self.cycles = u64::from_le_bytes(take(8).try_into().unwrap());
+        self.vblank_wait = take(1)[0] != 0;
+        self.vblank = take(1)[0] != 0;
+        self.cycle_budget = i32::from_le_bytes(take(4).try_into().unwrap());
+        self.halted = take(1)[0] != 0;
+        self.key_events.clear();
+        self.screen.copy_from_slice(take(SCREEN_WIDTH * SCREEN_HEIGHT));
+        self.screen_updated = true;
+
+        Ok(())
     }
 
     // Function for debugging binary ROM data
     #[allow(dead_code)]
     pub fn dump_rom(&mut self) {
-        for addr in (PROG_OFFSET..self.memory_end).step_by(2) {
-            let instruction = self.fetch(addr);
+        for addr in ((self.memory_map.program_start as usize)..self.memory_end).step_by(2) {
+            let instruction = self.fetch(addr).unwrap_or(0);
             let opcode = decode(instruction);
 
             eprintln!("{:#03X}:\t{:04X}\t{:?}", addr, instruction, opcode);
@@ -130,7 +499,7 @@ impl Chip8 {
     #[allow(dead_code)]
     pub fn dump(&self) {
         let pc = self.pc as usize;
-        let instruction = self.fetch(pc);
+        let instruction = self.fetch(pc).unwrap_or(0);
         let opcode = decode(instruction);
         eprintln!("{:#03X}:\t{:04X}\t{:?}", pc, instruction,opcode);
 
@@ -142,27 +511,38 @@ impl Chip8 {
         for i in 8..16 {
             eprint!("v{:x}: {:02x} ", i, self.v[i]);
         }
-        eprint!("\n");
+        eprintln!();
 
         eprintln!("\ti: {:X}", self.i);
         eprintln!("\tsp: {:X}", self.sp);
+        eprintln!("\tstack: {:03X?}", self.call_stack());
         eprintln!("\tdt: {:X}", self.dt);
         eprintln!("\tst: {:X}", self.st);
     }
 
-    fn fetch(&self,addr: usize) -> u16 {
-        if addr < PROG_OFFSET || addr >= self.memory_end {
-            eprintln!("Invalid memory access at address {}.",addr);
-            std::process::exit(1);
-        }
+    /// Read the instruction at `addr`, which may be anywhere in memory (even code written at runtime).
+    /// With the wraparound quirk, addresses past 0xFFF wrap back to 0x000.
+    fn fetch(&self,addr: usize) -> Result<u16, Chip8Error> {
+        let (high, low) = if self.quirks.wrap_addresses {
+            (addr & 0xFFF, (addr + 1) & 0xFFF)
+        } else if addr + 1 < MEM_SIZE {
+            (addr, addr + 1)
+        } else {
+            return Err(Chip8Error::PcOutOfBounds { pc: addr as u16 });
+        };
 
-        // Safely unwrapping because bounds are checked above
-        u16::from_be_bytes(self.memory[addr..addr+2].try_into().unwrap())
+        Ok(u16::from_be_bytes([self.memory[high], self.memory[low]]))
     }
 
     /// Will update the internal chip8 timers, if they need to.
+    /// Must be called on 60Hz, as it also marks the start of a new frame (vertical blank).
     /// Returns true if a sound should be played after this tick.
     pub fn tick_timers(&mut self) -> bool {
+        if self.vblank_wait { // Let the stalled DXYN draw
+            self.vblank_wait = false;
+            self.vblank = true;
+        }
+
         if self.dt > 0 {
             self.dt -= 1;
         }
@@ -175,30 +555,238 @@ impl Chip8 {
         false
     }
 
-    /// Answer the chip8 with the first key pressed.
+    /// Query wether the chip8 is beeping, which it does while the sound timer is active.
+    pub fn is_beeping(&self) -> bool {
+        self.st > 0
+    }
+
+    /// Run one 60Hz frame followed by a timers update. With `Timing::Fixed` that's up to `ticks`
+    /// clock cycles, with `Timing::CosmacVip` as many instructions as fit the VIP's cycle budget.
+    /// Stops ticking early if the chip8 reaches the ROM's end, or waits for the display or a key.
+    /// Returns true if a sound should be played during this frame, or the error that stopped the ROM.
+    pub fn run_frame(&mut self, ticks: usize) -> Result<bool, Chip8Error> {
+        self.run_frame_observed(ticks, &mut ())
+    }
+
+    /// Same as `run_frame`, letting `observer` look at the chip8 around the frame and every tick.
+    pub fn run_frame_observed<O: TickObserver + ?Sized>(&mut self, ticks: usize, observer: &mut O) -> Result<bool, Chip8Error> {
+        observer.before_frame(self);
+        if self.timing == Timing::CosmacVip {
+            self.cycle_budget = self.cycle_budget.saturating_add(timing::VIP_FRAME_BUDGET as i32);
+        }
+
+        let mut screen_updated = false;
+        let mut ticked = 0;
+        loop {
+            let has_time_left = match self.timing {
+                Timing::Fixed => ticked < ticks,
+                Timing::CosmacVip => self.cycle_budget > 0,
+            };
+            if !has_time_left || self.finished_running() || self.vblank_wait {
+                break;
+            }
+
+            observer.before_tick(self);
+            self.tick()?;
+            observer.after_tick(self);
+            ticked += 1;
+            screen_updated |= self.screen_updated;
+
+            if self.waiting.is_some() { // Idle until the key wait is answered, in a later frame
+                break;
+            }
+        }
+        self.cycle_budget = self.cycle_budget.min(0); // Time left idling isn't carried over
+        self.screen_updated = screen_updated;
+
+        let is_beeping = self.tick_timers();
+        observer.after_frame(self);
+
+        Ok(is_beeping)
+    }
+
+    /// Number of clock cycles executed since boot.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Update the whole keyboard at once, queueing an event for every key that changed.
+    /// Meant for frontends that poll the keys' state every frame.
+    pub fn set_keys(&mut self, keys: &[bool; 16]) {
+        for (key_pos, &is_down) in keys.iter().enumerate() {
+            if is_down {
+                self.press_key(key_pos as u8);
+            } else {
+                self.release_key(key_pos as u8);
+            }
+        }
+    }
+
+    /// Press key [0..F]. Does nothing if it's already down.
+    pub fn press_key(&mut self, key: u8) {
+        self.set_key(key, true);
+    }
+
+    /// Release key [0..F]. Does nothing if it's already up.
+    pub fn release_key(&mut self, key: u8) {
+        self.set_key(key, false);
+    }
+
+    fn set_key(&mut self, key: u8, is_down: bool) {
+        let key = key & 0xF;
+        if self.keyboard[key as usize] == is_down {
+            return;
+        }
+        self.keyboard[key as usize] = is_down;
+
+        if self.key_events.len() == KEY_EVENTS_LEN {
+            self.key_events.pop_front();
+        }
+        self.key_events.push_back(KeyEvent { key, is_down, cycle: self.cycles });
+    }
+
+    /// Key presses/releases waiting to be consumed by FX0A, oldest first.
+    pub fn key_events(&self) -> &VecDeque<KeyEvent> {
+        &self.key_events
+    }
+
+    /// Answer the chip8 with the key that satisfied its wait.
     /// After this, chip8 will no longer be waiting.
     pub fn answer_key(&mut self, key_pos: u8) {
+        self.key_wait_pressed = None;
         if let Some(reg_index) = self.waiting.take() {
             self.v[reg_index as usize] = key_pos;
         }
     }
 
+    /// Feed queued key events to the pending FX0A. Only keys pressed after the wait started count.
+    /// The wait is answered on the key's press, or on its release if the quirk asks so.
+    fn update_key_wait(&mut self) {
+        while let Some(event) = self.key_events.pop_front() {
+            match self.key_wait_pressed {
+                None if event.is_down && self.quirks.key_wait_release => {
+                    self.key_wait_pressed = Some(event.key);
+                },
+                None if event.is_down => {
+                    self.answer_key(event.key);
+                    return;
+                },
+                Some(key) if key == event.key && !event.is_down => {
+                    self.answer_key(key);
+                    return;
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// Number of return addresses on the stack.
+    pub fn stack_depth(&self) -> usize {
+        self.sp as usize / 2
+    }
+
+    /// Return addresses on the stack, outermost call first.
+    pub fn call_stack(&self) -> Vec<u16> {
+        let depth = self.stack_depth();
+        if !self.stack_config.in_memory {
+            return self.stack[..depth].to_vec();
+        }
+
+        let start = self.memory_map.stack_start as usize;
+        self.memory[start..start + depth * 2]
+            .chunks(2)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    /// Push a return address. Fails if the stack is as deep as allowed, or full.
+    fn push_stack(&mut self, return_addr: u16, pc: u16) -> Result<(), Chip8Error> {
+        let depth = self.stack_depth();
+        let max_depth = self.stack_config.max_depth.unwrap_or(STACK_CAPACITY).min(STACK_CAPACITY);
+        if depth >= max_depth {
+            return Err(Chip8Error::StackOverflow { pc, stack: self.call_stack() });
+        }
+
+        if self.stack_config.in_memory {
+            let sp = self.memory_map.stack_start as usize + self.sp as usize;
+            let [low, high] = return_addr.to_le_bytes();
+            self.write_memory(sp, low, pc)?;
+            self.write_memory(sp + 1, high, pc)?;
+        } else {
+            self.stack[depth] = return_addr;
+        }
+        self.sp += 2;
+
+        Ok(())
+    }
+
+    /// Pop the most recent return address. Fails if the stack is empty.
+    fn pop_stack(&mut self, pc: u16) -> Result<u16, Chip8Error> {
+        if self.sp == 0 {
+            return Err(Chip8Error::StackUnderflow { pc });
+        }
+        self.sp -= 2;
+
+        let return_addr = if self.stack_config.in_memory {
+            let sp = self.memory_map.stack_start as usize + self.sp as usize;
+            u16::from_le_bytes([self.read_memory(sp, pc)?, self.read_memory(sp + 1, pc)?])
+        } else {
+            self.stack[self.stack_depth()]
+        };
+
+        Ok(return_addr)
+    }
+
+    /// Query wether chip8 is stuck on a jump to itself (`JP` to its own address), which can't
+    /// ever be left. Returns the address of that jump.
+    pub fn self_loop(&self) -> Option<u16> {
+        let instruction = self.fetch(self.pc as usize).ok()?;
+        let is_self_jump = instruction == 0x1000 | self.pc;
+
+        if is_self_jump && self.waiting.is_none() { Some(self.pc) } else { None }
+    }
+
     /// Query wether chip8 is done executing.
-    /// Returns true if program counter is at loaded ROM's end.
+    /// Returns true once the program ran into empty memory (a 0000 instruction).
     pub fn finished_running(&self) -> bool {
-        self.pc as usize == self.memory_end
+        self.halted
+    }
+
+    /// Run a single instruction, for debuggers stepping through the ROM. Stepping runs no frames, so a
+    /// DXYN stalled by the display wait quirk draws right away, as if the frame it waits for had started.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        self.tick()?;
+        if self.vblank_wait {
+            self.vblank_wait = false;
+            self.vblank = true;
+            self.tick()?;
+        }
+        Ok(())
     }
 
     /// Process a single cycle of chip8's loaded rom.
-    /// Will exit the program if an invalid memory address is reached.
-    /// # Panics
-    /// Panics if an invalid (unknown) instruction is decoded.
-    pub fn tick(&mut self) {
+    /// While waiting for a key (FX0A) no instruction is executed, but timers keep going.
+    /// Returns an error if the ROM overflows or underflows the stack, accesses memory out of bounds,
+    /// or runs an unknown instruction. Whatever the ROM does, it never panics.
+    pub fn tick(&mut self) -> Result<(), Chip8Error> {
         // 0. Internal state updating
         self.screen_updated = false;
+        self.cycles = self.cycles.wrapping_add(1);
+        self.last_instruction = None;
+        self.memory_reads.clear();
+        self.memory_writes.clear();
+
+        if self.waiting.is_some() {
+            self.update_key_wait();
+            return Ok(());
+        }
 
         // 1. Instruction Fetch
-        let instruction = self.fetch(self.pc as usize);
+        if self.quirks.wrap_addresses { // Even if the PC was set from outside, e.g. by a save state
+            self.pc &= 0xFFF;
+        }
+        let pc = self.pc;   // Address of this instruction, for errors
+        let instruction = self.fetch(self.pc as usize)?;
         self.pc += 2;	// Increment pc for next instruction
 
         // 2. Instruction Decode
@@ -208,24 +796,25 @@ impl Chip8 {
         let nibble = nibbles.3;						// n   / nibble
         let y = nibbles.2; 							// x   / Index of Vy register
         let x = nibbles.1; 							// y   / Index of Vx register
+        let (vx, next_pc) = (self.v[x], self.pc);   // For timing: before the instruction runs
+
+        // 3. Execute
         match nibbles {
+            (0x0, 0x0, 0x0, 0x0) => { // HALT - Ran into empty memory
+                self.pc = pc;
+                self.halted = true;
+            },
             (0x0, 0x0, 0xE, 0x0) => { // CLS - Clear the whole display (set all pixels to 0)
                 self.screen.fill(0);
             }
             (0x0, 0x0, 0xE, 0xE) => { // RET - Pop address in top of stack and jump to it
-                self.sp -= 2;
-
-                let sp = self.sp as usize;
-                self.pc = u16::from_le_bytes(self.memory[sp..sp+2].try_into().unwrap());
+                self.pc = self.pop_stack(pc)?;
             }
             (0x1, _, _, _) => { // JP addr - Jump to address
                 self.pc = address;
             },
             (0x2, _, _, _) => { // CALL addr - Push pc then jump to addr
-                let sp = self.sp as usize;
-                self.memory[sp..sp+2].copy_from_slice(&self.pc.to_le_bytes());
-                self.sp += 2;
-
+                self.push_stack(self.pc, pc)?;
                 self.pc = address;
             },
             (0x3, _, _, _) => { // SE Vx, kk - Skip next instruction if V[x] == kk (byte)
@@ -253,13 +842,16 @@ impl Chip8 {
                 self.v[x] = self.v[y];
             },
             (0x8, _, _, 0x1) => { // OR Vx, Vy - Set V[x] = V[x] | V[y]
-                self.v[x] = self.v[x] | self.v[y];
+                self.v[x] |= self.v[y];
+                if self.quirks.logic_resets_vf { self.v[0xf] = 0; }
             },
             (0x8, _, _, 0x2) => { // AND Vx, Vy - Set V[x] = V[x] & V[y]
-                self.v[x] = self.v[x] & self.v[y];
+                self.v[x] &= self.v[y];
+                if self.quirks.logic_resets_vf { self.v[0xf] = 0; }
             },
             (0x8, _, _, 0x3) => { // XOR Vx, Vy - Set V[x] = V[x] ^ V[y]
-                self.v[x] = self.v[x] ^ self.v[y];
+                self.v[x] ^= self.v[y];
+                if self.quirks.logic_resets_vf { self.v[0xf] = 0; }
             },
             (0x8, _, _, 0x4) => { // ADD Vx, Vy - Set V[x] = V[x] + V[y] -> Vf = 1 on carry
                 let (vx, vy) = (self.v[x], self.v[y]);
@@ -276,7 +868,7 @@ impl Chip8 {
                 self.v[0xf] = (!is_borrowing) as u8;
             },
             (0x8, _, _, 0x6) => { // SHR Vx - Right shift Vx by 1 -> Vf = 1 if least-significant bit is set
-                let vx = self.v[x];
+                let vx = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
                 let (rshifted, lsb) = (vx>>1, vx & 1);
 
                 self.v[x] = rshifted;
@@ -290,7 +882,7 @@ impl Chip8 {
                 self.v[0xf] = (!is_borrowing) as u8;
             },
             (0x8, _, _, 0xE) => { // SHL Vx - Left shift Vx by 1 -> Vf = 1 if most-significant bit is set
-                let vx = self.v[x];
+                let vx = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
                 let (lshifted, msb) = (vx<<1, (vx & 128)>>7);
 
                 self.v[x] = lshifted;
@@ -304,22 +896,31 @@ impl Chip8 {
             (0xA, _, _, _) => { // LD I, nnn - Set register I to nnn
                 self.i = address;
             },
-            (0xB, _, _, _) => { // JP V0, addr - Jump to memory[addr + V[0]]
-                self.pc = address + self.v[0] as u16;
+            (0xB, _, _, _) => { // JP V0, addr - Jump to memory[addr + V[0]] (or V[x], per quirk)
+                let offset = if self.quirks.jump_uses_vx { self.v[x] } else { self.v[0] };
+                self.pc = address + offset as u16;
             },
             (0xC, _, _, _) => { // RND Vx, kk - Set V[x] to random byte [0,255] AND kk (byte)
                 let rand_byte = nanorand::tls_rng().generate::<u8>();
                 self.v[x] = rand_byte & byte;
             },
             (0xD, _, _, _) => { // DRAW Vx, Vy, n - Draw n-length sprite at screen[x][y] - V[f] = 1 on collision
-                let sprite_start = self.i as usize;
-                let sprite_end = sprite_start + nibble;
-                let sprite = &self.memory[sprite_start..sprite_end];
-                
+                if self.quirks.display_wait && !self.vblank { // Stall on this DXYN until the next frame
+                    self.vblank_wait = true;
+                    self.pc -= 2;
+                    return Ok(());
+                }
+                self.vblank = false;
+
+                let mut sprite = [0; 15];
+                for (row, byte) in sprite[..nibble].iter_mut().enumerate() {
+                    *byte = self.read_memory(self.i as usize + row, pc)?;
+                }
+
                 let x_pos = self.v[x] as usize % 64;
                 let mut y_pos = self.v[y] as usize % 32;
                 let mut has_collided = 0; // No collision has occurred!
-                for byte in sprite {
+                for byte in &sprite[..nibble] {
                     let sprite_pos = x_pos + y_pos * SCREEN_WIDTH;
                     let limit_pos = (y_pos+1) * SCREEN_WIDTH; // pos where this row ends
 
@@ -342,15 +943,15 @@ impl Chip8 {
             },
             (0xE, _, 0x9, 0xE) => { // SKP Vx - Skip next instruction if key[Vx] IS pressed (key is down)
                 let vx = self.v[x];
-                let is_key_pressed = self.keyboard[vx as usize];
+                let is_key_pressed = self.keyboard[(vx & 0xF) as usize];
                 if is_key_pressed {
                     self.pc += 2;
                 }
             },
             (0xE, _, 0xA, 0x1) => { // SKNP Vx - Skip next instruction if key[Vx] is NOT pressed (key is up)
                 let vx = self.v[x];
-                let is_key_pressed = self.keyboard[vx as usize];
-                if is_key_pressed == false {
+                let is_key_pressed = self.keyboard[(vx & 0xF) as usize];
+                if !is_key_pressed {
                     self.pc += 2;
                 }
             },
@@ -358,7 +959,7 @@ impl Chip8 {
                 self.v[x] = self.dt;
             },
             (0xF, _, 0x0, 0xA) => { // LD Vx, Key - Set V[x] = key
-                self.keyboard.fill(false); // Clear keyboard state
+                self.key_events.clear(); // Keys that were already down don't count
                 self.waiting = Some(x as u8);
             },
             (0xF, _, 0x1, 0x5) => { // LD DT, Vx - Set Delay timer = V[x]
@@ -368,10 +969,10 @@ impl Chip8 {
                 self.st = self.v[x];
             },
             (0xF, _, 0x1, 0xE) => { // ADD I, Vx - Add Vx to register I
-                self.i += self.v[x] as u16;
+                self.i = self.i.wrapping_add(self.v[x] as u16);
             },
             (0xF, _, 0x2, 0x9) => { // LD I, Sprite[Vx] - Set I to address of sprite Vx
-                self.i = SPRITES_START as u16 + (self.v[x] * 5) as u16;
+                self.i = self.memory_map.font_start + (self.v[x] & 0xF) as u16 * 5; // Only the low digit
             },
             (0xF, _, 0x3, 0x3) => { // STORE BCD, Vx - Store BCD in memory[register I]
                 // BCD = Binary-coded Decimal -> https://en.wikipedia.org/wiki/Binary-coded_decimal
@@ -380,21 +981,44 @@ impl Chip8 {
                 let tenths = (vx / 10) % 10;
                 let ones = vx % 10;
 
-                self.memory[(self.i)   as usize] = hundreds;
-                self.memory[(self.i+1) as usize] = tenths;
-                self.memory[(self.i+2) as usize] = ones;
+                self.write_memory(self.i as usize,     hundreds, pc)?;
+                self.write_memory(self.i as usize + 1, tenths, pc)?;
+                self.write_memory(self.i as usize + 2, ones, pc)?;
             },
             (0xF, _, 0x5, 0x5) => { // STORE MEM[I..I+x], V[0..x] - Store starting from reg v0 into mem[register I..I+x]
-                let (start, end) = (self.i as usize, self.i as usize + x);
-                self.memory[start..=end].copy_from_slice(&self.v[0..=x]);
-                self.i += x as u16 + 1;
+                let start = self.i as usize;
+                for reg_index in 0..=x {
+                    self.write_memory(start + reg_index, self.v[reg_index], pc)?;
+                }
+                if self.quirks.memory_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
+                }
             },
             (0xF, _, 0x6, 0x5) => { // READ V[0..x], MEM[I..I+x] - Read starting from pos[register I] into v0..vx
-                let (start, end) = (self.i as usize, self.i as usize + x);
-                self.v[0..=x].copy_from_slice(&self.memory[start..=end]);
-                self.i += x as u16 + 1;
+                let start = self.i as usize;
+                for reg_index in 0..=x {
+                    self.v[reg_index] = self.read_memory(start + reg_index, pc)?;
+                }
+                if self.quirks.memory_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
+                }
             },
-            _ => panic!("Instruction not specified: {:04X} -- decoded -> {:?}", instruction, nibbles)
+            _ => return Err(Chip8Error::UnknownInstruction { pc, instruction }),
+        }
+
+        if self.quirks.wrap_addresses { // PC is 12 bits wide
+            self.pc &= 0xFFF;
         }
+
+        self.last_instruction = Some((pc, instruction));
+
+        // 4. Charge the instruction's cost to this frame
+        if self.timing == Timing::CosmacVip {
+            let is_skip = matches!(nibbles.0, 0x3 | 0x4 | 0x5 | 0x9 | 0xE);
+            let skipped = is_skip && self.pc == next_pc + 2;
+            self.cycle_budget = self.cycle_budget.saturating_sub(timing::vip_cycles(instruction, vx, skipped) as i32);
+        }
+
+        Ok(())
     }
 }
\ No newline at end of file
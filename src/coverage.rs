@@ -0,0 +1,327 @@
+// Coverage tells which parts of memory a ROM executed, read and wrote, e.g. to find the code a
+// test ROM never reached. Counts are kept per address, so runs can be merged by adding them up.
+//
+// Coverage files are text: the SHA-1 of the ROM they count, then a line per address that was touched at all:
+//   rom <sha1 in hex>
+//   <address in hex> <times executed> <times read> <times written>
+// Executions are counted on the instruction's first byte. Lines starting with # are comments.
+// Only coverage of the same ROM can be merged, the counts of another one would be meaningless.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::chip8::{Chip8, TickObserver, MEM_SIZE};
+use crate::disasm::disassemble;
+use crate::hash::sha1_hex;
+
+const HEATMAP_COLUMNS: usize = 64;  // Bytes per heatmap row, so the 4Kb make a square
+const HEAT_CHARS: &[u8] = b".:-=+*#%@";     // Text heatmap, from least to most accessed
+
+/// Per-address execution, read and write counts, recorded when passed to `Chip8::run_frame_observed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    rom: Option<String>,    // SHA-1 of the ROM counted, if known
+    executed: Vec<u64>,
+    read: Vec<u64>,
+    written: Vec<u64>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Coverage::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage { rom: None, executed: vec![0; MEM_SIZE], read: vec![0; MEM_SIZE], written: vec![0; MEM_SIZE] }
+    }
+
+    /// Coverage of `rom`, to be merged only with coverage of the same ROM.
+    pub fn for_rom(rom: &[u8]) -> Self {
+        Coverage { rom: Some(sha1_hex(rom)), ..Coverage::new() }
+    }
+
+    /// SHA-1 of the ROM counted, if known.
+    pub fn rom(&self) -> Option<&str> {
+        self.rom.as_deref()
+    }
+
+    /// Times the instruction starting at `addr` was executed.
+    pub fn executed(&self, addr: u16) -> u64 {
+        self.executed.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    /// Times the byte at `addr` was read as data (sprites, FX65, the stack).
+    pub fn read(&self, addr: u16) -> u64 {
+        self.read.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    /// Times the byte at `addr` was written.
+    pub fn written(&self, addr: u16) -> u64 {
+        self.written.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    /// Add the counts of another run. Fails if it ran another ROM, or one that isn't known.
+    pub fn merge(&mut self, other: &Coverage) -> Result<(), String> {
+        if self.rom != other.rom {
+            let rom = |rom: &Option<String>| rom.as_deref().unwrap_or("unknown").to_string();
+            return Err(format!("coverage of ROM {} can't be merged with coverage of ROM {}", rom(&other.rom), rom(&self.rom)));
+        }
+
+        let counts = [(&mut self.executed, &other.executed), (&mut self.read, &other.read), (&mut self.written, &other.written)];
+        for (ours, theirs) in counts {
+            for (ours, theirs) in ours.iter_mut().zip(theirs) {
+                *ours += theirs;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse a coverage file. On failure, returns a message pointing at the faulty line.
+    pub fn parse(text: &str) -> Result<Coverage, String> {
+        let mut coverage = Coverage::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let ["rom", hash] = fields.as_slice() {
+                if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("line {}: invalid ROM hash '{}'", line_index + 1, hash));
+                }
+                coverage.rom = Some(hash.to_ascii_lowercase());
+                continue;
+            }
+
+            let counts = match fields.as_slice() {
+                [addr, executed, read, written] => {
+                    let addr = usize::from_str_radix(addr, 16).ok().filter(|&addr| addr < MEM_SIZE);
+                    addr.zip(executed.parse().ok()).zip(read.parse().ok()).zip(written.parse().ok())
+                },
+                _ => None,
+            };
+
+            let (((addr, executed), read), written) = counts
+                .ok_or(format!("line {}: expected '<address> <executed> <read> <written>', got '{}'", line_index + 1, line))?;
+            coverage.executed[addr] = executed;
+            coverage.read[addr] = read;
+            coverage.written[addr] = written;
+        }
+
+        Ok(coverage)
+    }
+
+    /// Read and parse the coverage file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Coverage, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Coverage::parse(&text)
+    }
+
+    /// Write the counts in the coverage file format.
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "# rusty-8 coverage: address, then times executed, read and written")?;
+        if let Some(rom) = &self.rom {
+            writeln!(out, "rom {}", rom)?;
+        }
+        for addr in 0..MEM_SIZE {
+            let counts = (self.executed[addr], self.read[addr], self.written[addr]);
+            if counts != (0, 0, 0) {
+                writeln!(out, "{:03X} {} {} {}", addr, counts.0, counts.1, counts.2)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the counts into a new coverage file at `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()
+    }
+
+    /// Query wether the byte at `addr` belongs to an executed instruction, as its first or second byte.
+    fn is_code(&self, addr: usize) -> bool {
+        self.executed[addr] > 0 || (addr > 0 && self.executed[addr - 1] > 0)
+    }
+
+    /// Write the disassembly of `memory[range]`, every line marked with how it was used:
+    /// X executed, R read, W written. Instructions follow the executed code's alignment.
+    pub fn write_disassembly<W: Write>(&self, out: &mut W, memory: &[u8], range: Range<usize>) -> io::Result<()> {
+        let end = range.end.min(memory.len()).min(MEM_SIZE);
+        let mut addr = range.start;
+        while addr < end {
+            // Bytes that are data, between code at odd and even addresses, are shown one at a time
+            let len = if self.executed[addr] == 0 && addr + 1 < end && self.executed[addr + 1] > 0 { 1 } else { 2 };
+            let len = len.min(end - addr);
+            let range = addr..addr + len;
+
+            let marks: String = [
+                ('X', range.clone().any(|addr| self.is_code(addr))),
+                ('R', range.clone().any(|addr| self.read[addr] > 0)),
+                ('W', range.clone().any(|addr| self.written[addr] > 0)),
+            ].iter().map(|&(mark, is_set)| if is_set { mark } else { '.' }).collect();
+
+            let line = if len == 2 {
+                let word = u16::from_be_bytes([memory[addr], memory[addr + 1]]);
+                let runs = match self.executed[addr] {
+                    0 => String::new(),
+                    runs => format!("{} runs", runs),
+                };
+                format!("{}  {:03X}  {:04X}  {:<20}{}", marks, addr, word, disassemble(word), runs)
+            } else {
+                format!("{}  {:03X}  {:02X}    DB {:#04X}", marks, addr, memory[addr], memory[addr])
+            };
+            writeln!(out, "{}", line.trim_end())?;
+
+            addr += len;
+        }
+
+        Ok(())
+    }
+
+    /// How much the byte at `addr` was used, between 0 (untouched) and 1 (the most used byte).
+    /// Logarithmic, so hot loops don't make everything else look cold.
+    fn heat(&self, addr: usize, max_accesses: u64) -> f64 {
+        let accesses = self.accesses(addr);
+        if accesses == 0 {
+            return 0.0;
+        }
+
+        (accesses as f64).ln_1p() / (max_accesses as f64).ln_1p()
+    }
+
+    fn accesses(&self, addr: usize) -> u64 {
+        let executed = if self.is_code(addr) { self.executed[addr].max(self.executed[addr.saturating_sub(1)]) } else { 0 };
+        executed + self.read[addr] + self.written[addr]
+    }
+
+    /// Write a text heatmap of the whole memory, a character per byte: blank when untouched,
+    /// then `.:-=+*#%@` from least to most accessed.
+    pub fn write_heatmap<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let max_accesses = (0..MEM_SIZE).map(|addr| self.accesses(addr)).max().unwrap_or(0);
+
+        for row_start in (0..MEM_SIZE).step_by(HEATMAP_COLUMNS) {
+            let row: String = (row_start..row_start + HEATMAP_COLUMNS).map(|addr| {
+                let heat = self.heat(addr, max_accesses);
+                if heat == 0.0 {
+                    ' '
+                } else {
+                    let level = (heat * (HEAT_CHARS.len() - 1) as f64).round() as usize;
+                    HEAT_CHARS[level] as char
+                }
+            }).collect();
+
+            writeln!(out, "{:03X} |{}|", row_start, row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write how much of memory, and of the ROM in `rom`, was used.
+    fn write_summary<W: Write>(&self, out: &mut W, rom: Range<usize>) -> io::Result<()> {
+        let (code, data) = (0..MEM_SIZE).fold((0, 0), |(code, data), addr| {
+            (code + self.is_code(addr) as usize, data + (self.read[addr] > 0 || self.written[addr] > 0) as usize)
+        });
+        let rom_len = rom.len();
+        let rom_code = rom.filter(|&addr| addr < MEM_SIZE && self.is_code(addr)).count();
+
+        writeln!(out, "{} bytes executed, {} bytes read or written as data", code, data)?;
+        writeln!(out, "{} of {} ROM bytes executed ({:.1}%)", rom_code, rom_len, 100.0 * rom_code as f64 / rom_len.max(1) as f64)
+    }
+
+    /// Write the text report: a heatmap of the whole memory, then the disassembly of the ROM in `memory[rom]`.
+    pub fn write_report<W: Write>(&self, out: &mut W, memory: &[u8], rom: Range<usize>) -> io::Result<()> {
+        self.write_summary(out, rom.clone())?;
+
+        writeln!(out, "\nHeatmap, {} bytes a row:", HEATMAP_COLUMNS)?;
+        self.write_heatmap(out)?;
+
+        writeln!(out, "\nDisassembly (X executed, R read, W written):")?;
+        self.write_disassembly(out, memory, rom)
+    }
+
+    /// Write the HTML report: a colored heatmap of the whole memory (red executed, green read,
+    /// blue written), then the disassembly of the ROM in `memory[rom]`.
+    pub fn write_html_report<W: Write>(&self, out: &mut W, memory: &[u8], rom: Range<usize>) -> io::Result<()> {
+        let max_accesses = (0..MEM_SIZE).map(|addr| self.accesses(addr)).max().unwrap_or(0);
+
+        let mut heatmap = String::new();
+        for row_start in (0..MEM_SIZE).step_by(HEATMAP_COLUMNS) {
+            let _ = write!(heatmap, "<tr><th>{:03X}</th>", row_start);
+            for addr in row_start..row_start + HEATMAP_COLUMNS {
+                let heat = 55.0 + 200.0 * self.heat(addr, max_accesses);
+                let color = |is_set: bool| if is_set { heat as u8 } else { 0 };
+                let (red, green, blue) = (color(self.is_code(addr)), color(self.read[addr] > 0), color(self.written[addr] > 0));
+
+                let _ = write!(heatmap, "<td style=\"background:rgb({},{},{})\" title=\"{:03X}: executed {}, read {}, written {}\"></td>",
+                    red, green, blue, addr, self.executed[addr], self.read[addr], self.written[addr]);
+            }
+            heatmap.push_str("</tr>\n");
+        }
+
+        let mut disassembly = Vec::new();
+        self.write_summary(&mut disassembly, rom.clone())?;
+        writeln!(disassembly, "\nDisassembly (X executed, R read, W written):")?;
+        self.write_disassembly(&mut disassembly, memory, rom)?;
+        let disassembly = String::from_utf8_lossy(&disassembly).replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+
+        write!(out, "\
+<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>rusty-8 coverage</title>
+<style>
+body {{ font-family: monospace; background: #111; color: #ddd; }}
+table {{ border-collapse: collapse; }}
+th {{ font-weight: normal; padding-right: 8px; }}
+td {{ width: 10px; height: 10px; padding: 0; border: 1px solid #111; }}
+</style>
+</head>
+<body>
+<h1>rusty-8 coverage</h1>
+<p>A square per byte: red executed, green read, blue written. Brighter is more accessed; hover for counts.</p>
+<table>
+{}</table>
+<pre>{}</pre>
+</body>
+</html>
+", heatmap, disassembly)
+    }
+
+    /// Write a report into a new file at `path`: HTML if it ends in `.html`, text otherwise.
+    pub fn save_report<P: AsRef<Path>>(&self, path: P, memory: &[u8], rom: Range<usize>) -> io::Result<()> {
+        let is_html = path.as_ref().extension().is_some_and(|extension| extension == "html" || extension == "htm");
+        let mut out = BufWriter::new(File::create(path)?);
+
+        if is_html {
+            self.write_html_report(&mut out, memory, rom)?;
+        } else {
+            self.write_report(&mut out, memory, rom)?;
+        }
+        out.flush()
+    }
+}
+
+impl TickObserver for Coverage {
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        if let Some((pc, _)) = chip8.last_instruction() {
+            self.executed[pc as usize % MEM_SIZE] += 1;
+        }
+        for &addr in chip8.memory_reads() {
+            self.read[addr as usize] += 1;
+        }
+        for &(addr, _) in chip8.memory_writes() {
+            self.written[addr as usize] += 1;
+        }
+    }
+}
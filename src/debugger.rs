@@ -0,0 +1,375 @@
+// The debugger view shows the chip8 screen next to its memory and registers, updated every frame,
+// and lets the user poke bytes and registers while the ROM is paused.
+//
+//   +----------+  V0 00 V8 00  PC 200  STACK  SPRITE
+//   |  screen  |  ...          I  000  ...    (bytes at I)
+//   +----------+  V7 00 VF 00  ...
+//   200  00 E0 A2 2A ...       (cursor instruction, RAM search and cheats)
+//   ...
+//   PAUSED  F1 RUN  F2 STEP ...
+
+use rusty_8::{Chip8, Cheats, RamSearch, SearchFilter};
+use rusty_8::cheats::{Cheat, CheatTarget};
+use rusty_8::chip8::{MEM_SIZE, SCREEN_WIDTH, SCREEN_HEIGHT};
+use rusty_8::disasm::disassemble;
+
+use crate::canvas::{Canvas, CHAR_WIDTH, LINE_HEIGHT};
+
+pub const DEBUG_WIDTH:  usize = 320;    // Debugger view width, in pixels
+pub const DEBUG_HEIGHT: usize = 200;    // Debugger view height, in pixels
+
+const HEX_ROWS: usize = 16;             // Rows of the memory hex view, 16 bytes each
+const HEX_TOP: usize = 76;              // y of the memory hex view
+const REGISTER_COUNT: usize = 21;       // V0 to VF, then I, PC, SP, DT and ST
+const STACK_LINES: usize = 8;           // Return addresses shown, innermost first
+const SPRITE_ROWS: usize = 15;          // Bytes shown from I, the tallest DXYN sprite
+const CANDIDATE_LINES: usize = 5;       // RAM search candidates shown
+const CHEAT_LINES: usize = 3;           // Cheats shown
+const SIDE_CHARS: usize = 24;           // Characters fitting right of the hex view
+
+const TEXT: u8 = 220;
+const LABEL: u8 = 120;
+const CURSOR: u8 = 255;
+const CURSOR_UNFOCUSED: u8 = 130;
+const PC_MARK: u8 = 90;
+const I_MARK: u8 = 50;
+const FRAME: u8 = 60;
+
+/// Keys the debugger handles, independent of the windowing library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugKey {
+    Digit(u8),  // Hex digit [0..F], shifted into the selected byte or register
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Tab,        // Switch between the memory and register views
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Memory,
+    Register(usize),    // Index as in REGISTER_COUNT
+}
+
+/// State of the debugger view: what's selected, wether the ROM is paused, and the RAM search.
+pub struct Debugger {
+    pub paused: bool,
+    cursor: u16,        // Selected memory address
+    scroll: u16,        // Address of the hex view's first row
+    focus: Focus,
+    search: Option<RamSearch>,
+    canvas: Canvas,
+}
+
+impl Debugger {
+    pub fn new(chip8: &Chip8) -> Self {
+        let cursor = chip8.memory_map().program_start;
+        Debugger {
+            paused: false,
+            cursor,
+            scroll: cursor & !0xF,
+            focus: Focus::Memory,
+            search: None,
+            canvas: Canvas::new(DEBUG_WIDTH, DEBUG_HEIGHT),
+        }
+    }
+
+    /// Handle a key press. Moving around works anytime, poking only while paused.
+    pub fn key(&mut self, key: DebugKey, chip8: &mut Chip8) {
+        match (self.focus, key) {
+            (_, DebugKey::Tab) => {
+                self.focus = match self.focus {
+                    Focus::Memory => Focus::Register(0),
+                    Focus::Register(_) => Focus::Memory,
+                };
+            },
+            (Focus::Memory, DebugKey::Digit(digit)) if self.paused => {
+                let byte = &mut chip8.memory_mut()[self.cursor as usize];
+                *byte = *byte << 4 | digit;
+            },
+            (Focus::Register(index), DebugKey::Digit(digit)) if self.paused => poke_register(chip8, index, digit),
+            (Focus::Memory, _) => {
+                let offset: i32 = match key {
+                    DebugKey::Up => -16,
+                    DebugKey::Down => 16,
+                    DebugKey::Left => -1,
+                    DebugKey::Right => 1,
+                    DebugKey::PageUp => -256,
+                    DebugKey::PageDown => 256,
+                    _ => 0,
+                };
+                self.move_cursor((self.cursor as i32 + offset).rem_euclid(MEM_SIZE as i32) as u16);
+            },
+            (Focus::Register(index), _) => {
+                let index = match key {
+                    DebugKey::Up | DebugKey::Left => index + REGISTER_COUNT - 1,
+                    DebugKey::Down | DebugKey::Right => index + 1,
+                    _ => index,
+                };
+                self.focus = Focus::Register(index % REGISTER_COUNT);
+            },
+        }
+    }
+
+    /// Select memory at `addr`, scrolling just enough to keep it in view.
+    fn move_cursor(&mut self, addr: u16) {
+        self.cursor = addr;
+
+        let row = self.cursor & !0xF;
+        let last_row = self.scroll as usize + (HEX_ROWS - 1) * 16;
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row as usize > last_row {
+            self.scroll = row - (HEX_ROWS as u16 - 1) * 16;
+        }
+    }
+
+    /// Narrow down the RAM search with `filter`, or start a new one if None.
+    pub fn search(&mut self, chip8: &Chip8, filter: Option<SearchFilter>) {
+        match (&mut self.search, filter) {
+            (Some(search), Some(filter)) => { search.filter(chip8, filter); },
+            _ => self.search = Some(RamSearch::new(chip8)),
+        }
+    }
+
+    /// Select the next address the RAM search found, after the cursor.
+    pub fn next_candidate(&mut self) {
+        let candidates = match &self.search {
+            Some(search) if !search.candidates().is_empty() => search.candidates(),
+            _ => return,
+        };
+
+        let next = candidates.iter().find(|&&addr| addr > self.cursor).unwrap_or(&candidates[0]);
+        self.focus = Focus::Memory;
+        self.move_cursor(*next);
+    }
+
+    /// Freeze the selected byte or register at its current value, or unfreeze it if it was.
+    /// Returns wether `cheats` changed (I, PC and SP can't be frozen).
+    pub fn toggle_freeze(&self, chip8: &Chip8, cheats: &mut Cheats) -> bool {
+        let target = match self.focus {
+            Focus::Memory => CheatTarget::Memory(self.cursor),
+            Focus::Register(index @ 0..=15) => CheatTarget::V(index as u8),
+            Focus::Register(19) => CheatTarget::DelayTimer,
+            Focus::Register(20) => CheatTarget::SoundTimer,
+            Focus::Register(_) => return false,
+        };
+
+        if cheats.remove(target).is_none() {
+            let name = format!("Cheat {}", cheats.cheats().len() + 1);  // Renamed by editing the cheat file
+            cheats.add(Cheat { name, target, value: target.read(chip8) });
+        }
+        true
+    }
+
+    /// Draw the view of `chip8` and its `cheats`, as a DEBUG_WIDTH x DEBUG_HEIGHT grayscale image.
+    pub fn render(&mut self, chip8: &Chip8, cheats: &Cheats) -> &[u8] {
+        self.canvas.clear();
+        self.draw_screen(chip8);
+        self.draw_registers(chip8);
+        self.draw_stack(chip8);
+        self.draw_sprite(chip8);
+        self.draw_memory(chip8, cheats);
+        self.draw_search(chip8);
+        self.draw_cheats(cheats);
+        self.draw_status();
+
+        &self.canvas.pixels
+    }
+
+    fn draw_screen(&mut self, chip8: &Chip8) {
+        self.canvas.fill_rect(3, 3, SCREEN_WIDTH * 2 + 2, SCREEN_HEIGHT * 2 + 2, FRAME);
+        self.canvas.fill_rect(4, 4, SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, 0);
+        self.canvas.draw_image(4, 4, &chip8.screen, SCREEN_WIDTH, 2, 255);
+    }
+
+    fn draw_registers(&mut self, chip8: &Chip8) {
+        let regs = chip8.registers();
+        let values = register_values(chip8);
+        let selected = match self.focus {
+            Focus::Register(index) => Some(index),
+            Focus::Memory => None,
+        };
+
+        for (index, value) in values.iter().enumerate() {
+            let (name, width) = match index {
+                0..=15 => (format!("V{:X}", index), 2),
+                16 => ("I".to_string(), 3),
+                17 => ("PC".to_string(), 3),
+                18 => ("SP".to_string(), 1),
+                19 => ("DT".to_string(), 2),
+                _ => ("ST".to_string(), 2),
+            };
+            let (x, y) = match index {
+                0..=15 => (140 + (index / 8) * 36, 4 + (index % 8) * LINE_HEIGHT),
+                _ => (212, 4 + (index - 16) * LINE_HEIGHT),
+            };
+
+            let x = self.canvas.draw_text(x, y, &name, LABEL).max(x + 3 * CHAR_WIDTH);
+            let shade = if selected == Some(index) {
+                self.canvas.fill_rect(x - 1, y - 1, width * CHAR_WIDTH + 1, LINE_HEIGHT, CURSOR);
+                0
+            } else {
+                TEXT
+            };
+            self.canvas.draw_text(x, y, &format!("{:01$X}", value, width), shade);
+        }
+
+        // Instruction about to run, under the registers
+        let opcode = read_word(chip8, regs.pc);
+        self.canvas.draw_text(140, 4 + 9 * LINE_HEIGHT, &format!("{:03X}  {:04X}  {}", regs.pc, opcode, disassemble(opcode)), TEXT);
+    }
+
+    fn draw_stack(&mut self, chip8: &Chip8) {
+        let (x, y) = (248, 4);
+        self.canvas.draw_text(x, y, "STACK", LABEL);
+
+        let stack = chip8.call_stack();
+        for (line, addr) in stack.iter().rev().take(STACK_LINES).enumerate() {
+            self.canvas.draw_text(x, y + (line + 1) * LINE_HEIGHT, &format!("{:03X}", addr), TEXT);
+        }
+        if stack.len() > STACK_LINES {
+            self.canvas.draw_text(x, y + (STACK_LINES + 1) * LINE_HEIGHT, &format!("+{}", stack.len() - STACK_LINES), LABEL);
+        }
+    }
+
+    fn draw_sprite(&mut self, chip8: &Chip8) {
+        let (x, y) = (284, 4);
+        self.canvas.draw_text(x - 4, y, "SPRITE", LABEL);
+
+        let i = chip8.registers().i as usize;
+        let mut pixels = [0u8; 8 * SPRITE_ROWS];
+        for row in 0..SPRITE_ROWS {
+            let byte = chip8.memory()[(i + row) % MEM_SIZE];
+            for column in 0..8 {
+                pixels[row * 8 + column] = byte & (0x80 >> column);
+            }
+        }
+
+        let top = y + LINE_HEIGHT;
+        self.canvas.fill_rect(x, top, 16, SPRITE_ROWS * 2, I_MARK);
+        self.canvas.draw_image(x, top, &pixels, 8, 2, 255);
+    }
+
+    fn draw_memory(&mut self, chip8: &Chip8, cheats: &Cheats) {
+        let regs = chip8.registers();
+        let memory = chip8.memory();
+        let cursor_shade = if self.focus == Focus::Memory { CURSOR } else { CURSOR_UNFOCUSED };
+
+        for row in 0..HEX_ROWS {
+            let row_addr = (self.scroll as usize + row * 16) % MEM_SIZE;
+            let y = HEX_TOP + row * LINE_HEIGHT;
+            let mut x = self.canvas.draw_text(4, y, &format!("{:03X}", row_addr), LABEL) + CHAR_WIDTH;
+
+            for (addr, byte) in memory.iter().enumerate().skip(row_addr).take(16) {
+                let background = if addr == self.cursor as usize {
+                    Some(cursor_shade)
+                } else if addr == regs.pc as usize || addr == regs.pc as usize + 1 {
+                    Some(PC_MARK)
+                } else if addr == regs.i as usize {
+                    Some(I_MARK)
+                } else {
+                    None
+                };
+
+                let shade = match background {
+                    Some(background) => {
+                        self.canvas.fill_rect(x - 1, y - 1, 2 * CHAR_WIDTH + 1, LINE_HEIGHT, background);
+                        if background == cursor_shade { 0 } else { TEXT }
+                    },
+                    None => TEXT,
+                };
+                self.canvas.draw_text(x, y, &format!("{:02X}", byte), shade);
+                if cheats.get(CheatTarget::Memory(addr as u16)).is_some() { // Underline frozen bytes
+                    self.canvas.fill_rect(x, y + LINE_HEIGHT - 2, 2 * CHAR_WIDTH - 1, 1, TEXT);
+                }
+                x += 3 * CHAR_WIDTH;
+            }
+        }
+
+        // The selected address, and the instruction it would hold
+        let x = 220;
+        let opcode = read_word(chip8, self.cursor);
+        self.canvas.draw_text(x, HEX_TOP, &format!("ADDR {:03X}", self.cursor), LABEL);
+        self.canvas.draw_text(x, HEX_TOP + LINE_HEIGHT, &format!("BYTE {:02X}", memory[self.cursor as usize]), TEXT);
+        self.canvas.draw_text(x, HEX_TOP + 2 * LINE_HEIGHT, &format!("WORD {:04X}", opcode), TEXT);
+        self.canvas.draw_text(x, HEX_TOP + 3 * LINE_HEIGHT, &disassemble(opcode), TEXT);
+    }
+
+    fn draw_search(&mut self, chip8: &Chip8) {
+        let (x, y) = (220, HEX_TOP + 5 * LINE_HEIGHT);
+        let search = match &self.search {
+            Some(search) => search,
+            None => {
+                self.canvas.draw_text(x, y, "F5 RAM SEARCH", LABEL);
+                return;
+            },
+        };
+
+        let candidates = search.candidates();
+        self.canvas.draw_text(x, y, &format!("SEARCH {}", candidates.len()), LABEL);
+        for (line, addr) in candidates.iter().take(CANDIDATE_LINES).enumerate() {
+            let text = format!("{:03X} {:02X}", addr, chip8.memory()[*addr as usize]);
+            self.canvas.draw_text(x, y + (line + 1) * LINE_HEIGHT, &text, TEXT);
+        }
+    }
+
+    fn draw_cheats(&mut self, cheats: &Cheats) {
+        let (x, y) = (220, HEX_TOP + (CANDIDATE_LINES + 7) * LINE_HEIGHT);
+        self.canvas.draw_text(x, y, &format!("CHEATS {}", cheats.cheats().len()), LABEL);
+
+        for (line, cheat) in cheats.cheats().iter().take(CHEAT_LINES).enumerate() {
+            let text: String = format!("{}={:02X} {}", cheat.target, cheat.value, cheat.name).chars().take(SIDE_CHARS).collect();
+            self.canvas.draw_text(x, y + (line + 1) * LINE_HEIGHT, &text, TEXT);
+        }
+    }
+
+    fn draw_status(&mut self) {
+        let y = DEBUG_HEIGHT - LINE_HEIGHT;
+        let (status, help) = if self.paused {
+            ("PAUSED", "F1 RUN  F2 STEP  F3 FRAME  TAB FOCUS  0-F POKE")
+        } else {
+            ("RUNNING", "F1 PAUSE  TAB FOCUS  F5-F9 SEARCH  F11 FREEZE")
+        };
+
+        let x = self.canvas.draw_text(4, y, status, TEXT);
+        self.canvas.draw_text(x + 2 * CHAR_WIDTH, y, help, LABEL);
+    }
+}
+
+/// The 2 bytes at `addr`, as an instruction.
+fn read_word(chip8: &Chip8, addr: u16) -> u16 {
+    let memory = chip8.memory();
+    u16::from_be_bytes([memory[addr as usize % MEM_SIZE], memory[(addr as usize + 1) % MEM_SIZE]])
+}
+
+/// Value of each register, as indexed by the register view. SP is the stack depth.
+fn register_values(chip8: &Chip8) -> [u16; REGISTER_COUNT] {
+    let regs = chip8.registers();
+    let mut values = [0; REGISTER_COUNT];
+    for (value, v) in values.iter_mut().zip(regs.v.iter()) {
+        *value = *v as u16;
+    }
+    values[16..].copy_from_slice(&[regs.i, regs.pc, regs.sp as u16 / 2, regs.dt as u16, regs.st as u16]);
+
+    values
+}
+
+/// Shift the hex `digit` into register `index`, dropping the digit that no longer fits.
+fn poke_register(chip8: &mut Chip8, index: usize, digit: u8) {
+    let mut regs = chip8.registers();
+    let shift = |value: u16, mask: u16| (value << 4 | digit as u16) & mask;
+
+    match index {
+        0..=15 => regs.v[index] = shift(regs.v[index] as u16, 0xFF) as u8,
+        16 => regs.i = shift(regs.i, 0xFFF),
+        17 => regs.pc = shift(regs.pc, 0xFFF),
+        18 => regs.sp = digit * 2,  // Depth, a single digit. Clamped to the stack's size
+        19 => regs.dt = shift(regs.dt as u16, 0xFF) as u8,
+        _ => regs.st = shift(regs.st as u16, 0xFF) as u8,
+    }
+    chip8.set_registers(regs);
+}
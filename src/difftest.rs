@@ -0,0 +1,293 @@
+// Differential testing runs a ROM next to a trace made by another emulator, and stops at the first
+// step where the two disagree, e.g. to check how a quirk is implemented.
+//
+// Reference traces are text, one line per executed instruction, giving the machine state right
+// BEFORE that instruction runs. A line is a list of KEY=VALUE fields, values in hex:
+//
+//   PC=200 OP=6E05 I=000 SP=0 DT=00 ST=00 V=00000000000000000000000000000000
+//   PC=202 OP=6500 VE=05
+//   PC=20A OP=DAB1 I=30C VA=00 VB=06 M30C=80
+//
+//   PC      Address of the instruction
+//   OP      The instruction, as stored in memory
+//   I       Index register
+//   SP      Stack depth, in return addresses (not bytes)
+//   DT, ST  Delay and sound timers
+//   V       V0 to VF, 2 digits each
+//   V0..VF  A single register
+//   M<addr> Memory bytes from <addr> on, 2 digits each
+//
+// Only the fields given are compared, so emulators that don't know some of them can leave them out.
+// Blank lines and lines starting with # are ignored.
+//
+// Random numbers (CXKK) can't match between emulators, so Vx takes the reference's value after one.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::chip8::{Chip8, Registers, TickObserver, MEM_SIZE};
+
+/// A machine state from a reference trace. Missing fields aren't compared.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReferenceState {
+    pub line: usize,        // Line of the trace it comes from, starting at 1
+    pub pc: Option<u16>,
+    pub opcode: Option<u16>,
+    pub i: Option<u16>,
+    pub sp: Option<u8>,     // Stack depth
+    pub dt: Option<u8>,
+    pub st: Option<u8>,
+    pub v: [Option<u8>; 16],
+    pub memory: Vec<(u16, Vec<u8>)>,    // Start address and the bytes from there
+}
+
+impl ReferenceState {
+    /// Parse a line of a reference trace.
+    pub fn parse(line: &str) -> Result<ReferenceState, String> {
+        let mut state = ReferenceState::default();
+
+        for field in line.split_whitespace() {
+            let (key, value) = field.split_once('=').ok_or(format!("expected KEY=VALUE, got '{}'", field))?;
+            let invalid = || format!("invalid value for {}: '{}'", key, value);
+            let hex = |max: u16| u16::from_str_radix(value, 16).ok().filter(|&value| value <= max).ok_or_else(invalid);
+
+            match key {
+                "PC" => state.pc = Some(hex(0xFFFF)?),
+                "OP" => state.opcode = Some(hex(0xFFFF)?),
+                "I" => state.i = Some(hex(0xFFFF)?),
+                "SP" => state.sp = Some(hex(0xFF)? as u8),
+                "DT" => state.dt = Some(hex(0xFF)? as u8),
+                "ST" => state.st = Some(hex(0xFF)? as u8),
+                "V" => {
+                    let bytes = parse_bytes(value).filter(|bytes| bytes.len() == 16).ok_or_else(invalid)?;
+                    for (reg, byte) in state.v.iter_mut().zip(bytes) {
+                        *reg = Some(byte);
+                    }
+                },
+                _ if key.len() == 2 && key.starts_with('V') => {
+                    let reg_index = usize::from_str_radix(&key[1..], 16).map_err(|_| format!("unknown field '{}'", key))?;
+                    state.v[reg_index] = Some(hex(0xFF)? as u8);
+                },
+                _ if key.starts_with('M') => {
+                    let addr = u16::from_str_radix(&key[1..], 16).map_err(|_| format!("unknown field '{}'", key))?;
+                    let bytes = parse_bytes(value).ok_or_else(invalid)?;
+                    if addr as usize + bytes.len() > MEM_SIZE {
+                        return Err(format!("{} goes past the end of memory", key));
+                    }
+                    state.memory.push((addr, bytes));
+                },
+                _ => return Err(format!("unknown field '{}'", key)),
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Compare against `ours`, the state of the chip8 before it ran the same step.
+    /// Returns a row per field, ours and the reference's, with wether they differ.
+    fn compare(&self, ours: &Snapshot) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut row = |name: String, ours: String, theirs: Option<String>| {
+            let differs = theirs.as_ref().is_some_and(|theirs| *theirs != ours);
+            rows.push(Row { name, ours, theirs: theirs.unwrap_or_else(|| "-".to_string()), differs });
+        };
+        let regs = &ours.registers;
+
+        row("PC".to_string(), format!("{:03X}", regs.pc), self.pc.map(|pc| format!("{:03X}", pc)));
+        row("OP".to_string(), format!("{:04X}", ours.opcode), self.opcode.map(|op| format!("{:04X}", op)));
+        row("I".to_string(), format!("{:03X}", regs.i), self.i.map(|i| format!("{:03X}", i)));
+        row("SP".to_string(), format!("{:X}", regs.sp / 2), self.sp.map(|sp| format!("{:X}", sp)));
+        row("DT".to_string(), format!("{:02X}", regs.dt), self.dt.map(|dt| format!("{:02X}", dt)));
+        row("ST".to_string(), format!("{:02X}", regs.st), self.st.map(|st| format!("{:02X}", st)));
+        for (reg_index, (ours, theirs)) in regs.v.iter().zip(self.v.iter()).enumerate() {
+            row(format!("V{:X}", reg_index), format!("{:02X}", ours), theirs.map(|theirs| format!("{:02X}", theirs)));
+        }
+        for ((addr, theirs), ours) in self.memory.iter().zip(ours.memory.iter()) {
+            for (offset, (ours, theirs)) in ours.iter().zip(theirs.iter()).enumerate() {
+                row(format!("M{:03X}", *addr as usize + offset), format!("{:02X}", ours), Some(format!("{:02X}", theirs)));
+            }
+        }
+
+        rows
+    }
+}
+
+fn parse_bytes(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() & 1 != 0 || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2)
+        .map(|pos| u8::from_str_radix(&hex[pos..pos + 2], 16).ok())
+        .collect()
+}
+
+/// Parse a whole reference trace.
+pub fn parse_reference(trace: &str) -> Result<Vec<ReferenceState>, String> {
+    let mut states = Vec::new();
+
+    for (line_index, line) in trace.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut state = ReferenceState::parse(line).map_err(|e| format!("line {}: {}", line_index + 1, e))?;
+        state.line = line_index + 1;
+        states.push(state);
+    }
+
+    Ok(states)
+}
+
+/// Our side of a step: what the chip8 looked like before running it.
+struct Snapshot {
+    registers: Registers,
+    opcode: u16,
+    memory: Vec<Vec<u8>>,   // Bytes at the reference state's memory fields
+}
+
+/// A field of both states, as shown side by side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub name: String,
+    pub ours: String,
+    pub theirs: String,     // "-" when the reference doesn't say
+    pub differs: bool,
+}
+
+/// First step where the chip8 and the reference disagreed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub step: u64,          // Instructions that matched before this one
+    pub line: usize,        // Line of the reference trace
+    pub cycle: u64,         // Chip8 clock cycle of the step
+    pub stopped: bool,      // The ROM stopped running (halted or self-looped) while the reference went on
+    pub rows: Vec<Row>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.stopped {
+            writeln!(f, "Stopped at step {} (reference line {}, cycle {}), while the reference goes on:", self.step, self.line, self.cycle)?;
+        } else {
+            writeln!(f, "Diverged at step {} (reference line {}, cycle {}), before running:", self.step, self.line, self.cycle)?;
+        }
+        writeln!(f, "\t{:<6}{:<10}reference", "", "rusty-8")?;
+        for row in &self.rows {
+            let line = format!("{:<6}{:<10}{:<10}{}", row.name, row.ours, row.theirs, if row.differs { "<-- differs" } else { "" });
+            writeln!(f, "\t{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares a running chip8 against a reference trace, step by step,
+/// when passed to `Chip8::run_frame_observed`.
+pub struct DiffTester {
+    reference: Vec<ReferenceState>,
+    step: usize,                    // Reference state of the next instruction
+    before: Option<Snapshot>,       // Chip8 state before the current tick
+    divergence: Option<Divergence>,
+}
+
+impl DiffTester {
+    pub fn new(reference: Vec<ReferenceState>) -> Self {
+        DiffTester { reference, step: 0, before: None, divergence: None }
+    }
+
+    /// Read and parse the reference trace at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<DiffTester, String> {
+        let trace = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Ok(DiffTester::new(parse_reference(&trace)?))
+    }
+
+    /// Number of steps that matched so far.
+    pub fn steps(&self) -> u64 {
+        self.step as u64
+    }
+
+    /// Query wether the whole reference trace was compared, or the run diverged from it.
+    pub fn is_done(&self) -> bool {
+        self.divergence.is_some() || self.step >= self.reference.len()
+    }
+
+    /// Where the run first diverged from the reference, if it did.
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    /// Tell that the ROM stopped running, e.g. halted or stuck in a self-loop. Whatever is left of the
+    /// reference trace can't match then, so it diverges on its next step, unless it was all compared.
+    pub fn stopped(&mut self, chip8: &Chip8) {
+        if self.is_done() {
+            return;
+        }
+
+        let mut snapshot = self.snapshot(chip8);
+        let pc = chip8.registers().pc as usize;
+        let memory = chip8.memory();
+        snapshot.opcode = u16::from_be_bytes([memory[pc % MEM_SIZE], memory[(pc + 1) % MEM_SIZE]]);
+        self.divergence = Some(Divergence {
+            step: self.step as u64,
+            line: self.reference[self.step].line,
+            cycle: chip8.cycles(),
+            stopped: true,
+            rows: self.reference[self.step].compare(&snapshot),
+        });
+    }
+
+    /// Our side of the next step, but for its opcode.
+    fn snapshot(&self, chip8: &Chip8) -> Snapshot {
+        let memory = chip8.memory();
+        let snapshot_memory = self.reference[self.step].memory.iter()
+            .map(|(addr, bytes)| memory[*addr as usize..*addr as usize + bytes.len()].to_vec())
+            .collect();
+
+        Snapshot { registers: chip8.registers(), opcode: 0, memory: snapshot_memory }
+    }
+}
+
+impl TickObserver for DiffTester {
+    fn before_tick(&mut self, chip8: &mut Chip8) {
+        self.before = None;
+        if self.is_done() {
+            return;
+        }
+
+        self.before = Some(self.snapshot(chip8));
+        // The opcode is only known after the tick, which may not run an instruction (e.g. waiting for a key)
+    }
+
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        let (mut before, (_, opcode)) = match (self.before.take(), chip8.last_instruction()) {
+            (Some(before), Some(instruction)) => (before, instruction),
+            _ => return,
+        };
+        before.opcode = opcode;
+
+        let rows = self.reference[self.step].compare(&before);
+        if rows.iter().any(|row| row.differs) {
+            self.divergence = Some(Divergence {
+                step: self.step as u64,
+                line: self.reference[self.step].line,
+                cycle: chip8.cycles(),
+                stopped: false,
+                rows,
+            });
+            return;
+        }
+        self.step += 1;
+
+        // RND Vx, kk - Take the reference's random number
+        if opcode >> 12 == 0xC {
+            let x = ((opcode & 0x0F00) >> 8) as usize;
+            if let Some(vx) = self.reference.get(self.step).and_then(|next| next.v[x]) {
+                let mut registers = chip8.registers();
+                registers.v[x] = vx;
+                chip8.set_registers(registers);
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+// Mnemonics follow Cowgod's Chip-8 Technical Reference -> http://devernay.free.fr/hacks/chip8/C8TECH10.HTM
+
+/// Disassemble a single instruction, e.g. `6A12` -> `LD VA, 0x12`.
+/// Anything that isn't a valid instruction is shown as data, `DW 0xNNNN`.
+pub fn disassemble(instruction: u16) -> String {
+    let x = (instruction & 0x0F00) >> 8;
+    let y = (instruction & 0x00F0) >> 4;
+    let n = instruction & 0x000F;
+    let byte = instruction & 0x00FF;
+    let address = instruction & 0x0FFF;
+
+    match (instruction >> 12, x, y, n) {
+        (0x0, 0x0, 0x0, 0x0) => "HALT".to_string(),
+        (0x0, 0x0, 0xE, 0x0) => "CLS".to_string(),
+        (0x0, 0x0, 0xE, 0xE) => "RET".to_string(),
+        (0x0, _, _, _) => format!("SYS {:#05X}", address),
+        (0x1, _, _, _) => format!("JP {:#05X}", address),
+        (0x2, _, _, _) => format!("CALL {:#05X}", address),
+        (0x3, _, _, _) => format!("SE V{:X}, {:#04X}", x, byte),
+        (0x4, _, _, _) => format!("SNE V{:X}, {:#04X}", x, byte),
+        (0x5, _, _, 0x0) => format!("SE V{:X}, V{:X}", x, y),
+        (0x6, _, _, _) => format!("LD V{:X}, {:#04X}", x, byte),
+        (0x7, _, _, _) => format!("ADD V{:X}, {:#04X}", x, byte),
+        (0x8, _, _, 0x0) => format!("LD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x1) => format!("OR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x2) => format!("AND V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x3) => format!("XOR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x4) => format!("ADD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x5) => format!("SUB V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x6) => format!("SHR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x7) => format!("SUBN V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0xE) => format!("SHL V{:X}, V{:X}", x, y),
+        (0x9, _, _, 0x0) => format!("SNE V{:X}, V{:X}", x, y),
+        (0xA, _, _, _) => format!("LD I, {:#05X}", address),
+        (0xB, _, _, _) => format!("JP V0, {:#05X}", address),
+        (0xC, _, _, _) => format!("RND V{:X}, {:#04X}", x, byte),
+        (0xD, _, _, _) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        (0xE, _, 0x9, 0xE) => format!("SKP V{:X}", x),
+        (0xE, _, 0xA, 0x1) => format!("SKNP V{:X}", x),
+        (0xF, _, 0x0, 0x7) => format!("LD V{:X}, DT", x),
+        (0xF, _, 0x0, 0xA) => format!("LD V{:X}, K", x),
+        (0xF, _, 0x1, 0x5) => format!("LD DT, V{:X}", x),
+        (0xF, _, 0x1, 0x8) => format!("LD ST, V{:X}", x),
+        (0xF, _, 0x1, 0xE) => format!("ADD I, V{:X}", x),
+        (0xF, _, 0x2, 0x9) => format!("LD F, V{:X}", x),
+        (0xF, _, 0x3, 0x3) => format!("LD B, V{:X}", x),
+        (0xF, _, 0x5, 0x5) => format!("LD [I], V{:X}", x),
+        (0xF, _, 0x6, 0x5) => format!("LD V{:X}, [I]", x),
+        _ => format!("DW {:#06X}", instruction),
+    }
+}
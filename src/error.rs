@@ -0,0 +1,101 @@
+use std::fmt;
+use std::io;
+
+/// Something the running ROM did that the chip8 can't carry on from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// CALL (2NNN) at `pc` with the stack already at its maximum depth.
+    StackOverflow { pc: u16, stack: Vec<u16> },
+    /// RET (00EE) at `pc` with an empty stack.
+    StackUnderflow { pc: u16 },
+    /// Instruction fetch at `pc`, past the end of memory.
+    PcOutOfBounds { pc: u16 },
+    /// Instruction at `pc` read or wrote `address`, past the end of memory.
+    MemoryOutOfBounds { pc: u16, address: usize },
+    /// Instruction at `pc` isn't one the chip8 knows.
+    UnknownInstruction { pc: u16, instruction: u16 },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::StackOverflow { pc, stack } => {
+                writeln!(f, "Stack overflow at {:#05X}: CALL with {} return addresses already on the stack", pc, stack.len())?;
+                write_call_stack(f, stack)
+            },
+            Chip8Error::StackUnderflow { pc } => {
+                write!(f, "Stack underflow at {:#05X}: RET with an empty stack", pc)
+            },
+            Chip8Error::PcOutOfBounds { pc } => {
+                write!(f, "Program counter out of memory at {:#05X}: no instruction to fetch there", pc)
+            },
+            Chip8Error::MemoryOutOfBounds { pc, address } => {
+                write!(f, "Memory access out of bounds at {:#05X}: {:#05X} is past the end of memory", pc, address)
+            },
+            Chip8Error::UnknownInstruction { pc, instruction } => {
+                write!(f, "Unknown instruction at {:#05X}: {:04X}", pc, instruction)
+            },
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
+
+/// Why a ROM couldn't be read or loaded.
+#[derive(Debug)]
+pub enum RomError {
+    /// Reading the file (or stdin) failed.
+    Io(io::Error),
+    /// The ROM has no instructions at all.
+    Empty,
+    /// The ROM is `size` bytes, but only `capacity` fit in memory after the program start.
+    TooLarge { size: usize, capacity: usize },
+    /// The zip archive is corrupt, or uses something we can't read (e.g. encryption).
+    Archive(String),
+    /// The zip archive holds no ROM.
+    NoRomInArchive,
+    /// No entry of the zip archive has this name.
+    NotInArchive(String),
+    /// The zip archive holds these ROMs, and none was picked by name.
+    SeveralRomsInArchive(Vec<String>),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::Io(e) => write!(f, "{}", e),
+            RomError::Empty => write!(f, "ROM is empty"),
+            RomError::TooLarge { size, capacity } => {
+                write!(f, "ROM is too large: {} bytes, but only {} fit in memory", size, capacity)
+            },
+            RomError::Archive(e) => write!(f, "Invalid zip archive: {}", e),
+            RomError::NoRomInArchive => write!(f, "No ROM in the zip archive"),
+            RomError::NotInArchive(name) => write!(f, "No ROM named '{}' in the zip archive", name),
+            RomError::SeveralRomsInArchive(names) => {
+                write!(f, "Several ROMs in the zip archive, and none was picked:")?;
+                for name in names {
+                    write!(f, "\n\t{}", name)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for RomError {}
+
+impl From<io::Error> for RomError {
+    fn from(e: io::Error) -> Self {
+        RomError::Io(e)
+    }
+}
+
+/// Dump return addresses, innermost call first.
+fn write_call_stack(f: &mut fmt::Formatter<'_>, stack: &[u16]) -> fmt::Result {
+    write!(f, "Call stack (innermost first):")?;
+    for (depth, return_addr) in stack.iter().rev().enumerate() {
+        write!(f, "\n\t#{:<3} returns to {:#05X}", depth, return_addr)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+// Watches files for changes, to reload a ROM as soon as it's rebuilt. It polls each file's modification
+// time and size, which is plenty for a handful of files and needs no platform-specific API.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// What a file looked like when last polled: its modification time and size, None if it's missing.
+type Stamp = Option<(SystemTime, u64)>;
+
+struct WatchedFile {
+    path: PathBuf,
+    reported: Stamp,    // As of the last change reported (or when watching started)
+    polled: Stamp,      // As of the last poll
+}
+
+/// Watches files, reporting the ones that changed once they settle.
+pub struct FileWatcher {
+    files: Vec<WatchedFile>,
+    interval: Duration,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    /// A watcher polling its files at most every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        FileWatcher { files: Vec::new(), interval, last_poll: Instant::now() }
+    }
+
+    /// Start watching the file at `path`. It doesn't need to exist yet.
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref().to_path_buf();
+        let stamp = stamp(&path);
+        self.files.push(WatchedFile { path, reported: stamp, polled: stamp });
+    }
+
+    /// Files that changed since the last call, if it's time to poll them again.
+    ///
+    /// A change is reported once the file stays the same for a whole interval, so that a file still
+    /// being written isn't read half-way. Files that go missing aren't reported until they come back,
+    /// as editors and build tools often delete a file before writing it anew.
+    pub fn changed(&mut self) -> Vec<&Path> {
+        if self.last_poll.elapsed() < self.interval {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+        for file in self.files.iter_mut() {
+            let stamp = stamp(&file.path);
+            let has_settled = stamp == file.polled;
+            file.polled = stamp;
+
+            if has_settled && stamp.is_some() && stamp != file.reported {
+                file.reported = stamp;
+                changed.push(file.path.as_path());
+            }
+        }
+
+        changed
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -0,0 +1,232 @@
+// A gym-style environment, to train agents on CHIP-8 games: reset() starts an episode, and
+// step(action) holds the action's keys for a few frames, then returns what the screen shows, the
+// reward earned and wether the episode is over. It runs as fast as the interpreter does, and
+// environments can be cloned, e.g. to run many in parallel from the same state.
+//
+// What's rewarded and when an episode ends depends on the game, so each has a config file.
+// One setting per line, blank lines and lines starting with # are ignored, e.g. for BRIX:
+//
+//   actions - 4 6      Key sets an action holds: hex keys together (e.g. 46), or - for none
+//   reward V5 1        Reward the change of V5 (the score), times 1. Repeat for more rewards
+//   done VE 0          Episode ends once VE (lives) is 0. Repeat for more conditions
+//   frame_skip 4       Frames each step runs (default 1)
+//   ipf 8              Instructions per frame (default 8)
+//   max_frames 36000   Episode ends after these many frames (default never)
+//
+// Values are a V register (VE), a memory byte (0x2F0) or a number stored as decimal digits,
+// a byte each like FX33 does, with its address and digit count (bcd:0x314:3).
+// Episodes also end when the ROM ends, or fails.
+
+use std::path::Path;
+
+use crate::chip8::{Chip8, MEM_SIZE};
+use crate::error::Chip8Error;
+use crate::memory_map::MemoryMap;
+
+/// A number a game keeps, for rewards and episode ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Register(u8),       // V register [0..F]
+    Byte(u16),          // Memory byte at this address
+    Bcd(u16, u8),       // Decimal digits, a byte each, from this address on
+}
+
+impl Value {
+    /// Parse a value as written in config files: `VE`, `0x2F0` or `bcd:0x314:3`.
+    pub fn parse(value: &str) -> Option<Value> {
+        if let Some(bcd) = value.strip_prefix("bcd:") {
+            let (addr, digits) = bcd.split_once(':')?;
+            let addr = MemoryMap::parse_address(addr)?;
+            let digits = digits.parse().ok().filter(|&digits| (1..=9).contains(&digits))?;
+            return (addr as usize + digits as usize <= MEM_SIZE).then_some(Value::Bcd(addr, digits));
+        }
+
+        match value.strip_prefix('V') {
+            Some(x) if x.len() == 1 => u8::from_str_radix(x, 16).ok().map(Value::Register),
+            _ => MemoryMap::parse_address(value).filter(|&addr| (addr as usize) < MEM_SIZE).map(Value::Byte),
+        }
+    }
+
+    /// Current value in `chip8`.
+    pub fn read(&self, chip8: &Chip8) -> u32 {
+        match *self {
+            Value::Register(x) => chip8.registers().v[x as usize] as u32,
+            Value::Byte(addr) => chip8.memory()[addr as usize] as u32,
+            Value::Bcd(addr, digits) => chip8.memory()[addr as usize..addr as usize + digits as usize]
+                .iter()
+                .fold(0, |number, &digit| number * 10 + digit as u32),
+        }
+    }
+}
+
+/// How a game is played as an environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvConfig {
+    pub actions: Vec<u16>,          // Keys each action holds down, a bit per key
+    pub rewards: Vec<(Value, f64)>, // Values whose change is rewarded, and by how much
+    pub done: Vec<(Value, u32)>,    // Episode ends when any of these values is reached
+    pub frame_skip: u32,            // Frames a step runs
+    pub ticks_per_frame: usize,
+    pub max_frames: Option<u32>,    // Frames an episode lasts at most
+}
+
+impl Default for EnvConfig {
+    /// No rewards, and an action per key, after one for no key at all.
+    fn default() -> Self {
+        EnvConfig {
+            actions: std::iter::once(0).chain((0..16).map(|key| 1 << key)).collect(),
+            rewards: Vec::new(),
+            done: Vec::new(),
+            frame_skip: 1,
+            ticks_per_frame: 8,
+            max_frames: None,
+        }
+    }
+}
+
+impl EnvConfig {
+    /// Parse a config file. On failure, returns a message pointing at the faulty line.
+    pub fn parse(text: &str) -> Result<EnvConfig, String> {
+        let mut config = EnvConfig::default();
+        let mut actions = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match fields.as_slice() {
+                ["actions", keys @ ..] if !keys.is_empty() => keys.iter()
+                    .map(|keys| parse_keys(keys).map(|keys| actions.push(keys)))
+                    .collect(),
+                ["reward", value, weight] => Value::parse(value)
+                    .zip(weight.parse().ok())
+                    .map(|reward| config.rewards.push(reward)),
+                ["done", value, target] => Value::parse(value)
+                    .zip(target.parse().ok())
+                    .map(|done| config.done.push(done)),
+                ["frame_skip", frames] => frames.parse().ok().filter(|&frames| frames > 0).map(|frames| config.frame_skip = frames),
+                ["ipf", ticks] => ticks.parse().ok().map(|ticks| config.ticks_per_frame = ticks),
+                ["max_frames", frames] => frames.parse().ok().map(|frames| config.max_frames = Some(frames)),
+                _ => None,
+            };
+            parsed.ok_or(format!("line {}: invalid setting '{}'", line_index + 1, line))?;
+        }
+
+        if !actions.is_empty() {
+            config.actions = actions;
+        }
+        Ok(config)
+    }
+
+    /// Read and parse the config file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<EnvConfig, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        EnvConfig::parse(&text)
+    }
+}
+
+/// Parse a key set: hex keys together, or - for none.
+fn parse_keys(keys: &str) -> Option<u16> {
+    if keys == "-" {
+        return Some(0);
+    }
+
+    keys.chars().try_fold(0, |set, key| key.to_digit(16).map(|key| set | 1 << key))
+}
+
+/// A game being played by an agent.
+#[derive(Clone)]
+pub struct Env {
+    start: Chip8,               // The chip8 as episodes start
+    chip8: Chip8,
+    config: EnvConfig,
+    frames: u32,                // Frames run this episode
+    reward_values: Vec<u32>,    // Rewarded values, as of the last step
+    done: bool,
+    error: Option<Chip8Error>,  // Why the ROM failed, ending the episode
+}
+
+impl Env {
+    /// An environment playing `chip8`, with its ROM loaded. Episodes start from its current state.
+    pub fn new(chip8: Chip8, config: EnvConfig) -> Self {
+        let reward_values = config.rewards.iter().map(|(value, _)| value.read(&chip8)).collect();
+        Env { start: chip8.clone(), chip8, config, frames: 0, reward_values, done: false, error: None }
+    }
+
+    /// Start a new episode. Returns the first observation: the screen, a byte per pixel.
+    pub fn reset(&mut self) -> &[u8] {
+        self.chip8.clone_from(&self.start);
+        self.frames = 0;
+        self.reward_values = self.config.rewards.iter().map(|(value, _)| value.read(&self.chip8)).collect();
+        self.done = false;
+        self.error = None;
+
+        &self.chip8.screen
+    }
+
+    /// Hold the keys of action number `action` for the config's frame_skip frames.
+    /// Returns the observation (the screen), the reward earned and wether the episode is over.
+    /// Once over, steps do nothing until the next reset.
+    ///
+    /// Panics if `action` isn't below `action_count()`.
+    pub fn step(&mut self, action: usize) -> (&[u8], f64, bool) {
+        let keys = self.config.actions[action];
+        if self.done {
+            return (&self.chip8.screen, 0.0, true);
+        }
+
+        let mut keyboard = [false; 16];
+        for (key, is_down) in keyboard.iter_mut().enumerate() {
+            *is_down = keys & (1 << key) != 0;
+        }
+        self.chip8.set_keys(&keyboard);
+
+        for _ in 0..self.config.frame_skip {
+            if let Err(e) = self.chip8.run_frame(self.config.ticks_per_frame) {
+                self.error = Some(e);
+                break;
+            }
+            self.frames += 1;
+            if self.chip8.finished_running() {
+                break;
+            }
+        }
+
+        let mut reward = 0.0;
+        for ((value, weight), last) in self.config.rewards.iter().zip(self.reward_values.iter_mut()) {
+            let now = value.read(&self.chip8);
+            reward += weight * (now as f64 - *last as f64);
+            *last = now;
+        }
+
+        self.done = self.error.is_some()
+            || self.chip8.finished_running()
+            || self.config.max_frames.is_some_and(|max_frames| self.frames >= max_frames)
+            || self.config.done.iter().any(|(value, target)| value.read(&self.chip8) == *target);
+
+        (&self.chip8.screen, reward, self.done)
+    }
+
+    /// Number of actions `step` takes.
+    pub fn action_count(&self) -> usize {
+        self.config.actions.len()
+    }
+
+    /// Frames run in the current episode.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Why the ROM failed, if that ended the episode.
+    pub fn error(&self) -> Option<&Chip8Error> {
+        self.error.as_ref()
+    }
+
+    /// The chip8 being played, e.g. to look at more than the screen.
+    pub fn chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+}
@@ -0,0 +1,72 @@
+// Input scripts press and release keys on given frames, so runs without a player are repeatable.
+// One event per line: the frame number (counting from 0, the first frame run), the key [0..F],
+// then `down` or `up`. Blank lines and lines starting with # are ignored, e.g.
+//
+//   # Start the game, then hold left for a second
+//   30 5 down
+//   32 5 up
+//   60 4 down
+//   120 4 up
+
+use std::path::Path;
+
+use crate::chip8::Chip8;
+
+/// Key presses and releases, by frame.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InputScript {
+    events: Vec<(u32, u8, bool)>,   // Frame, key and wether it goes down, sorted by frame
+}
+
+impl InputScript {
+    /// Parse a script. On failure, returns a message pointing at the faulty line.
+    pub fn parse(script: &str) -> Result<InputScript, String> {
+        let mut events = Vec::new();
+
+        for (line_index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let event = match fields.as_slice() {
+                [frame, key, action] => {
+                    let frame = frame.parse().ok();
+                    let key = u8::from_str_radix(key, 16).ok().filter(|&key| key <= 0xF);
+                    let is_down = match *action {
+                        "down" => Some(true),
+                        "up" => Some(false),
+                        _ => None,
+                    };
+                    frame.zip(key).zip(is_down).map(|((frame, key), is_down)| (frame, key, is_down))
+                },
+                _ => None,
+            };
+
+            let event = event.ok_or(format!("line {}: expected '<frame> <key> down|up', got '{}'", line_index + 1, line))?;
+            events.push(event);
+        }
+        events.sort_by_key(|&(frame, _, _)| frame); // Stable, so same-frame events keep their order
+
+        Ok(InputScript { events })
+    }
+
+    /// Read and parse the script at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<InputScript, String> {
+        let script = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        InputScript::parse(&script)
+    }
+
+    /// Press and release the keys scripted for `frame`. Meant to be called right before running it.
+    pub fn apply(&self, frame: u32, chip8: &mut Chip8) {
+        let start = self.events.partition_point(|&(event_frame, _, _)| event_frame < frame);
+        for &(_, key, is_down) in self.events[start..].iter().take_while(|&&(event_frame, _, _)| event_frame == frame) {
+            if is_down {
+                chip8.press_key(key);
+            } else {
+                chip8.release_key(key);
+            }
+        }
+    }
+}
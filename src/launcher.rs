@@ -0,0 +1,268 @@
+// The launcher lists the ROMs of a directory, those in its zip archives included, to pick one to run
+// with the keypad, written with the CHIP-8 font. It's what rusty-8 shows when started without a ROM,
+// and F12 returns to it.
+//
+//   ROMS IN ROMS/                        4/27
+//   15PUZZLE
+//   BLINKY
+//   BRIX                                  (selected)
+//   ...
+//   BRIX - ANDREAS GUSTAFSSON, 1990      (from the ROM database)
+//   BREAKOUT CLONE. KEYS 4 AND 6 MOVE THE PADDLE
+//   2 8 SELECT  4 6 PAGE  5 RUN  F12 BACK  ESC QUIT
+
+use std::path::Path;
+
+use rusty_8::{RomDatabase, RomInfo};
+use rusty_8::chip8::MEM_SIZE;
+use rusty_8::rom::{is_rom, is_zip, read_zip_rom, zip_roms};
+
+use crate::canvas::{Canvas, CHIP8_CHAR_WIDTH, LINE_HEIGHT};
+
+pub const LAUNCHER_WIDTH:  usize = 256; // Launcher view width, in pixels: 4 times the chip8 screen
+pub const LAUNCHER_HEIGHT: usize = 128; // Launcher view height, in pixels
+
+const MAX_FILE_SIZE: u64 = 1 << 20;     // Bigger files aren't ROMs, nor ROM packs
+const LIST_TOP: usize = 11;             // y of the first ROM in the list
+const DETAIL_LINES: usize = 3;          // Lines about the selected ROM, below the list
+
+const TEXT: u8 = 220;
+const LABEL: u8 = 120;
+const SELECTED: u8 = 70;
+const FRAME: u8 = 60;
+
+/// Keys the launcher handles, independent of the windowing library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LauncherKey {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+}
+
+/// A ROM the launcher lists.
+pub struct LauncherRom {
+    pub label: String,          // File name, preceded by the archive's for ROMs in zip archives
+    pub rom: Vec<u8>,
+    pub info: Option<RomInfo>,  // What the ROM database knows about it
+}
+
+/// State of the launcher view: the ROMs found and which one is selected.
+pub struct Launcher {
+    dir: String,
+    roms: Vec<LauncherRom>,
+    error: Option<String>,      // Why the directory couldn't be listed
+    message: Option<String>,    // Shown in place of the key help, e.g. why a ROM stopped
+    selected: usize,
+    scroll: usize,              // Index of the first ROM shown
+    canvas: Canvas,
+}
+
+impl Launcher {
+    /// A launcher listing the ROMs in `dir`, drawn on a `width` x `height` canvas.
+    pub fn new(dir: &str, database: &RomDatabase, width: usize, height: usize) -> Self {
+        let mut launcher = Launcher {
+            dir: dir.to_string(),
+            roms: Vec::new(),
+            error: None,
+            message: None,
+            selected: 0,
+            scroll: 0,
+            canvas: Canvas::new(width, height),
+        };
+        launcher.scan(database);
+        launcher
+    }
+
+    /// List the directory again, for ROMs that came or went. Keeps the selection where it can.
+    pub fn scan(&mut self, database: &RomDatabase) {
+        self.roms.clear();
+        self.error = None;
+
+        let dir = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) => {
+                self.error = Some(format!("Can't read {}: {}", self.dir, e));
+                return;
+            },
+        };
+
+        for entry in dir.flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let is_small_file = entry.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.len() <= MAX_FILE_SIZE);
+            if !is_small_file || !is_rom(&file_name) {
+                continue;
+            }
+            let bytes = match std::fs::read(entry.path()) {
+                Ok(bytes) if !bytes.is_empty() => bytes,
+                _ => continue,
+            };
+
+            if !is_zip(&bytes) {
+                let info = database.get(&bytes).cloned();
+                self.roms.push(LauncherRom { label: file_name, rom: bytes, info });
+                continue;
+            }
+            for name in zip_roms(&bytes).unwrap_or_default() {
+                // Like ROM files, those too large for the memory map are listed, and fail to run
+                if let Ok(rom) = read_zip_rom(&bytes, Some(&name), MEM_SIZE) {
+                    let info = database.get(&rom).cloned();
+                    self.roms.push(LauncherRom { label: format!("{}/{}", file_name, name), rom, info });
+                }
+            }
+        }
+
+        self.roms.sort_by_key(|rom| rom.label.to_lowercase());
+        self.selected = self.selected.min(self.roms.len().saturating_sub(1));
+    }
+
+    /// The selected ROM, if there's any.
+    pub fn selected(&self) -> Option<&LauncherRom> {
+        self.roms.get(self.selected)
+    }
+
+    /// Show `message` until the next key press, e.g. why the last ROM stopped.
+    pub fn set_message(&mut self, message: &str) {
+        // Only the first line fits, the rest (e.g. a call stack) goes to the terminal
+        eprintln!("{}", message);
+        self.message = message.lines().next().map(str::to_string);
+    }
+
+    /// Handle a key press.
+    pub fn key(&mut self, key: LauncherKey) {
+        self.message = None;
+        if self.roms.is_empty() {
+            return;
+        }
+
+        let page = self.list_lines();
+        let last = self.roms.len() - 1;
+        self.selected = match key {
+            LauncherKey::Up => self.selected.saturating_sub(1),
+            LauncherKey::Down => (self.selected + 1).min(last),
+            LauncherKey::PageUp => self.selected.saturating_sub(page),
+            LauncherKey::PageDown => (self.selected + page).min(last),
+        };
+    }
+
+    /// Draw the launcher view. Returns its pixels, ready for the window's buffer.
+    pub fn render(&mut self) -> &[u8] {
+        self.canvas.clear();
+        self.draw_header();
+        self.draw_list();
+        self.draw_details();
+        self.draw_footer();
+
+        &self.canvas.pixels
+    }
+
+    /// ROMs the list shows at once.
+    fn list_lines(&self) -> usize {
+        let list_height = self.canvas.height.saturating_sub(LIST_TOP + (DETAIL_LINES + 1) * LINE_HEIGHT + 6);
+        (list_height / LINE_HEIGHT).max(1)
+    }
+
+    /// Characters fitting on a line.
+    fn line_chars(&self) -> usize {
+        (self.canvas.width - 4) / CHIP8_CHAR_WIDTH
+    }
+
+    fn draw_header(&mut self) {
+        let title = format!("ROMS IN {}", Path::new(&self.dir).display());
+        self.canvas.draw_chip8_text(2, 2, &fit(&title, self.line_chars() - 10), TEXT);
+
+        if !self.roms.is_empty() {
+            let count = format!("{}/{}", self.selected + 1, self.roms.len());
+            let x = self.canvas.width - 2 - count.len() * CHIP8_CHAR_WIDTH;
+            self.canvas.draw_chip8_text(x, 2, &count, LABEL);
+        }
+    }
+
+    fn draw_list(&mut self) {
+        let lines = self.list_lines();
+        if let Some(error) = self.error.clone() {
+            self.canvas.draw_chip8_text(2, LIST_TOP, &fit(&error, self.line_chars()), TEXT);
+            return;
+        }
+        if self.roms.is_empty() {
+            let message = format!("No ROMs in {}", self.dir);
+            self.canvas.draw_chip8_text(2, LIST_TOP, &fit(&message, self.line_chars()), TEXT);
+            return;
+        }
+
+        // Scroll just enough to keep the selected ROM in view
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + lines {
+            self.scroll = self.selected + 1 - lines;
+        }
+
+        let chars = self.line_chars();
+        for (line, index) in (self.scroll..self.roms.len()).take(lines).enumerate() {
+            let y = LIST_TOP + line * LINE_HEIGHT;
+            if index == self.selected {
+                self.canvas.fill_rect(0, y - 1, self.canvas.width, LINE_HEIGHT, SELECTED);
+            }
+            let label = fit(&self.roms[index].label, chars);
+            self.canvas.draw_chip8_text(2, y, &label, TEXT);
+        }
+    }
+
+    fn draw_details(&mut self) {
+        let top = LIST_TOP + self.list_lines() * LINE_HEIGHT + 2;
+        self.canvas.fill_rect(0, top, self.canvas.width, 1, FRAME);
+
+        let chars = self.line_chars();
+        let lines = match self.selected() {
+            Some(LauncherRom { info: Some(info), .. }) => {
+                let mut heading = info.title.clone();
+                match (&info.author, info.year) {
+                    (Some(author), Some(year)) => heading += &format!(" - {}, {}", author, year),
+                    (Some(author), None) => heading += &format!(" - {}", author),
+                    (None, Some(year)) => heading += &format!(" - {}", year),
+                    (None, None) => {},
+                }
+
+                let mut lines = vec![heading];
+                lines.extend(wrap(info.description.as_deref().unwrap_or(""), chars));
+                lines
+            },
+            Some(rom) => vec![format!("{} bytes, not in the ROM database", rom.rom.len())],
+            None => Vec::new(),
+        };
+
+        for (line, text) in lines.iter().take(DETAIL_LINES).enumerate() {
+            let shade = if line == 0 { TEXT } else { LABEL };
+            self.canvas.draw_chip8_text(2, top + 3 + line * LINE_HEIGHT, &fit(text, chars), shade);
+        }
+    }
+
+    fn draw_footer(&mut self) {
+        let y = self.canvas.height - LINE_HEIGHT;
+        match self.message.clone() {
+            Some(message) => self.canvas.draw_chip8_text(2, y, &fit(&message, self.line_chars()), TEXT),
+            None => self.canvas.draw_chip8_text(2, y, "2 8 SELECT  4 6 PAGE  5 RUN  F12 BACK  ESC QUIT", LABEL),
+        };
+    }
+}
+
+/// `text`, cut to `chars` characters.
+fn fit(text: &str, chars: usize) -> String {
+    text.chars().take(chars).collect()
+}
+
+/// Split `text` into lines of at most `chars` characters, between words.
+fn wrap(text: &str, chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= chars => {
+                line.push(' ');
+                line.push_str(word);
+            },
+            _ => lines.push(word.to_string()),
+        }
+    }
+
+    lines
+}
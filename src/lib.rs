@@ -0,0 +1,44 @@
+//! The rusty-8 interpreter core.
+//!
+//! Everything needed to run a CHIP-8 program without a window lives here, so that
+//! other frontends (like the libretro core in `libretro/`) can drive the same `Chip8`.
+
+pub mod cheats;
+pub mod chip8;
+pub mod coverage;
+pub mod difftest;
+pub mod disasm;
+pub mod error;
+pub mod file_watcher;
+pub mod gym;
+pub mod hash;
+pub mod input_script;
+pub mod memory_map;
+pub mod profiler;
+pub mod quirks;
+pub mod rom;
+pub mod romdb;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod timing;
+pub mod trace;
+pub mod watchdog;
+pub mod wav;
+
+pub use cheats::{Cheats, RamSearch, SearchFilter};
+pub use chip8::{Chip8, Registers, TickObserver};
+pub use coverage::Coverage;
+pub use difftest::DiffTester;
+pub use error::{Chip8Error, RomError};
+pub use file_watcher::FileWatcher;
+pub use gym::{Env, EnvConfig};
+pub use input_script::InputScript;
+pub use memory_map::MemoryMap;
+pub use profiler::Profiler;
+pub use quirks::{Quirks, StackConfig};
+pub use romdb::{RomDatabase, RomInfo};
+#[cfg(feature = "scripting")]
+pub use script::Script;
+pub use timing::Timing;
+pub use trace::{TraceFilter, TraceFormat, Tracer};
+pub use watchdog::{ExitReason, Limits, Watchdog};
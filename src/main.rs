@@ -1,16 +1,34 @@
 use mini_gl_fb::{self, config};
 use mini_gl_fb::glutin::{dpi::LogicalSize, event::VirtualKeyCode};
-use rodio::{OutputStream, Source, source::SineWave};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::{Duration, Instant};
 
-mod chip8;
+mod audio;
+mod canvas;
+mod debugger;
+mod launcher;
+mod options;
 
-use chip8::Chip8;
-use chip8::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use audio::{open_backend, AudioBackend, SoundTimerObserver};
+use canvas::Canvas;
+use debugger::{DebugKey, Debugger, DEBUG_WIDTH, DEBUG_HEIGHT};
+use launcher::{Launcher, LauncherKey, LAUNCHER_WIDTH, LAUNCHER_HEIGHT};
+use options::Options;
+
+use rusty_8::{Chip8, Cheats, FileWatcher, Profiler, RomDatabase, RomError, RomInfo, SearchFilter};
+use rusty_8::cheats::cheat_file_name;
+use rusty_8::chip8::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use rusty_8::profiler::REPORT_TOP_ADDRESSES;
+use rusty_8::rom;
 
 const SCREEN_SCALE: usize = 8;      // Initial scale between Chip-8 screen and displayed Window
 const WINDOW_WIDTH:  f64  = (SCREEN_WIDTH  * SCREEN_SCALE) as f64;  // Displayed Window Width
 const WINDOW_HEIGHT: f64  = (SCREEN_HEIGHT * SCREEN_SCALE) as f64;  // Displayed Window Height
+const DEBUG_SCALE: usize = 3;       // Initial scale between the debugger view and displayed Window
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);  // Chip-8 runs in 60Hz frames
+const DATABASE_FILE_NAME: &str = "database.txt";    // ROM database completing the built-in one, in --roms
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);    // How often --watch polls files for changes
 
 // Array mapping Key codes to keys in the chip8 keyboard
 const CHIP8_VIRTUAL_KEY_CODES: [VirtualKeyCode; 16] = [
@@ -32,34 +50,278 @@ const CHIP8_VIRTUAL_KEY_CODES: [VirtualKeyCode; 16] = [
     VirtualKeyCode::V       // F
 ];
 
-/// Read keys that are down during `input` event poll.
-fn read_chip8_keys(keyboard: &mut [bool; 16], input: &mini_gl_fb::BasicInput) {
+// Keys typing hex digits into the debugger, from 0 to F
+const HEX_VIRTUAL_KEY_CODES: [VirtualKeyCode; 16] = [
+    VirtualKeyCode::Key0, VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6, VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8, VirtualKeyCode::Key9, VirtualKeyCode::A, VirtualKeyCode::B,
+    VirtualKeyCode::C, VirtualKeyCode::D, VirtualKeyCode::E, VirtualKeyCode::F
+];
+
+// Keys moving around the debugger
+const DEBUG_VIRTUAL_KEY_CODES: [(VirtualKeyCode, DebugKey); 7] = [
+    (VirtualKeyCode::Up, DebugKey::Up),
+    (VirtualKeyCode::Down, DebugKey::Down),
+    (VirtualKeyCode::Left, DebugKey::Left),
+    (VirtualKeyCode::Right, DebugKey::Right),
+    (VirtualKeyCode::PageUp, DebugKey::PageUp),
+    (VirtualKeyCode::PageDown, DebugKey::PageDown),
+    (VirtualKeyCode::Tab, DebugKey::Tab)
+];
+
+// Keys starting (None) and narrowing down a RAM search in the debugger
+const SEARCH_VIRTUAL_KEY_CODES: [(VirtualKeyCode, Option<SearchFilter>); 5] = [
+    (VirtualKeyCode::F5, None),
+    (VirtualKeyCode::F6, Some(SearchFilter::Changed)),
+    (VirtualKeyCode::F7, Some(SearchFilter::Unchanged)),
+    (VirtualKeyCode::F8, Some(SearchFilter::Increased)),
+    (VirtualKeyCode::F9, Some(SearchFilter::Decreased))
+];
+
+// Keys moving around the launcher: the keypad's 2 8 4 6, like in games, or the arrows
+const LAUNCHER_VIRTUAL_KEY_CODES: [(VirtualKeyCode, LauncherKey); 8] = [
+    (VirtualKeyCode::Key2, LauncherKey::Up),
+    (VirtualKeyCode::S, LauncherKey::Down),
+    (VirtualKeyCode::Q, LauncherKey::PageUp),
+    (VirtualKeyCode::E, LauncherKey::PageDown),
+    (VirtualKeyCode::Up, LauncherKey::Up),
+    (VirtualKeyCode::Down, LauncherKey::Down),
+    (VirtualKeyCode::PageUp, LauncherKey::PageUp),
+    (VirtualKeyCode::PageDown, LauncherKey::PageDown)
+];
+
+// Keys booting the ROM selected in the launcher: the keypad's 5, or Enter
+const BOOT_VIRTUAL_KEY_CODES: [VirtualKeyCode; 2] = [VirtualKeyCode::W, VirtualKeyCode::Return];
+
+/// Read keys that are down during `input` event poll, and hand them to the chip8.
+fn read_chip8_keys(chip8: &mut Chip8, input: &mini_gl_fb::BasicInput) {
+    let mut keyboard = [false; 16];
     for (key_pos, key_code) in CHIP8_VIRTUAL_KEY_CODES.iter().enumerate() {
         keyboard[key_pos] = input.key_is_down(*key_code);
     }
+
+    chip8.set_keys(&keyboard);
+}
+
+/// Hand the debugger keys pressed during `input` event poll. Hex digits only count while paused,
+/// as they're chip8 keys otherwise.
+fn read_debug_keys(debugger: &mut Debugger, chip8: &mut Chip8, input: &mini_gl_fb::BasicInput) {
+    for (key_code, key) in DEBUG_VIRTUAL_KEY_CODES.iter() {
+        if input.key_pressed(*key_code) {
+            debugger.key(*key, chip8);
+        }
+    }
+
+    if debugger.paused {
+        for (digit, key_code) in HEX_VIRTUAL_KEY_CODES.iter().enumerate() {
+            if input.key_pressed(*key_code) {
+                debugger.key(DebugKey::Digit(digit as u8), chip8);
+            }
+        }
+    }
+}
+
+/// A ROM running, with what goes along with it.
+struct Session {
+    chip8: Chip8,
+    cheats: Cheats,
+    cheats_path: PathBuf,
+    debugger: Option<Debugger>,
+    profiler: Option<Profiler>,
+    sound: SoundTimerObserver,
+    rom_name: Option<String>,   // Name the ROM was picked by in its zip archive, to reload the same one
+}
+
+impl Session {
+    /// Boot `rom` as the options say, with the quirks the ROM database knows for it unless they say otherwise.
+    /// On failure, returns a message explaining what's wrong.
+    fn boot(options: &Options, rom: &[u8], info: Option<&RomInfo>) -> Result<Session, String> {
+        let quirks = options.quirks.or(info.and_then(|info| info.quirks)).unwrap_or_default();
+        let mut chip8 = Chip8::with_quirks(quirks);
+        chip8.timing = options.timing;
+        chip8.stack_config = options.stack_config;
+        chip8.set_memory_map(options.memory_map).expect("memory map is validated with the options");
+        chip8.load_rom_bytes(rom).map_err(|e| format!("Failure during ROM open/read\n{}", e))?;
+
+        // Cheats are kept per ROM, in a file named after its hash
+        let cheats_path = Path::new(&options.cheats).join(cheat_file_name(rom));
+        let cheats = if cheats_path.exists() {
+            Cheats::load(&cheats_path).map_err(|e| format!("Failure reading {}\n{}", cheats_path.display(), e))?
+        } else {
+            Cheats::new()
+        };
+
+        let debugger = options.debugger.then(|| Debugger::new(&chip8));
+        let profiler = options.profile.as_ref().map(|_| Profiler::new());
+        let sound = SoundTimerObserver::new(options.ticks_per_frame);
+        Ok(Session { chip8, cheats, cheats_path, debugger, profiler, sound, rom_name: None })
+    }
+
+    /// Replace the ROM with `rom`, rebooting into it. The profiler starts over, as the old counts would
+    /// point at different code.
+    fn reload(&mut self, rom: &[u8]) -> Result<(), RomError> {
+        self.chip8.reload_rom(rom)?;
+        if self.profiler.is_some() {
+            self.profiler = Some(Profiler::new());
+        }
+        Ok(())
+    }
+
+    /// Stop running, writing the profiler report if asked for.
+    fn end(self, options: &Options) {
+        if let (Some(path), Some(profiler)) = (&options.profile, &self.profiler) {
+            if let Err(e) = profiler.save_report(path, REPORT_TOP_ADDRESSES) {
+                eprintln!("Failure writing {}\n{}", path, e);
+            }
+        }
+    }
+
+    /// Draw what the window shows: the debugger view, or the chip8 screen, scaled up to `canvas`
+    /// when the window's buffer is bigger (as it's sized for the launcher).
+    fn render<'a>(&'a mut self, canvas: &'a mut Canvas) -> &'a [u8] {
+        match self.debugger.as_mut() {
+            Some(debugger) => debugger.render(&self.chip8, &self.cheats),
+            None if canvas.width == SCREEN_WIDTH => &self.chip8.screen,
+            None => {
+                canvas.clear();
+                canvas.draw_image(0, 0, &self.chip8.screen, SCREEN_WIDTH, canvas.width / SCREEN_WIDTH, 255);
+                &canvas.pixels
+            },
+        }
+    }
+
+    /// Handle the keys of `input` event poll, and run a 60Hz frame if `is_frame`.
+    /// Returns wether the ROM goes on, or why it failed.
+    fn update(&mut self, fb: &mut mini_gl_fb::Framebuffer, input: &mini_gl_fb::BasicInput, options: &Options,
+              beep: &dyn AudioBackend, is_frame: bool, canvas: &mut Canvas) -> Result<bool, String> {
+        let is_paused = self.debugger.as_ref().is_some_and(|debugger| debugger.paused);
+        if !is_paused {
+            read_chip8_keys(&mut self.chip8, input);
+        }
+
+        if self.chip8.finished_running() {
+            return Ok(false);
+        }
+
+        let should_reboot = input.key_is_down(VirtualKeyCode::LControl) && input.key_is_down(VirtualKeyCode::R);
+        if should_reboot { // Reboot the chip8 with current ROM
+            self.chip8.reboot();
+            if self.debugger.is_none() {
+                fb.update_buffer(self.render(canvas));
+            }
+            beep.set_gate(false);
+        }
+
+        if let Some(debugger) = self.debugger.as_mut() {
+            if input.key_pressed(VirtualKeyCode::F1) {
+                debugger.paused = !debugger.paused;
+                beep.set_gate(false);
+            }
+            read_debug_keys(debugger, &mut self.chip8, input);
+
+            for (key_code, filter) in SEARCH_VIRTUAL_KEY_CODES.iter() {
+                if input.key_pressed(*key_code) {
+                    debugger.search(&self.chip8, *filter);
+                }
+            }
+            if input.key_pressed(VirtualKeyCode::F10) {
+                debugger.next_candidate();
+            }
+            if input.key_pressed(VirtualKeyCode::F11) && debugger.toggle_freeze(&self.chip8, &mut self.cheats) {
+                if let Err(e) = self.cheats.save(&self.cheats_path) {
+                    eprintln!("Failure writing {}\n{}", self.cheats_path.display(), e);
+                }
+            }
+
+            // Stepping, while paused: a single instruction, or a whole frame
+            if debugger.paused && input.key_pressed(VirtualKeyCode::F2) {
+                self.chip8.step().map_err(|e| e.to_string())?;
+            } else if debugger.paused && input.key_pressed(VirtualKeyCode::F3) {
+                self.chip8.run_frame_observed(options.ticks_per_frame, &mut (&mut self.cheats, &mut self.profiler))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        if is_frame { // Run one 60Hz frame: clock cycles, then timers
+            let is_paused = self.debugger.as_ref().is_some_and(|debugger| debugger.paused);
+            if !is_paused {
+                // ROM can't go on on failure, report why
+                self.chip8.run_frame_observed(options.ticks_per_frame, &mut (&mut self.cheats, &mut self.profiler, &mut self.sound))
+                    .map_err(|e| e.to_string())?;
+                beep.play_frame(self.sound.edges());
+            }
+
+            if self.debugger.is_some() || self.chip8.screen_updated {
+                fb.update_buffer(self.render(canvas));
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Run the --build `command` through the shell, its output going to the terminal.
+/// On failure, returns a message explaining what's wrong.
+fn build(command: &str) -> Result<(), String> {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let status = Command::new(shell).arg(flag).arg(command).status().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("'{}' failed ({})", command, status));
+    }
+    Ok(())
 }
 
 fn main() {
-    let rom = match std::env::args().nth(1) {
-        Some(rom) => rom,
-        None => {
-            eprintln!("Missing rom file path. Try ./rusty8 <rom_path> or cargo run --release -- <rom_path>");
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
             std::process::exit(1);
         },
     };
 
-    let mut chip8 = Chip8::new();
-    if let Err(e) = chip8.load_rom(rom) {
-        eprintln!("Failure during ROM open/read\n{}", e);
-        std::process::exit(1);
+    // The ROM database is the built-in one, completed by the ROM directory's if there's one
+    let mut database = RomDatabase::builtin();
+    let database_path = Path::new(&options.rom_dir).join(DATABASE_FILE_NAME);
+    if database_path.exists() {
+        match RomDatabase::load(&database_path) {
+            Ok(extra) => database.merge(extra),
+            Err(e) => eprintln!("Failure reading {}\n{}", database_path.display(), e),
+        }
     }
 
+    // Without a ROM to run, start in the launcher
+    let mut session = options.rom.as_ref().map(|path| {
+        let booted = rom::read_rom_interactive(path, options.memory_map.rom_capacity())
+            .map_err(|e| format!("Failure during ROM open/read\n{}", e))
+            .and_then(|(rom, rom_name)| {
+                let session = Session::boot(&options, &rom, database.get(&rom))?;
+                Ok(Session { rom_name, ..session })
+            });
+        booted.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    });
+
+    // The debugger view takes the whole window, chip8 screen included. The launcher needs more pixels
+    // than the chip8 screen, which is then scaled up to them.
+    let (buffer_width, buffer_height, window_size) = if options.debugger {
+        (DEBUG_WIDTH, DEBUG_HEIGHT, LogicalSize::new((DEBUG_WIDTH * DEBUG_SCALE) as f64, (DEBUG_HEIGHT * DEBUG_SCALE) as f64))
+    } else if session.is_none() {
+        (LAUNCHER_WIDTH, LAUNCHER_HEIGHT, LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT))
+    } else {
+        (SCREEN_WIDTH, SCREEN_HEIGHT, LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT))
+    };
+    let mut launcher = session.is_none().then(|| Launcher::new(&options.rom_dir, &database, buffer_width, buffer_height));
+    let mut screen_canvas = Canvas::new(buffer_width, buffer_height);
+
     // Initializing window - event loop and config
     let mut event_loop = mini_gl_fb::glutin::event_loop::EventLoop::new();
     let config = config! {
         window_title: String::from("rusty-8"),
-        window_size: LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT),
-        buffer_size: Some(LogicalSize::new(SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)),
+        window_size: window_size,
+        buffer_size: Some(LogicalSize::new(buffer_width as u32, buffer_height as u32)),
         resizable: true,
         invert_y: false
     };
@@ -68,23 +330,30 @@ fn main() {
     let mut fb = mini_gl_fb::get_fancy(config, &event_loop);
     fb.change_buffer_format::<u8>(mini_gl_fb::BufferFormat::R);
     fb.use_grayscale_shader();
-    fb.update_buffer(&chip8.screen);
+    match (session.as_mut(), launcher.as_mut()) {
+        (Some(session), _) => fb.update_buffer(session.render(&mut screen_canvas)),
+        (None, Some(launcher)) => fb.update_buffer(launcher.render()),
+        (None, None) => unreachable!("the launcher starts when there's no ROM"),
+    }
+
+    // With --watch, the ROM file (and its source) are polled for changes to reload it
+    let mut watcher = options.watch.then(|| {
+        let mut watcher = FileWatcher::new(WATCH_INTERVAL);
+        watcher.watch(options.rom.as_ref().expect("--watch is validated with the options"));
+        if let Some(source) = &options.watch_source {
+            watcher.watch(source);
+        }
+        watcher
+    });
 
-    // Get handle to audio device, create audio source then make audio controller
-    let (_stream, handle) = OutputStream::try_default().unwrap();
-    let source = SineWave::new(815).take_duration(Duration::from_millis(12));
-    let beep = rodio::Sink::try_new(&handle).unwrap();
-    beep.set_volume(0.3);
+    // Open audio device (or fall back to silence), then start the (silent until gated) beep tone
+    let beep = open_backend(options.no_audio, options.waveform, options.tone_frequency, options.volume);
 
-    // Event loop helpers - callback ids and playback sound
-    let mut timers_id = None;
-    let mut tick_id = None;
+    // Event loop helpers - callback id
+    let mut frame_id = None;
 
     fb.glutin_handle_basic_input(&mut event_loop, |fb, input| {
-        read_chip8_keys(&mut chip8.keyboard, &input);
-
         let mut should_close = input.key_is_down(VirtualKeyCode::Escape);
-        should_close |= chip8.finished_running();
         should_close |= input.key_is_down(VirtualKeyCode::LControl) && input.key_is_down(VirtualKeyCode::W);
 
         if should_close { // Exit event loop and close program
@@ -95,66 +364,107 @@ fn main() {
             fb.redraw();
         }
 
-        let should_reboot = input.key_is_down(VirtualKeyCode::LControl) && input.key_is_down(VirtualKeyCode::R);
-        if should_reboot { // Reboot the chip8 with current ROM
-            chip8.reboot();
-            fb.update_buffer(&chip8.screen);
-        }
-
-        // Special handling needed when chip8 is idly waiting for a key press
-        if chip8.waiting.is_some() {
-            match chip8.keyboard.iter().position(|&key_down| key_down == true) {
-                Some(key_pos) => { // Answer which key was pressed and reset loop events
-                    chip8.answer_key(key_pos as u8);
-                    
-                    input.wait = false;
-                    timers_id = None;
-                    tick_id = None;
-                },
-                None => { // Clear event loop and kick into waiting mode
-                    input.wait = true;
-                    if !input.wakeups.is_empty() {
-                        input.wakeups.clear();
-                    }
-
-                    return true; // While chip8 isn't answered, ignore events
-                }
-            }
+        let should_toggle_mute = input.key_is_down(VirtualKeyCode::LControl) && input.key_pressed(VirtualKeyCode::M);
+        if should_toggle_mute {
+            beep.toggle_mute();
         }
 
         // ---- Event handling ----
         // Inserting events in the event queue
-        if let None = timers_id {
-            timers_id = Some(input.schedule_wakeup(Instant::now()));
-        }
-        
-        if let None = tick_id {
-            tick_id = Some(input.schedule_wakeup(Instant::now()));
+        if frame_id.is_none() {
+            frame_id = Some(input.schedule_wakeup(Instant::now()));
         }
 
-        // Executing event, if there is any.
+        // Rescheduling the frame event, if it's the one happening
+        let mut is_frame = false;
         if let Some(mut wakeup) = input.wakeup {
-            if Some(wakeup.id) == tick_id { // Tick one clock cycle of the chip8
-                chip8.tick();
+            if Some(wakeup.id) == frame_id {
+                is_frame = true;
+                wakeup.trigger_after(FRAME_DURATION);
+                input.reschedule_wakeup(wakeup);
+            }
+        }
+
+        // Hot reloading: a changed source is rebuilt, then a changed ROM is rebooted into
+        if let (Some(watcher), Some(running), true) = (watcher.as_mut(), session.as_mut(), is_frame) {
+            let rom_path = options.rom.as_ref().expect("--watch is validated with the options");
+            let changed = watcher.changed();
+            let is_rom_changed = changed.contains(&Path::new(rom_path));
+            let is_source_changed = options.watch_source.as_ref().is_some_and(|source| changed.contains(&Path::new(source)));
 
-                if chip8.screen_updated {
-                    fb.update_buffer(&chip8.screen);
+            if let (true, Some(command)) = (is_source_changed, &options.build) {
+                // The ROM it writes is reloaded once the watcher sees it change
+                if let Err(e) = build(command) {
+                    eprintln!("Failure building {}\n{}", rom_path, e);
+                }
+            }
+            if is_rom_changed {
+                match rom::read_rom(rom_path, running.rom_name.as_deref(), options.memory_map.rom_capacity()).and_then(|rom| running.reload(&rom)) {
+                    Ok(()) => {
+                        eprintln!("Reloaded {}", rom_path);
+                        beep.set_gate(false);
+                        fb.update_buffer(running.render(&mut screen_canvas));
+                    },
+                    Err(e) => eprintln!("Failure reloading {}, still running the previous ROM\n{}", rom_path, e),
                 }
+            }
+        }
 
-                wakeup.trigger_after(Duration::from_millis(2));
-                input.reschedule_wakeup(wakeup);
-            } else if Some(wakeup.id) == timers_id { // Tick chip8 timers on 60Hz
-                let is_beeping = chip8.tick_timers();
-                if is_beeping {
-                    beep.append(source.clone());
-                    beep.play()
+        if let Some(running) = session.as_mut() {
+            // F12 leaves the ROM for the launcher, when it was picked there
+            let (is_running, error) = match running.update(fb, input, &options, beep.as_ref(), is_frame, &mut screen_canvas) {
+                Ok(is_running) => (is_running && !(launcher.is_some() && input.key_pressed(VirtualKeyCode::F12)), None),
+                Err(e) => (false, Some(e)),
+            };
+
+            // Once the ROM stops, go back to the launcher, or close without one
+            if !is_running {
+                beep.set_gate(false);
+                if let Some(stopped) = session.take() {
+                    stopped.end(&options);
+                }
+                match launcher.as_mut() {
+                    Some(launcher) => {
+                        launcher.scan(&database);
+                        if let Some(e) = error {
+                            launcher.set_message(&e);
+                        }
+                        fb.update_buffer(launcher.render());
+                    },
+                    None => {
+                        if let Some(e) = error {
+                            eprintln!("{}", e);
+                        }
+                        return false;
+                    },
+                }
+            }
+        } else if let Some(launcher) = launcher.as_mut() {
+            for (key_code, key) in LAUNCHER_VIRTUAL_KEY_CODES.iter() {
+                if input.key_pressed(*key_code) {
+                    launcher.key(*key);
+                }
+            }
+
+            if BOOT_VIRTUAL_KEY_CODES.iter().any(|key_code| input.key_pressed(*key_code)) {
+                match launcher.selected().map(|selected| Session::boot(&options, &selected.rom, selected.info.as_ref())) {
+                    Some(Ok(booted)) => session = Some(booted),
+                    Some(Err(e)) => launcher.set_message(&e),
+                    None => {},
                 }
+            }
 
-                wakeup.trigger_after(Duration::from_millis(16));
-                input.reschedule_wakeup(wakeup);
+            match session.as_mut() {
+                Some(booted) => fb.update_buffer(booted.render(&mut screen_canvas)),
+                None if is_frame => fb.update_buffer(launcher.render()),
+                None => {},
             }
         }
         
         true
     });
+
+    if let Some(session) = session {
+        session.end(&options);
+    }
 }
@@ -0,0 +1,93 @@
+use crate::chip8::{FONT_SIZE, MEM_SIZE, STACK_SIZE};
+
+// Where things live in the 4Kb of RAM. The original interpreter lived in the first 512 bytes,
+// so programs start at 0x200, but interpreters disagree on the rest: most modern ones copy
+// the font to 0x050, which some ROMs read directly, and ETI-660 programs start at 0x600.
+
+/// Addresses of the font, the stack and the program in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub font_start: u16,    // Where the 0-F hex font (5 bytes per digit) is copied
+    pub stack_start: u16,   // Where return addresses are kept, when the stack is in memory
+    pub program_start: u16, // Where ROMs are loaded, and execution starts
+}
+
+impl MemoryMap {
+    /// De-facto standard layout of modern interpreters: font at 0x050, stack right after it.
+    pub const STANDARD: MemoryMap = MemoryMap {
+        font_start: 0x050,
+        stack_start: 0x0A0,
+        program_start: 0x200,
+    };
+
+    /// ETI-660 computers load programs at 0x600.
+    pub const ETI_660: MemoryMap = MemoryMap {
+        font_start: 0x050,
+        stack_start: 0x0A0,
+        program_start: 0x600,
+    };
+
+    /// Look up a preset by name: `rusty-8`, `standard` or `eti660`.
+    pub fn from_name(name: &str) -> Option<MemoryMap> {
+        match name {
+            "rusty-8" | "default" => Some(MemoryMap::default()),
+            "standard" => Some(MemoryMap::STANDARD),
+            "eti660" | "eti-660" => Some(MemoryMap::ETI_660),
+            _ => None
+        }
+    }
+
+    /// Check that the font, stack and (at least a byte of) program fit in memory, without overlapping.
+    /// The program is taken to run up to the end of memory, whatever the size of the ROM loaded.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.font_start as usize + FONT_SIZE > MEM_SIZE {
+            return Err(format!("Font at {:#05X} doesn't fit in memory", self.font_start));
+        }
+        if self.stack_start as usize + STACK_SIZE > MEM_SIZE {
+            return Err(format!("Stack at {:#05X} doesn't fit in memory", self.stack_start));
+        }
+        if self.program_start as usize >= MEM_SIZE {
+            return Err(format!("Program at {:#05X} is outside of memory", self.program_start));
+        }
+
+        let regions = [
+            ("Font", self.font_start as usize, FONT_SIZE),
+            ("Stack", self.stack_start as usize, STACK_SIZE),
+            ("Program", self.program_start as usize, MEM_SIZE - self.program_start as usize),
+        ];
+        for (i, &(name, start, size)) in regions.iter().enumerate() {
+            for &(other_name, other_start, other_size) in &regions[i + 1..] {
+                if start < other_start + other_size && other_start < start + size {
+                    return Err(format!("{} at {:#05X} overlaps the {} at {:#05X}",
+                        name, start, other_name.to_lowercase(), other_start));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bytes of ROM that fit in memory, from the program start to the end.
+    pub fn rom_capacity(&self) -> usize {
+        MEM_SIZE.saturating_sub(self.program_start as usize)
+    }
+
+    /// Parse an address, written in hex (`0x200`) or decimal (`512`).
+    pub fn parse_address(address: &str) -> Option<u16> {
+        match address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")) {
+            Some(hex) => u16::from_str_radix(hex, 16).ok(),
+            None => address.parse().ok(),
+        }
+    }
+}
+
+impl Default for MemoryMap {
+    /// Layout rusty-8 always had: stack at the very start of RAM, font right after it.
+    fn default() -> Self {
+        MemoryMap {
+            font_start: 0x0FF,
+            stack_start: 0x000,
+            program_start: 0x200,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::audio::Waveform;
+use rusty_8::{MemoryMap, Quirks, StackConfig, Timing};
+
+const USAGE: &str = "\
+Usage: rusty-8 [options] [rom_path]
+
+rom_path is a ROM file, - to read it from stdin, or a zip archive (asks which ROM if it holds several).
+Without it, a launcher lists the ROMs of --roms to pick one with the keypad, and F12 returns to it.
+
+Options:
+    --ipf <n>               Instructions per frame (default 8, ~500Hz)
+    --quirks <preset>       Quirks preset: rusty-8, vip or chip48 (default the ROM database's, or rusty-8)
+    --timing <model>        Instruction timing: fixed (--ipf per frame) or vip (default fixed)
+    --stack-depth <n>       Nested calls allowed, e.g. 12 (VIP) or 16 (CHIP-48) (default unlimited)
+    --stack-outside-memory  Keep the stack apart from RAM, like modern interpreters
+    --memory-map <preset>   Memory layout: rusty-8, standard (font at 0x050) or eti660 (load at 0x600)
+    --font-address <addr>   Override where the font is copied, e.g. 0x050
+    --stack-address <addr>  Override where the stack lives, when in memory
+    --load-address <addr>   Override where the ROM is loaded and execution starts, e.g. 0x600
+    --tone <hz>             Beep frequency (default 815)
+    --waveform <shape>      Beep waveform: square or sine (default square)
+    --volume <0-1>          Beep volume (default 0.3)
+    --no-audio              Run without sound, even if there's an audio device
+    --profile <path>        Write a report of the rom's hot spots, calls and frame load at exit
+    --debugger              Show memory and registers next to the screen, to pause, step and poke them
+    --cheats <dir>          Where cheat files are kept, one per ROM (default cheats)
+    --roms <dir>            Where the launcher looks for ROMs, and an extra database.txt (default roms)
+    --watch                 Reload and reboot the ROM whenever its file changes
+    --watch-source <path>   Also watch the ROM's source, running --build when it changes (implies --watch)
+    --build <command>       Shell command turning the source into the ROM, e.g. \"make game.ch8\"";
+
+/// Command line options of the windowed emulator.
+pub struct Options {
+    pub rom: Option<String>,          // None starts the launcher
+    pub ticks_per_frame: usize,
+    pub quirks: Option<Quirks>,       // None lets the ROM database pick
+    pub timing: Timing,
+    pub stack_config: StackConfig,
+    pub memory_map: MemoryMap,
+    pub tone_frequency: f32,
+    pub waveform: Waveform,
+    pub volume: f32,
+    pub no_audio: bool,
+    pub profile: Option<String>,
+    pub debugger: bool,
+    pub cheats: String,
+    pub rom_dir: String,
+    pub watch: bool,
+    pub watch_source: Option<String>,
+    pub build: Option<String>,
+}
+
+impl Options {
+    /// Parse options from the process arguments.
+    /// On failure, returns a message explaining what's wrong, followed by the usage.
+    pub fn from_args() -> Result<Options, String> {
+        let mut options = Options {
+            rom: None,
+            ticks_per_frame: 8,
+            quirks: None,
+            timing: Timing::default(),
+            stack_config: StackConfig::default(),
+            memory_map: MemoryMap::default(),
+            tone_frequency: 815.0,
+            waveform: Waveform::Square,
+            volume: 0.3,
+            no_audio: false,
+            profile: None,
+            debugger: false,
+            cheats: String::from("cheats"),
+            rom_dir: String::from("roms"),
+            watch: false,
+            watch_source: None,
+            build: None,
+        };
+
+        let mut address_overrides = (None, None, None); // Font, stack and load address, over the preset
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("Missing value for {}\n\n{}", name, USAGE));
+
+            match arg.as_str() {
+                "--ipf" => options.ticks_per_frame = parse(&arg, &value("--ipf")?)?,
+                "--quirks" => {
+                    let name = value("--quirks")?;
+                    options.quirks = Some(Quirks::from_name(&name)
+                        .ok_or(format!("Unknown quirks preset '{}'\n\n{}", name, USAGE))?);
+                },
+                "--timing" => {
+                    let name = value("--timing")?;
+                    options.timing = Timing::from_name(&name)
+                        .ok_or(format!("Unknown timing model '{}'\n\n{}", name, USAGE))?;
+                },
+                "--stack-depth" => {
+                    let depth = value("--stack-depth")?;
+                    options.stack_config.max_depth = StackConfig::parse_depth(&depth)
+                        .ok_or(format!("Invalid value '{}' for --stack-depth\n\n{}", depth, USAGE))?;
+                },
+                "--stack-outside-memory" => options.stack_config.in_memory = false,
+                "--memory-map" => {
+                    let name = value("--memory-map")?;
+                    options.memory_map = MemoryMap::from_name(&name)
+                        .ok_or(format!("Unknown memory map preset '{}'\n\n{}", name, USAGE))?;
+                },
+                "--font-address" => address_overrides.0 = Some(parse_address(&arg, &value("--font-address")?)?),
+                "--stack-address" => address_overrides.1 = Some(parse_address(&arg, &value("--stack-address")?)?),
+                "--load-address" => address_overrides.2 = Some(parse_address(&arg, &value("--load-address")?)?),
+                "--tone" => options.tone_frequency = parse(&arg, &value("--tone")?)?,
+                "--waveform" => options.waveform = parse(&arg, &value("--waveform")?)?,
+                "--volume" => options.volume = parse(&arg, &value("--volume")?)?,
+                "--no-audio" => options.no_audio = true,
+                "--profile" => options.profile = Some(value("--profile")?),
+                "--debugger" => options.debugger = true,
+                "--cheats" => options.cheats = value("--cheats")?,
+                "--roms" => options.rom_dir = value("--roms")?,
+                "--watch" => options.watch = true,
+                "--watch-source" => {
+                    options.watch_source = Some(value("--watch-source")?);
+                    options.watch = true;
+                },
+                "--build" => options.build = Some(value("--build")?),
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n\n{}", arg, USAGE)),
+                _ => options.rom = Some(arg),
+            }
+        }
+
+        let (font_start, stack_start, program_start) = address_overrides;
+        options.memory_map.font_start = font_start.unwrap_or(options.memory_map.font_start);
+        options.memory_map.stack_start = stack_start.unwrap_or(options.memory_map.stack_start);
+        options.memory_map.program_start = program_start.unwrap_or(options.memory_map.program_start);
+        options.memory_map.validate().map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+
+        if options.watch && options.rom.as_deref().unwrap_or("-") == "-" {
+            return Err(format!("Nothing to watch, --watch needs a ROM file\n\n{}", USAGE));
+        }
+        if options.watch_source.is_some() != options.build.is_some() {
+            return Err(format!("--watch-source and --build go together, to rebuild the ROM when its source changes\n\n{}", USAGE));
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_address(name: &str, value: &str) -> Result<u16, String> {
+    MemoryMap::parse_address(value).ok_or(format!("Invalid address '{}' for {}\n\n{}", value, name, USAGE))
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value '{}' for {}\n\n{}", value, name, USAGE))
+}
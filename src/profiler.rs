@@ -0,0 +1,147 @@
+// The profiler counts what a ROM spends its time on, to find its hot spots and pick a fitting
+// instructions per frame setting: a ROM that draws a lot needs more of them to run smoothly.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::chip8::{Chip8, TickObserver, MEM_SIZE};
+use crate::disasm::disassemble;
+
+const HISTOGRAM_WIDTH: u64 = 40;    // Characters the longest histogram bar takes
+
+/// Hot spots a report lists, unless asked otherwise.
+pub const REPORT_TOP_ADDRESSES: usize = 20;
+
+/// Counts executed instructions, when passed to `Chip8::run_frame_observed`.
+pub struct Profiler {
+    pc_counts: Vec<u64>,        // Executions per address
+    opcodes: Vec<u16>,          // Last instruction executed at each address
+    class_counts: [u64; 16],    // Executions per opcode class (first hex digit)
+    call_counts: Vec<u64>,      // 2NNN calls per subroutine address
+    frames: u64,
+    frame_start_cycle: u64,     // Clock cycle the current frame started on
+    frame_draws: u64,           // DXYN run in the current frame
+    cycles_per_frame: BTreeMap<u64, u64>,   // Clock cycles in a frame, and how many frames had that many
+    draws_per_frame: BTreeMap<u64, u64>,    // DXYN in a frame, and how many frames had that many
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            pc_counts: vec![0; MEM_SIZE],
+            opcodes: vec![0; MEM_SIZE],
+            class_counts: [0; 16],
+            call_counts: vec![0; MEM_SIZE],
+            frames: 0,
+            frame_start_cycle: 0,
+            frame_draws: 0,
+            cycles_per_frame: BTreeMap::new(),
+            draws_per_frame: BTreeMap::new(),
+        }
+    }
+
+    /// Number of instructions executed so far.
+    pub fn instructions(&self) -> u64 {
+        self.class_counts.iter().sum()
+    }
+
+    /// Times the instruction at `addr` was executed.
+    pub fn count_at(&self, addr: u16) -> u64 {
+        self.pc_counts.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    /// Write a report of the hottest `top` addresses, instructions by opcode class,
+    /// subroutine calls, and how many cycles and draws frames took.
+    pub fn write_report<W: Write>(&self, out: &mut W, top: usize) -> io::Result<()> {
+        let instructions = self.instructions();
+        let percent = |count: u64| 100.0 * count as f64 / instructions.max(1) as f64;
+        writeln!(out, "{} instructions executed over {} frames", instructions, self.frames)?;
+
+        let mut hot_spots: Vec<usize> = (0..MEM_SIZE).filter(|&addr| self.pc_counts[addr] > 0).collect();
+        hot_spots.sort_by_key(|&addr| std::cmp::Reverse(self.pc_counts[addr]));
+        writeln!(out, "\nTop {} addresses:", top.min(hot_spots.len()))?;
+        writeln!(out, "{:>12} {:>7}  addr  opcode  instruction", "count", "%")?;
+        for &addr in hot_spots.iter().take(top) {
+            let (count, opcode) = (self.pc_counts[addr], self.opcodes[addr]);
+            writeln!(out, "{:>12} {:>6.2}%  {:03X}   {:04X}    {}", count, percent(count), addr, opcode, disassemble(opcode))?;
+        }
+
+        writeln!(out, "\nOpcode classes:")?;
+        writeln!(out, "{:>12} {:>7}  class", "count", "%")?;
+        for (class, &count) in self.class_counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+            writeln!(out, "{:>12} {:>6.2}%  {:X}XXX", count, percent(count), class)?;
+        }
+
+        let mut subroutines: Vec<usize> = (0..MEM_SIZE).filter(|&addr| self.call_counts[addr] > 0).collect();
+        subroutines.sort_by_key(|&addr| std::cmp::Reverse(self.call_counts[addr]));
+        writeln!(out, "\nSubroutine calls (2NNN):")?;
+        writeln!(out, "{:>12}  subroutine", "calls")?;
+        for &addr in &subroutines {
+            writeln!(out, "{:>12}  {:03X}", self.call_counts[addr], addr)?;
+        }
+
+        writeln!(out, "\nClock cycles per frame:")?;
+        write_histogram(out, &self.cycles_per_frame, "cycles")?;
+        writeln!(out, "\nDXYN per frame:")?;
+        write_histogram(out, &self.draws_per_frame, "draws")
+    }
+
+    /// Write the report (see `write_report`) into a new file at `path`.
+    pub fn save_report<P: AsRef<Path>>(&self, path: P, top: usize) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_report(&mut out, top)?;
+        out.flush()
+    }
+}
+
+/// Write a frames histogram as text bars, one per value.
+fn write_histogram<W: Write>(out: &mut W, histogram: &BTreeMap<u64, u64>, value_name: &str) -> io::Result<()> {
+    let most_frames = histogram.values().copied().max().unwrap_or(0);
+
+    writeln!(out, "{:>12} {:>8}", value_name, "frames")?;
+    for (&value, &frames) in histogram {
+        let bar = (frames * HISTOGRAM_WIDTH).div_ceil(most_frames) as usize;
+        writeln!(out, "{:>12} {:>8}  {}", value, frames, "#".repeat(bar))?;
+    }
+
+    Ok(())
+}
+
+impl TickObserver for Profiler {
+    fn before_frame(&mut self, chip8: &mut Chip8) {
+        self.frame_start_cycle = chip8.cycles();
+        self.frame_draws = 0;
+    }
+
+    fn after_frame(&mut self, chip8: &mut Chip8) {
+        self.frames += 1;
+        *self.cycles_per_frame.entry(chip8.cycles().wrapping_sub(self.frame_start_cycle)).or_insert(0) += 1;
+        *self.draws_per_frame.entry(self.frame_draws).or_insert(0) += 1;
+    }
+
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        let (pc, instruction) = match chip8.last_instruction() {
+            Some(executed) => executed,
+            None => return,
+        };
+
+        let addr = pc as usize % MEM_SIZE;
+        self.pc_counts[addr] += 1;
+        self.opcodes[addr] = instruction;
+        self.class_counts[(instruction >> 12) as usize] += 1;
+
+        match instruction >> 12 {
+            0x2 => self.call_counts[(instruction & 0x0FFF) as usize] += 1,
+            0xD => self.frame_draws += 1,
+            _ => {}
+        }
+    }
+}
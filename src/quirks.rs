@@ -0,0 +1,95 @@
+// CHIP-8 was never formally specified, so every interpreter since the COSMAC VIP one
+// has behaved a little differently. ROMs are usually written (and tested) against one
+// specific interpreter, and rely on its behaviour for a handful of instructions.
+// These are known as "quirks" -> https://github.com/chip-8/chip-8-database/blob/master/database/quirks.json
+
+/// Where return addresses of CALL (2NNN) are kept, and how many fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackConfig {
+    pub max_depth: Option<usize>,   // Nested CALLs allowed: 12 on the VIP, 16 on CHIP-48. None for no limit
+    pub in_memory: bool,            // Kept in RAM like the original interpreters, or outside addressable memory
+}
+
+impl StackConfig {
+    /// The COSMAC VIP had room for 12 return addresses in RAM.
+    pub const COSMAC_VIP: StackConfig = StackConfig { max_depth: Some(12), in_memory: true };
+
+    /// CHIP-48 and most modern interpreters keep 16 return addresses apart from RAM.
+    pub const CHIP48: StackConfig = StackConfig { max_depth: Some(16), in_memory: false };
+
+    /// Parse a maximum depth: a number of return addresses or `unlimited`.
+    pub fn parse_depth(depth: &str) -> Option<Option<usize>> {
+        match depth {
+            "unlimited" => Some(None),
+            _ => depth.parse().ok().map(Some),
+        }
+    }
+}
+
+impl Default for StackConfig {
+    /// rusty-8 always kept its stack at the start of RAM, limited only by room there.
+    fn default() -> Self {
+        StackConfig { max_depth: None, in_memory: true }
+    }
+}
+
+/// Interpreter behaviours that differ between CHIP-8 implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    pub shift_uses_vy: bool,    // 8XY6/8XYE shift V[y] into V[x], instead of shifting V[x] in place
+    pub memory_increments_i: bool,  // FX55/FX65 leave I pointing right after the last register
+    pub jump_uses_vx: bool,     // BNNN jumps to NNN + V[x] instead of NNN + V[0]
+    pub logic_resets_vf: bool,  // 8XY1/8XY2/8XY3 set V[f] to 0
+    pub key_wait_release: bool, // FX0A completes when the pressed key is released, not when pressed
+    pub display_wait: bool,     // DXYN waits for the next 60Hz frame (vertical blank) before drawing
+    pub wrap_addresses: bool,   // PC and memory accesses wrap around past 0xFFF (12 bits), instead of failing
+}
+
+impl Quirks {
+    /// Behaviour of the original COSMAC VIP interpreter.
+    pub const COSMAC_VIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        memory_increments_i: true,
+        jump_uses_vx: false,
+        logic_resets_vf: true,
+        key_wait_release: true,
+        display_wait: true,
+        wrap_addresses: true,
+    };
+
+    /// Behaviour of CHIP-48 (and SUPER-CHIP), which most ROMs after 1990 target.
+    pub const CHIP48: Quirks = Quirks {
+        shift_uses_vy: false,
+        memory_increments_i: false,
+        jump_uses_vx: true,
+        logic_resets_vf: false,
+        key_wait_release: false,
+        display_wait: false,
+        wrap_addresses: false,
+    };
+
+    /// Look up a preset by name: `rusty-8`, `vip` or `chip48`.
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name {
+            "rusty-8" | "default" => Some(Quirks::default()),
+            "vip" | "cosmac-vip" => Some(Quirks::COSMAC_VIP),
+            "chip48" | "schip" => Some(Quirks::CHIP48),
+            _ => None
+        }
+    }
+}
+
+impl Default for Quirks {
+    /// Behaviour rusty-8 always had, which most ROMs in `roms/` are happy with.
+    fn default() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            memory_increments_i: true,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            key_wait_release: false,
+            display_wait: false,
+            wrap_addresses: false,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// Reading ROMs from where they're kept: files, stdin (`-`), and zip archives since ROM packs are
+// usually distributed zipped. Archives are recognized by their contents, whatever their name.
+//
+// An archive holding a single ROM gives that one, otherwise it's picked by name: its path in the
+// archive or its file name, with or without extension, ignoring case. Files that come along with
+// ROMs in packs (README.txt, screenshots, sources...) aren't counted as ROMs.
+
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+use crate::error::RomError;
+use crate::hash::crc32;
+
+const ZIP_LOCAL_HEADER: u32 = 0x04034B50;
+const ZIP_CENTRAL_HEADER: u32 = 0x02014B50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x06054B50;
+const ZIP_END_OF_DIRECTORY_SIZE: usize = 22;
+const ZIP_MAX_COMMENT: usize = u16::MAX as usize;
+
+const ZIP_STORED: u16 = 0;
+const ZIP_DEFLATED: u16 = 8;
+const ZIP_ENCRYPTED: u16 = 1;   // General purpose flag
+
+// Extensions of the files that aren't ROMs, in ROM packs
+const NOT_ROM_EXTENSIONS: [&str; 16] = [
+    "txt", "md", "nfo", "diz", "doc", "pdf", "htm", "html",
+    "png", "jpg", "gif", "bmp", "8o", "c8s", "asm", "src",
+];
+
+/// A file in a zip archive, as listed in its central directory.
+struct ZipEntry {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    offset: usize,  // Of its local header
+}
+
+/// Read the ROM at `source`, a file or `-` for stdin. From a zip archive, reads the ROM named
+/// `name`, or its only ROM if None, failing if it's bigger than `capacity` (see `MemoryMap::rom_capacity`).
+pub fn read_rom<P: AsRef<Path>>(source: P, name: Option<&str>, capacity: usize) -> Result<Vec<u8>, RomError> {
+    let bytes = read_source(source.as_ref())?;
+    match is_zip(&bytes) {
+        true => read_zip_rom(&bytes, name, capacity),
+        false => Ok(bytes),
+    }
+}
+
+/// Same as `read_rom` with no name, but when a zip archive holds several ROMs, lists them and asks
+/// which one on the terminal (the answer is read from stdin). For command line frontends.
+/// Returns the ROM, with the name it was picked by if it was, to read the same one again.
+pub fn read_rom_interactive<P: AsRef<Path>>(source: P, capacity: usize) -> Result<(Vec<u8>, Option<String>), RomError> {
+    let source = source.as_ref();
+    let bytes = read_source(source)?;
+    if !is_zip(&bytes) {
+        return Ok((bytes, None));
+    }
+
+    match read_zip_rom(&bytes, None, capacity) {
+        // Stdin can't answer when the archive itself came from it
+        Err(RomError::SeveralRomsInArchive(names)) if source != Path::new("-") => {
+            let name = ask_rom_name(&names)?;
+            Ok((read_zip_rom(&bytes, Some(&name), capacity)?, Some(name)))
+        },
+        result => Ok((result?, None)),
+    }
+}
+
+fn read_source(source: &Path) -> Result<Vec<u8>, RomError> {
+    if source == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        return Ok(bytes);
+    }
+
+    Ok(std::fs::read(source)?)
+}
+
+/// List the ROMs, and read which one to pick: its number or name.
+fn ask_rom_name(names: &[String]) -> Result<String, RomError> {
+    eprintln!("Several ROMs in the zip archive:");
+    for (index, name) in names.iter().enumerate() {
+        eprintln!("\t{:>3}. {}", index + 1, name);
+    }
+    eprint!("Which one (number or name)? ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        eprintln!();
+        return Err(RomError::SeveralRomsInArchive(names.to_vec()));
+    }
+
+    let answer = answer.trim();
+    Ok(match answer.parse::<usize>() {
+        Ok(number) if (1..=names.len()).contains(&number) => names[number - 1].clone(),
+        _ => answer.to_string(),
+    })
+}
+
+/// Query wether `bytes` are a zip archive. No CHIP-8 ROM starts like one: it'd be a 5XYB instruction.
+pub fn is_zip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZIP_LOCAL_HEADER.to_le_bytes()) || bytes.starts_with(&ZIP_END_OF_DIRECTORY.to_le_bytes())
+}
+
+/// Names of the ROMs in a zip archive.
+pub fn zip_roms(archive: &[u8]) -> Result<Vec<String>, RomError> {
+    Ok(zip_entries(archive)?.into_iter().map(|entry| entry.name).filter(|name| is_rom(name)).collect())
+}
+
+/// Read the ROM named `name` from a zip archive, or its only ROM if None.
+/// Fails if it's bigger than `capacity`, without inflating it.
+pub fn read_zip_rom(archive: &[u8], name: Option<&str>, capacity: usize) -> Result<Vec<u8>, RomError> {
+    let entries = zip_entries(archive)?;
+    let entry = match name {
+        // ROMs go first, for a name matching both BRIX and BRIX.txt
+        Some(name) => entries.iter()
+            .filter(|entry| !entry.name.ends_with('/') && matches_name(&entry.name, name))
+            .min_by_key(|entry| !is_rom(&entry.name))
+            .ok_or_else(|| RomError::NotInArchive(name.to_string()))?,
+        None => {
+            let roms: Vec<&ZipEntry> = entries.iter().filter(|entry| is_rom(&entry.name)).collect();
+            match roms.as_slice() {
+                [] => return Err(RomError::NoRomInArchive),
+                [rom] => *rom,
+                _ => return Err(RomError::SeveralRomsInArchive(roms.iter().map(|rom| rom.name.clone()).collect())),
+            }
+        },
+    };
+
+    extract(archive, entry, capacity)
+}
+
+/// Query wether the file at `path` (in an archive or a directory) looks like a ROM, by its name.
+pub fn is_rom(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name.rsplit_once('.').map_or("", |(_, extension)| extension);
+
+    !path.ends_with('/')
+        && !path.starts_with("__MACOSX/")  // Resource forks macOS adds
+        && !file_name.starts_with('.')
+        && !NOT_ROM_EXTENSIONS.iter().any(|not_rom| extension.eq_ignore_ascii_case(not_rom))
+}
+
+/// Query wether the archive file at `path` goes by `name`.
+fn matches_name(path: &str, name: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+    [path, file_name, stem].iter().any(|candidate| candidate.eq_ignore_ascii_case(name))
+}
+
+/// List the files of a zip archive, from its central directory.
+fn zip_entries(archive: &[u8]) -> Result<Vec<ZipEntry>, RomError> {
+    // The end of central directory record closes the archive, followed by a comment at most
+    let last = archive.len().checked_sub(ZIP_END_OF_DIRECTORY_SIZE).ok_or_else(truncated)?;
+    let end = (last.saturating_sub(ZIP_MAX_COMMENT)..=last).rev()
+        .find(|&at| read_u32(archive, at).ok() == Some(ZIP_END_OF_DIRECTORY))
+        .ok_or_else(truncated)?;
+
+    let count = read_u16(archive, end + 10)? as usize;
+    let mut at = read_u32(archive, end + 16)? as usize;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if read_u32(archive, at)? != ZIP_CENTRAL_HEADER {
+            return Err(RomError::Archive("corrupt central directory".to_string()));
+        }
+
+        let name_len = read_u16(archive, at + 28)? as usize;
+        let extra_len = read_u16(archive, at + 30)? as usize;
+        let comment_len = read_u16(archive, at + 32)? as usize;
+        let name = archive.get(at + 46..at + 46 + name_len).ok_or_else(truncated)?;
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            flags: read_u16(archive, at + 8)?,
+            method: read_u16(archive, at + 10)?,
+            crc: read_u32(archive, at + 16)?,
+            compressed_size: read_u32(archive, at + 20)? as usize,
+            size: read_u32(archive, at + 24)? as usize,
+            offset: read_u32(archive, at + 42)? as usize,
+        });
+
+        at += 46 + name_len + extra_len + comment_len;
+    }
+
+    Ok(entries)
+}
+
+/// Decompress a file of a zip archive, checking it against its CRC.
+fn extract(archive: &[u8], entry: &ZipEntry, capacity: usize) -> Result<Vec<u8>, RomError> {
+    if entry.flags & ZIP_ENCRYPTED != 0 {
+        return Err(RomError::Archive(format!("{} is encrypted", entry.name)));
+    }
+    // A ROM that won't fit would fail to load anyway, no need to inflate it
+    if entry.size > capacity {
+        return Err(RomError::TooLarge { size: entry.size, capacity });
+    }
+
+    if read_u32(archive, entry.offset)? != ZIP_LOCAL_HEADER {
+        return Err(RomError::Archive(format!("corrupt header for {}", entry.name)));
+    }
+    let name_len = read_u16(archive, entry.offset + 26)? as usize;
+    let extra_len = read_u16(archive, entry.offset + 28)? as usize;
+    let start = entry.offset + 30 + name_len + extra_len;
+    let data = archive.get(start..start + entry.compressed_size).ok_or_else(truncated)?;
+
+    let rom = match entry.method {
+        ZIP_STORED => data.to_vec(),
+        ZIP_DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(data, entry.size)
+            .map_err(|_| RomError::Archive(format!("{} is corrupt, it doesn't inflate", entry.name)))?,
+        method => return Err(RomError::Archive(format!(
+            "{} is compressed with method {}, only stored and deflated files are supported", entry.name, method))),
+    };
+
+    if rom.len() != entry.size || crc32(&rom) != entry.crc {
+        return Err(RomError::Archive(format!("{} is corrupt, its CRC doesn't match", entry.name)));
+    }
+    Ok(rom)
+}
+
+fn truncated() -> RomError {
+    RomError::Archive("truncated".to_string())
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, RomError> {
+    match bytes.get(at..at + 2) {
+        Some(&[low, high]) => Ok(u16::from_le_bytes([low, high])),
+        _ => Err(truncated()),
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, RomError> {
+    match bytes.get(at..at + 4) {
+        Some(&[b0, b1, b2, b3]) => Ok(u32::from_le_bytes([b0, b1, b2, b3])),
+        _ => Err(truncated()),
+    }
+}
@@ -0,0 +1,111 @@
+// A database of known ROMs, keyed by the SHA-1 of their contents, for frontends to show what a ROM
+// is and run it with the right quirks. The one built in comes from roms/database.txt. A line per ROM,
+// fields separated by |, blank lines and lines starting with # are ignored:
+//
+//   sha1 | title | author | year | quirks | description
+//
+// Only the title is required, e.g.
+//
+//   f13766c14aeb02ad8d4d103cb5eadd282d20cddc | Brix | Andreas Gustafsson | 1990 | | Breakout clone
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::hash::sha1_hex;
+use crate::quirks::Quirks;
+
+const BUILTIN: &str = include_str!("../roms/database.txt");
+
+/// What's known about a ROM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomInfo {
+    pub title: String,
+    pub author: Option<String>,
+    pub year: Option<u16>,
+    pub quirks: Option<Quirks>,     // Preset the ROM needs, if not the default one
+    pub description: Option<String>,
+}
+
+/// Known ROMs, by SHA-1.
+#[derive(Debug, Clone, Default)]
+pub struct RomDatabase {
+    roms: HashMap<String, RomInfo>,
+}
+
+impl RomDatabase {
+    /// The database rusty-8 comes with, of the ROMs in roms/.
+    pub fn builtin() -> Self {
+        RomDatabase::parse(BUILTIN).expect("the built-in ROM database is valid")
+    }
+
+    /// Parse a database. On failure, returns a message pointing at the faulty line.
+    pub fn parse(text: &str) -> Result<RomDatabase, String> {
+        let mut database = RomDatabase::default();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (sha1, info) = parse_entry(line).ok_or(format!("line {}: invalid entry '{}'", line_index + 1, line))?;
+            database.roms.insert(sha1, info);
+        }
+
+        Ok(database)
+    }
+
+    /// Read and parse the database at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<RomDatabase, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        RomDatabase::parse(&text)
+    }
+
+    /// Add the entries of `other`, replacing the ones already known.
+    pub fn merge(&mut self, other: RomDatabase) {
+        self.roms.extend(other.roms);
+    }
+
+    /// What's known about `rom`, if anything.
+    pub fn get(&self, rom: &[u8]) -> Option<&RomInfo> {
+        self.roms.get(&sha1_hex(rom))
+    }
+
+    /// Number of known ROMs.
+    pub fn len(&self) -> usize {
+        self.roms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roms.is_empty()
+    }
+}
+
+fn parse_entry(line: &str) -> Option<(String, RomInfo)> {
+    let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+    let (sha1, title, author, year, quirks, description) = match fields.as_slice() {
+        [sha1, title, author, year, quirks, description] => (sha1, title, author, year, quirks, description),
+        _ => return None,
+    };
+
+    let sha1 = sha1.to_ascii_lowercase();
+    if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) || title.is_empty() {
+        return None;
+    }
+
+    let optional = |field: &str| (!field.is_empty()).then(|| field.to_string());
+    let info = RomInfo {
+        title: title.to_string(),
+        author: optional(author),
+        year: match *year {
+            "" => None,
+            year => Some(year.parse().ok()?),
+        },
+        quirks: match *quirks {
+            "" => None,
+            name => Some(Quirks::from_name(name)?),
+        },
+        description: optional(description),
+    };
+
+    Some((sha1, info))
+}
@@ -0,0 +1,261 @@
+// Scripts drive and check a running chip8, for automated tests and bots. They're written in Rhai
+// (https://rhai.rs), a small Rust-like language, and run as an observer: the script's top level
+// runs once before the first frame, then these functions run whenever the script defines them:
+//
+//   fn on_frame_start() { ... }      Before each frame, e.g. to press keys
+//   fn on_frame_end() { ... }        After each frame, once the timers ticked
+//   fn on_write(addr, value) { ... } After an instruction wrote `value` at `addr`
+//
+// and `on_pc(addr, |pc| { ... })` at the top level runs a closure after each time the instruction
+// at `addr` runs. Scripts see the chip8 through these functions:
+//
+//   peek(addr), poke(addr, value)    Memory bytes
+//   reg(name), set_reg(name, value)  Registers: "V0" to "VF", "I", "PC", "SP" (depth), "DT", "ST"
+//   press(key), release(key), key(key)    Keypad [0..F]; key tells wether it's down
+//   pixel(x, y)                      Wether a screen pixel is lit
+//   screenshot(path)                 Save the screen as a PGM image
+//   save_state(), load_state(state)  Save states, as blobs
+//   frame(), cycles()                Frames and clock cycles run so far
+//   stop()                           End the run, as successful
+//
+// `throw "message"` fails the run, e.g. when an assertion doesn't hold:
+//
+//   fn on_frame_end() {
+//       if frame() == 120 && peek(0x2F0) != 3 { throw "should still have 3 lives"; }
+//   }
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::rc::Rc;
+
+use rhai::{Blob, CallFnOptions, Dynamic, Engine, EvalAltResult, FnPtr, Scope, AST, INT};
+
+use crate::chip8::{Chip8, TickObserver, MEM_SIZE, SCREEN_WIDTH, SCREEN_HEIGHT};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// What the script's API functions reach into.
+struct Shared {
+    chip8: Chip8,       // The running chip8, swapped in for as long as the script runs
+    frames: u64,
+    pc_hooks: Vec<(u16, FnPtr)>,
+    stopped: bool,
+}
+
+/// A loaded script, run when passed to `Chip8::run_frame_observed`.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    shared: Rc<RefCell<Shared>>,
+    started: bool,              // Top level already ran
+    hooks: (bool, bool, bool),  // Script defines on_frame_start, on_frame_end and on_write
+    error: Option<String>,
+}
+
+impl Script {
+    /// Compile a script. On failure, returns a message pointing at the faulty line.
+    pub fn new(source: &str) -> Result<Script, String> {
+        let shared = Rc::new(RefCell::new(Shared { chip8: Chip8::new(), frames: 0, pc_hooks: Vec::new(), stopped: false }));
+        let engine = new_engine(&shared);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+
+        let defines = |name: &str, params: usize| ast.iter_functions().any(|f| f.name == name && f.params.len() == params);
+        let hooks = (defines("on_frame_start", 0), defines("on_frame_end", 0), defines("on_write", 2));
+
+        Ok(Script { engine, ast, scope: Scope::new(), shared, started: false, hooks, error: None })
+    }
+
+    /// Read and compile the script at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Script, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Script::new(&source)
+    }
+
+    /// Why the script failed (threw, or misused the API), if it did. It doesn't run anymore after.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Query wether the script called stop().
+    pub fn is_stopped(&self) -> bool {
+        self.shared.borrow().stopped
+    }
+
+    /// Run `f` with `chip8` reachable from the script, keeping the first error.
+    fn run<F: FnOnce(&mut Script) -> ScriptResult<()>>(&mut self, chip8: &mut Chip8, f: F) {
+        if self.error.is_some() || self.is_stopped() {
+            return;
+        }
+
+        std::mem::swap(chip8, &mut self.shared.borrow_mut().chip8);
+        let result = f(self);
+        std::mem::swap(chip8, &mut self.shared.borrow_mut().chip8);
+
+        if let Err(e) = result {
+            self.error = Some(e.to_string());
+        }
+    }
+
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) -> ScriptResult<()> {
+        // The top level ran once already: running it again would repeat its on_pc() calls
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine.call_fn_with_options::<Dynamic>(options, &mut self.scope, &self.ast, name, args).map(|_| ())
+    }
+}
+
+impl TickObserver for Script {
+    fn before_frame(&mut self, chip8: &mut Chip8) {
+        if !self.started {
+            self.started = true;
+            self.run(chip8, |script| script.engine.run_ast_with_scope(&mut script.scope, &script.ast));
+        }
+        if self.hooks.0 {
+            self.run(chip8, |script| script.call("on_frame_start", ()));
+        }
+    }
+
+    fn after_frame(&mut self, chip8: &mut Chip8) {
+        self.shared.borrow_mut().frames += 1;
+        if self.hooks.1 {
+            self.run(chip8, |script| script.call("on_frame_end", ()));
+        }
+    }
+
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        if let Some((pc, _)) = chip8.last_instruction() {
+            let hooks: Vec<FnPtr> = self.shared.borrow().pc_hooks.iter()
+                .filter(|(addr, _)| *addr == pc)
+                .map(|(_, hook)| hook.clone())
+                .collect();
+            for hook in hooks {
+                self.run(chip8, |script| hook.call::<Dynamic>(&script.engine, &script.ast, (pc as INT,)).map(|_| ()));
+            }
+        }
+
+        if self.hooks.2 && !chip8.memory_writes().is_empty() {
+            for (addr, value) in chip8.memory_writes().to_vec() {
+                self.run(chip8, |script| script.call("on_write", (addr as INT, value as INT)));
+            }
+        }
+    }
+}
+
+/// An engine with the chip8 API registered, reaching into `shared`.
+fn new_engine(shared: &Rc<RefCell<Shared>>) -> Engine {
+    let mut engine = Engine::new();
+
+    let state = shared.clone();
+    engine.register_fn("peek", move |addr: INT| -> ScriptResult<INT> {
+        Ok(state.borrow().chip8.memory()[address(addr)?] as INT)
+    });
+    let state = shared.clone();
+    engine.register_fn("poke", move |addr: INT, value: INT| -> ScriptResult<()> {
+        state.borrow_mut().chip8.memory_mut()[address(addr)?] = byte(value)?;
+        Ok(())
+    });
+
+    let state = shared.clone();
+    engine.register_fn("reg", move |name: &str| -> ScriptResult<INT> {
+        let regs = state.borrow().chip8.registers();
+        let value = match name {
+            "I" => regs.i,
+            "PC" => regs.pc,
+            "SP" => regs.sp as u16 / 2,
+            "DT" => regs.dt as u16,
+            "ST" => regs.st as u16,
+            _ => regs.v[v_index(name)?] as u16,
+        };
+        Ok(value as INT)
+    });
+    let state = shared.clone();
+    engine.register_fn("set_reg", move |name: &str, value: INT| -> ScriptResult<()> {
+        let chip8 = &mut state.borrow_mut().chip8;
+        let mut regs = chip8.registers();
+        match name {
+            "I" => regs.i = value as u16 & 0xFFF,
+            "PC" => regs.pc = value as u16 & 0xFFF,
+            "SP" => regs.sp = byte(value)?.saturating_mul(2),
+            "DT" => regs.dt = byte(value)?,
+            "ST" => regs.st = byte(value)?,
+            _ => regs.v[v_index(name)?] = byte(value)?,
+        }
+        chip8.set_registers(regs);
+        Ok(())
+    });
+
+    let state = shared.clone();
+    engine.register_fn("press", move |key: INT| -> ScriptResult<()> {
+        state.borrow_mut().chip8.press_key(key_index(key)?);
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("release", move |key: INT| -> ScriptResult<()> {
+        state.borrow_mut().chip8.release_key(key_index(key)?);
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("key", move |key: INT| -> ScriptResult<bool> {
+        Ok(state.borrow().chip8.keyboard[key_index(key)? as usize])
+    });
+
+    let state = shared.clone();
+    engine.register_fn("pixel", move |x: INT, y: INT| -> bool {
+        let (x, y) = (x.rem_euclid(SCREEN_WIDTH as INT) as usize, y.rem_euclid(SCREEN_HEIGHT as INT) as usize);
+        state.borrow().chip8.screen[y * SCREEN_WIDTH + x] != 0
+    });
+    let state = shared.clone();
+    engine.register_fn("screenshot", move |path: &str| -> ScriptResult<()> {
+        save_pgm(path, &state.borrow().chip8.screen).map_err(|e| format!("Failure writing {}: {}", path, e).into())
+    });
+
+    let state = shared.clone();
+    engine.register_fn("save_state", move || -> Blob {
+        state.borrow().chip8.save_state()
+    });
+    let state = shared.clone();
+    engine.register_fn("load_state", move |saved: Blob| -> ScriptResult<()> {
+        state.borrow_mut().chip8.load_state(&saved).map_err(|e| e.to_string().into())
+    });
+
+    let state = shared.clone();
+    engine.register_fn("frame", move || state.borrow().frames as INT);
+    let state = shared.clone();
+    engine.register_fn("cycles", move || state.borrow().chip8.cycles() as INT);
+    let state = shared.clone();
+    engine.register_fn("stop", move || state.borrow_mut().stopped = true);
+    let state = shared.clone();
+    engine.register_fn("on_pc", move |addr: INT, hook: FnPtr| -> ScriptResult<()> {
+        state.borrow_mut().pc_hooks.push((address(addr)? as u16, hook));
+        Ok(())
+    });
+
+    engine
+}
+
+fn address(addr: INT) -> ScriptResult<usize> {
+    usize::try_from(addr).ok().filter(|&addr| addr < MEM_SIZE).ok_or_else(|| format!("Address {:#X} is out of memory", addr).into())
+}
+
+fn byte(value: INT) -> ScriptResult<u8> {
+    u8::try_from(value).map_err(|_| format!("{} doesn't fit in a byte", value).into())
+}
+
+fn key_index(key: INT) -> ScriptResult<u8> {
+    u8::try_from(key).ok().filter(|&key| key <= 0xF).ok_or_else(|| format!("No key {}, keys go from 0 to 0xF", key).into())
+}
+
+fn v_index(name: &str) -> ScriptResult<usize> {
+    name.strip_prefix('V')
+        .filter(|digit| digit.len() == 1)
+        .and_then(|digit| usize::from_str_radix(digit, 16).ok())
+        .ok_or_else(|| format!("Unknown register '{}'", name).into())
+}
+
+/// Save the chip8 screen as a binary PGM image, lit pixels white.
+fn save_pgm(path: &str, screen: &[u8]) -> std::io::Result<()> {
+    let mut image = format!("P5\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+    image.extend(screen.iter().map(|&pixel| if pixel != 0 { 255 } else { 0 }));
+    std::fs::write(path, image)
+}
@@ -0,0 +1,293 @@
+// Execution traces log every instruction a ROM runs, with the registers and memory it changed.
+// Text traces are meant to be read (or diffed) by people. Long runs can make huge text traces,
+// so there's also a compact binary format, which can be converted to text afterwards.
+//
+// Binary traces start with "R8TR" and a version byte, followed by one record per instruction:
+//   cycle: u64, pc: u16, instruction: u16       (all little endian)
+//   changed registers count: u8, then for each: register id: u8, new value: u16
+//   memory writes count: u8, then for each: address: u16, new value: u8
+// Register ids are 0x0-0xF for V0-VF, then I, SP, DT and ST.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::chip8::{Chip8, Registers, TickObserver};
+use crate::disasm::disassemble;
+use crate::memory_map::MemoryMap;
+
+const TRACE_MAGIC: &[u8; 4] = b"R8TR";
+const TRACE_VERSION: u8 = 1;
+
+const REG_I:  u8 = 0x10;
+const REG_SP: u8 = 0x11;
+const REG_DT: u8 = 0x12;
+const REG_ST: u8 = 0x13;
+
+/// How a trace is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceFormat {
+    /// A line per instruction, with its disassembly.
+    #[default]
+    Text,
+    /// Compact records, see `convert_to_text` to read them.
+    Binary,
+}
+
+impl TraceFormat {
+    /// Look up a trace format by name: `text` or `binary`.
+    pub fn from_name(name: &str) -> Option<TraceFormat> {
+        match name {
+            "text" => Some(TraceFormat::Text),
+            "binary" | "bin" => Some(TraceFormat::Binary),
+            _ => None
+        }
+    }
+}
+
+/// Which instructions make it into a trace. The default lets everything through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceFilter {
+    pub pc_range: Option<(u16, u16)>,   // Only instructions at these addresses, inclusive
+    pub opcode_classes: u16,            // Bit N set lets instructions NXXX through
+    pub max_cycles: Option<u64>,        // Only the first N clock cycles
+}
+
+impl Default for TraceFilter {
+    fn default() -> Self {
+        TraceFilter { pc_range: None, opcode_classes: 0xFFFF, max_cycles: None }
+    }
+}
+
+impl TraceFilter {
+    /// Parse an address range like `0x200-0x2FF`.
+    pub fn parse_pc_range(range: &str) -> Option<(u16, u16)> {
+        let (start, end) = range.split_once('-')?;
+        let (start, end) = (MemoryMap::parse_address(start)?, MemoryMap::parse_address(end)?);
+
+        if start <= end { Some((start, end)) } else { None }
+    }
+
+    /// Parse a list of opcode classes (an instruction's first hex digit) like `D,F` or `1,2,B`.
+    pub fn parse_opcode_classes(classes: &str) -> Option<u16> {
+        classes.split(',')
+            .map(|class| u8::from_str_radix(class.trim(), 16).ok().filter(|&class| class <= 0xF))
+            .try_fold(0, |mask, class| Some(mask | 1 << class?))
+    }
+
+    /// Query wether the instruction at `pc`, ran on clock `cycle`, belongs in the trace.
+    pub fn accepts(&self, cycle: u64, pc: u16, instruction: u16) -> bool {
+        let in_range = self.pc_range.is_none_or(|(start, end)| (start..=end).contains(&pc));
+        let in_class = self.opcode_classes & (1 << (instruction >> 12)) != 0;
+        let in_time = self.max_cycles.is_none_or(|max_cycles| cycle <= max_cycles);
+
+        in_range && in_class && in_time
+    }
+}
+
+/// One executed instruction, and what it changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub cycle: u64,
+    pub pc: u16,
+    pub instruction: u16,
+    pub registers: Vec<(u8, u16)>,      // Register id and its new value
+    pub memory_writes: Vec<(u16, u8)>,  // Address and its new value
+}
+
+impl TraceRecord {
+    /// Record the instruction `chip8` just executed, comparing its registers against `before`.
+    /// Returns None if the last tick didn't execute one.
+    pub fn from_tick(before: &Registers, chip8: &Chip8) -> Option<TraceRecord> {
+        let (pc, instruction) = chip8.last_instruction()?;
+        let after = chip8.registers();
+
+        let mut registers = Vec::new();
+        for (reg_index, (old, new)) in before.v.iter().zip(after.v.iter()).enumerate() {
+            if old != new {
+                registers.push((reg_index as u8, *new as u16));
+            }
+        }
+        if before.i != after.i { registers.push((REG_I, after.i)); }
+        if before.sp != after.sp { registers.push((REG_SP, after.sp as u16)); }
+        if before.dt != after.dt { registers.push((REG_DT, after.dt as u16)); }
+        if before.st != after.st { registers.push((REG_ST, after.st as u16)); }
+
+        Some(TraceRecord {
+            cycle: chip8.cycles(),
+            pc,
+            instruction,
+            registers,
+            memory_writes: chip8.memory_writes().to_vec(),
+        })
+    }
+
+    /// Write the record as a line of text, e.g.
+    /// `      1234  2A4  7A01  ADD VA, 0x01        VA=0x05`
+    pub fn write_text<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut line = format!("{:>10}  {:03X}  {:04X}  {:<20}", self.cycle, self.pc, self.instruction, disassemble(self.instruction));
+
+        for &(id, value) in &self.registers {
+            line += &match id {
+                REG_I => format!(" I={:#05X}", value),
+                REG_SP => format!(" SP={:#04X}", value),
+                REG_DT => format!(" DT={:#04X}", value),
+                REG_ST => format!(" ST={:#04X}", value),
+                _ => format!(" V{:X}={:#04X}", id, value),
+            };
+        }
+        for &(addr, value) in &self.memory_writes {
+            line += &format!(" [{:#05X}]={:#04X}", addr, value);
+        }
+
+        writeln!(out, "{}", line.trim_end())
+    }
+
+    /// Write the record in the binary trace format.
+    pub fn write_binary<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&self.cycle.to_le_bytes())?;
+        out.write_all(&self.pc.to_le_bytes())?;
+        out.write_all(&self.instruction.to_le_bytes())?;
+
+        out.write_all(&[self.registers.len() as u8])?;
+        for &(id, value) in &self.registers {
+            out.write_all(&[id])?;
+            out.write_all(&value.to_le_bytes())?;
+        }
+
+        // FX55 writes 16 bytes at most, a CALL on the in-memory stack 2
+        out.write_all(&[self.memory_writes.len() as u8])?;
+        for &(addr, value) in &self.memory_writes {
+            out.write_all(&addr.to_le_bytes())?;
+            out.write_all(&[value])?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a record written by `write_binary`. Returns None at the end of the trace.
+    pub fn read_binary<R: Read>(input: &mut R) -> io::Result<Option<TraceRecord>> {
+        // Only ending before a record is the end of the trace, ending inside one is truncation
+        let mut cycle = [0; 8];
+        if input.read(&mut cycle[..1])? == 0 {
+            return Ok(None);
+        }
+        input.read_exact(&mut cycle[1..])?;
+
+        let mut header = [0; 5];
+        input.read_exact(&mut header)?;
+
+        let mut registers = Vec::with_capacity(header[4] as usize);
+        for _ in 0..header[4] {
+            let mut register = [0; 3];
+            input.read_exact(&mut register)?;
+            registers.push((register[0], u16::from_le_bytes([register[1], register[2]])));
+        }
+
+        let mut writes_len = [0; 1];
+        input.read_exact(&mut writes_len)?;
+        let mut memory_writes = Vec::with_capacity(writes_len[0] as usize);
+        for _ in 0..writes_len[0] {
+            let mut write = [0; 3];
+            input.read_exact(&mut write)?;
+            memory_writes.push((u16::from_le_bytes([write[0], write[1]]), write[2]));
+        }
+
+        Ok(Some(TraceRecord {
+            cycle: u64::from_le_bytes(cycle),
+            pc: u16::from_le_bytes([header[0], header[1]]),
+            instruction: u16::from_le_bytes([header[2], header[3]]),
+            registers,
+            memory_writes,
+        }))
+    }
+}
+
+/// Logs the instructions of a running chip8, when passed to `Chip8::run_frame_observed`.
+pub struct Tracer<W: Write> {
+    out: W,
+    format: TraceFormat,
+    filter: TraceFilter,
+    before: Registers,          // Registers before the current tick
+    records: u64,               // Records written so far
+    error: Option<io::Error>,   // First write failure, tracing stops there
+}
+
+impl Tracer<BufWriter<File>> {
+    /// Trace into a new file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P, format: TraceFormat, filter: TraceFilter) -> io::Result<Self> {
+        Tracer::new(BufWriter::new(File::create(path)?), format, filter)
+    }
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(mut out: W, format: TraceFormat, filter: TraceFilter) -> io::Result<Self> {
+        if format == TraceFormat::Binary {
+            out.write_all(TRACE_MAGIC)?;
+            out.write_all(&[TRACE_VERSION])?;
+        }
+
+        Ok(Tracer { out, format, filter, before: Registers::default(), records: 0, error: None })
+    }
+
+    /// Number of instructions traced so far.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Flush the trace, returning the first error met while writing it.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> TickObserver for Tracer<W> {
+    fn before_tick(&mut self, chip8: &mut Chip8) {
+        self.before = chip8.registers();
+    }
+
+    fn after_tick(&mut self, chip8: &mut Chip8) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let record = match TraceRecord::from_tick(&self.before, chip8) {
+            Some(record) if self.filter.accepts(record.cycle, record.pc, record.instruction) => record,
+            _ => return,
+        };
+        let result = match self.format {
+            TraceFormat::Text => record.write_text(&mut self.out),
+            TraceFormat::Binary => record.write_binary(&mut self.out),
+        };
+
+        match result {
+            Ok(()) => self.records += 1,
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+/// Convert a binary trace into the text format. Returns the number of records converted.
+pub fn convert_to_text<R: Read, W: Write>(input: &mut R, out: &mut W) -> io::Result<u64> {
+    let mut header = [0; 5];
+    input.read_exact(&mut header)?;
+    if &header[0..4] != TRACE_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a rusty-8 binary trace"));
+    }
+    if header[4] != TRACE_VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported trace version {}", header[4])));
+    }
+
+    let mut records = 0;
+    while let Some(record) = TraceRecord::read_binary(input)? {
+        record.write_text(out)?;
+        records += 1;
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+
+/// Why a (headless) run stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Program ended: ran into empty memory (a 0000 instruction).
+    Halted,
+    /// Program is stuck on a jump to itself at `pc`, which is how most ROMs end.
+    SelfLoop { pc: u16 },
+    /// Screen didn't change for `frames` frames.
+    Idle { frames: u32 },
+    /// Ran for all the frames or cycles it was allowed to.
+    BudgetExhausted,
+    /// Program did something the chip8 can't carry on from.
+    Error(Chip8Error),
+    /// Every step of the reference trace matched, all `steps` of them.
+    Matched { steps: u64 },
+    /// Program ran differently than the reference trace, on `step`.
+    Diverged { step: u64 },
+    /// The script ended the run.
+    Stopped,
+    /// The script failed (e.g. an assertion threw), for this reason.
+    ScriptFailed(String),
+}
+
+impl ExitReason {
+    /// Process exit code for CI: 0 when the program ended on its own, non-zero otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ExitReason::Halted | ExitReason::SelfLoop { .. } | ExitReason::Matched { .. } | ExitReason::Stopped => 0,
+            ExitReason::Error(_) => 1,
+            ExitReason::Idle { .. } => 2,
+            ExitReason::BudgetExhausted => 3,
+            ExitReason::Diverged { .. } => 4,
+            ExitReason::ScriptFailed(_) => 5,
+        }
+    }
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::Halted => write!(f, "halted"),
+            ExitReason::SelfLoop { pc } => write!(f, "self-loop at {:#05X}", pc),
+            ExitReason::Idle { frames } => write!(f, "idle, screen unchanged for {} frames", frames),
+            ExitReason::BudgetExhausted => write!(f, "budget exhausted"),
+            ExitReason::Error(e) => write!(f, "error: {}", e),
+            ExitReason::Matched { steps } => write!(f, "matched the reference for all {} steps", steps),
+            ExitReason::Diverged { step } => write!(f, "diverged from the reference on step {}", step),
+            ExitReason::Stopped => write!(f, "stopped by the script"),
+            ExitReason::ScriptFailed(e) => write!(f, "script failed: {}", e),
+        }
+    }
+}
+
+/// When a run should be stopped. Unset limits are never hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    pub max_frames: Option<u32>,    // Frames to run at most
+    pub max_cycles: Option<u64>,    // Clock cycles to run, checked after each frame: the last one runs whole
+    pub idle_frames: Option<u32>,   // Frames without a screen change before giving up
+    pub stop_on_self_loop: bool,    // Stop as soon as the program jumps to itself
+}
+
+/// Checks a running chip8 after every frame, and tells when (and why) it should stop.
+pub struct Watchdog {
+    limits: Limits,
+    frames: u32,            // Frames checked so far
+    idle_frames: u32,       // Frames since the screen last changed
+    last_screen: Vec<u8>,
+}
+
+impl Watchdog {
+    pub fn new(limits: Limits) -> Self {
+        Watchdog {
+            limits,
+            frames: 0,
+            idle_frames: 0,
+            last_screen: Vec::new(),
+        }
+    }
+
+    /// Frames checked so far.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Check the chip8 after it ran a frame. Returns why it should stop, if it should.
+    pub fn check(&mut self, chip8: &Chip8) -> Option<ExitReason> {
+        self.frames += 1;
+
+        if chip8.screen == self.last_screen {
+            self.idle_frames += 1;
+        } else {
+            self.idle_frames = 0;
+            self.last_screen.clear();
+            self.last_screen.extend_from_slice(&chip8.screen);
+        }
+
+        if chip8.finished_running() {
+            return Some(ExitReason::Halted);
+        }
+        if self.limits.stop_on_self_loop {
+            if let Some(pc) = chip8.self_loop() {
+                return Some(ExitReason::SelfLoop { pc });
+            }
+        }
+        if let Some(idle_frames) = self.limits.idle_frames {
+            if self.idle_frames >= idle_frames {
+                return Some(ExitReason::Idle { frames: self.idle_frames });
+            }
+        }
+
+        let frames_exhausted = self.limits.max_frames.is_some_and(|max| self.frames >= max);
+        let cycles_exhausted = self.limits.max_cycles.is_some_and(|max| chip8.cycles() >= max);
+        if frames_exhausted || cycles_exhausted {
+            return Some(ExitReason::BudgetExhausted);
+        }
+
+        None
+    }
+}
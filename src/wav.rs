@@ -0,0 +1,91 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const FRAME_RATE: u32 = 60;         // Chip-8 timers tick on 60Hz
+const AMPLITUDE: f32 = 0.3 * i16::MAX as f32;
+
+/// Renders the chip8 beep into 16-bit mono PCM, one 60Hz frame at a time,
+/// so sound timer behaviour can be checked without an audio device.
+pub struct WavRecorder {
+    sample_rate: u32,
+    frequency: f32,
+    phase: f32,             // Position in the square wave's period, between [0, 1)
+    samples: Vec<i16>,
+    frames: u32,            // Frames recorded so far
+    beeps: Vec<(u32, u32)>, // (first frame, length in frames) of every beep
+}
+
+impl WavRecorder {
+    pub fn new(sample_rate: u32, frequency: f32) -> Self {
+        WavRecorder {
+            sample_rate,
+            frequency,
+            phase: 0.0,
+            samples: Vec::new(),
+            frames: 0,
+            beeps: Vec::new(),
+        }
+    }
+
+    /// Render one frame worth of samples: a square wave if `is_beeping`, silence otherwise.
+    pub fn record_frame(&mut self, is_beeping: bool) {
+        // Frame boundaries are computed from the total, so rates not divisible by 60 don't drift
+        let frame_end = (self.frames as u64 + 1) * self.sample_rate as u64 / FRAME_RATE as u64;
+        let frame_len = frame_end as usize - self.samples.len();
+
+        for _ in 0..frame_len {
+            let sample = match is_beeping {
+                true if self.phase < 0.5 => AMPLITUDE as i16,
+                true => -AMPLITUDE as i16,
+                false => 0,
+            };
+            self.samples.push(sample);
+            self.phase = (self.phase + self.frequency / self.sample_rate as f32).fract();
+        }
+
+        if is_beeping {
+            match self.beeps.last_mut() {
+                Some((start, len)) if *start + *len == self.frames => *len += 1,
+                _ => self.beeps.push((self.frames, 1)),
+            }
+        }
+        self.frames += 1;
+    }
+
+    /// Every beep recorded so far, as (first frame, length in frames).
+    pub fn beeps(&self) -> &[(u32, u32)] {
+        &self.beeps
+    }
+
+    /// Write the recording as a RIFF/WAVE file.
+    pub fn write<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+        let data_len = (self.samples.len() * 2) as u32;
+        let byte_rate = self.sample_rate * 2;
+
+        out.write_all(b"RIFF")?;
+        out.write_all(&(36 + data_len).to_le_bytes())?;
+        out.write_all(b"WAVE")?;
+
+        out.write_all(b"fmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;   // fmt chunk length
+        out.write_all(&1u16.to_le_bytes())?;    // PCM
+        out.write_all(&1u16.to_le_bytes())?;    // Mono
+        out.write_all(&self.sample_rate.to_le_bytes())?;
+        out.write_all(&byte_rate.to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;    // Block align
+        out.write_all(&16u16.to_le_bytes())?;   // Bits per sample
+
+        out.write_all(b"data")?;
+        out.write_all(&data_len.to_le_bytes())?;
+        for sample in &self.samples {
+            out.write_all(&sample.to_le_bytes())?;
+        }
+
+        out.flush()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
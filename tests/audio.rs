@@ -0,0 +1,166 @@
+// The beep without a sound device: where the sound timer's edges fall within frames, and on which samples the tone starts and stops.
+// The audio belongs to the windowed frontend, so its module is built in here.
+
+#[allow(dead_code)]
+#[path = "../src/audio.rs"]
+mod audio;
+mod common;
+
+use rusty_8::{Chip8, Quirks};
+
+use audio::{AudioBackend, GateEdge, NullBackend, SoundTimerObserver, Tone, ToneControl, Waveform};
+
+const TICKS_PER_FRAME: usize = 10;
+const FRAME_SAMPLES: usize = 44100 / 60;
+
+// 6003  V0 = 3
+// F018  ST = V0, after the 2nd tick
+// 1204  Loop
+const BEEP_ROM: [u8; 6] = [0x60, 0x03, 0xF0, 0x18, 0x12, 0x04];
+
+/// A square wave at full volume, so each sample's magnitude is the tone's level.
+fn tone() -> (Tone, ToneControl) {
+    Tone::new(Waveform::Square, 440.0, 1.0)
+}
+
+/// The level of the next `count` samples.
+fn levels(tone: &mut Tone, count: usize) -> Vec<f32> {
+    tone.take(count).map(f32::abs).collect()
+}
+
+/// The samples the tone starts and stops fading in, as it's gated on and off.
+fn gate_changes(levels: &[f32]) -> Vec<(usize, bool)> {
+    let mut changes = Vec::new();
+    let mut previous = 0.0;
+    let mut is_open = false;
+    for (sample, &level) in levels.iter().enumerate() {
+        if level > previous && !is_open || level < previous && is_open {
+            is_open = !is_open;
+            changes.push((sample, is_open));
+        }
+        previous = level;
+    }
+    changes
+}
+
+/// The edges of each of `frames` frames of `rom`, as the frontend hands them to its backend.
+fn frame_edges(rom: &[u8], frames: usize) -> (Chip8, Vec<Vec<GateEdge>>) {
+    let mut chip8 = common::booted(Quirks::default(), rom);
+    let mut sound = SoundTimerObserver::new(TICKS_PER_FRAME);
+    let backend: Box<dyn AudioBackend> = Box::new(NullBackend);
+
+    let mut edges = Vec::new();
+    for _ in 0..frames {
+        chip8.run_frame_observed(TICKS_PER_FRAME, &mut sound).unwrap();
+        backend.play_frame(sound.edges());
+        edges.push(sound.edges().to_vec());
+    }
+    (chip8, edges)
+}
+
+#[test]
+fn sound_timer_edges_fall_on_their_tick() {
+    let (chip8, edges) = frame_edges(&BEEP_ROM, 4);
+
+    // On after the 2nd of 10 ticks, for 3 timer ticks: until the end of the 3rd frame
+    assert_eq!(edges, [
+        vec![(0.0, false), (0.2, true)],
+        vec![(0.0, true)],
+        vec![(0.0, true), (1.0, false)],
+        vec![(0.0, false)],
+    ]);
+    assert_eq!(chip8.registers().st, 0);
+}
+
+#[test]
+fn frames_cut_short_keep_their_length() {
+    // 6002  V0 = 2
+    // F018  ST = V0
+    // F00A  Wait for a key, ending the frame on the 3rd tick
+    let (_, edges) = frame_edges(&[0x60, 0x02, 0xF0, 0x18, 0xF0, 0x0A], 2);
+
+    assert_eq!(edges, [
+        vec![(0.0, false), (0.2, true)],
+        vec![(0.0, true), (1.0, false)],
+    ]);
+}
+
+#[test]
+fn tone_follows_the_sound_timer() {
+    let (_, edges) = frame_edges(&BEEP_ROM, 4);
+    let (mut tone, control) = tone();
+    for frame in &edges {
+        control.play_frame(frame);
+    }
+
+    // On 0.2 into the first frame, off at the end of the third
+    let levels = levels(&mut tone, 5 * FRAME_SAMPLES);
+    assert_eq!(gate_changes(&levels), [(FRAME_SAMPLES / 5, true), (3 * FRAME_SAMPLES, false)]);
+    assert!(levels[..FRAME_SAMPLES / 5].iter().all(|&level| level == 0.0));
+    assert!(levels[FRAME_SAMPLES..3 * FRAME_SAMPLES].iter().all(|&level| level == 1.0));
+    assert!(levels[4 * FRAME_SAMPLES..].iter().all(|&level| level == 0.0));
+}
+
+#[test]
+fn frames_play_back_to_back() {
+    let (mut tone, control) = tone();
+    control.play_frame(&[(0.0, false), (0.5, true)]);
+    control.play_frame(&[(0.0, true), (0.25, false)]);
+    control.play_frame(&[(0.0, false), (1.0, true)]);
+
+    let levels = levels(&mut tone, 4 * FRAME_SAMPLES);
+    let half = FRAME_SAMPLES / 2;
+    let quarter = FRAME_SAMPLES / 4;
+    assert_eq!(gate_changes(&levels), [(half, true), (FRAME_SAMPLES + quarter, false), (3 * FRAME_SAMPLES, true)]);
+}
+
+#[test]
+fn frames_falling_behind_start_over_ahead() {
+    let (mut tone, control) = tone();
+    levels(&mut tone, 1000);
+
+    // The first frame would start on sample 0, already played
+    control.play_frame(&[(0.0, true)]);
+    let levels = levels(&mut tone, 3 * FRAME_SAMPLES);
+    assert_eq!(gate_changes(&levels), [(2 * FRAME_SAMPLES, true)]);
+}
+
+#[test]
+fn frames_running_too_far_ahead_start_over() {
+    let (mut tone, control) = tone();
+    for _ in 0..7 {
+        control.play_frame(&[(0.0, false)]);
+    }
+
+    // 7 frames ahead is more than 6, so the 8th goes back to 2 frames ahead
+    control.play_frame(&[(0.0, true), (0.5, false)]);
+    let levels = levels(&mut tone, 4 * FRAME_SAMPLES);
+    assert_eq!(gate_changes(&levels), [(2 * FRAME_SAMPLES, true), (2 * FRAME_SAMPLES + FRAME_SAMPLES / 2, false)]);
+}
+
+#[test]
+fn setting_the_gate_drops_queued_edges() {
+    let (mut tone, control) = tone();
+    control.play_frame(&[(0.0, false), (0.5, true)]);
+    control.set_gate(false);
+    assert!(levels(&mut tone, 2 * FRAME_SAMPLES).iter().all(|&level| level == 0.0));
+
+    control.play_frame(&[(0.0, false), (0.5, true)]);
+    control.set_gate(true);
+    assert_eq!(gate_changes(&levels(&mut tone, 2 * FRAME_SAMPLES)), [(0, true)]);
+}
+
+#[test]
+fn muting_silences_the_open_gate() {
+    let (mut tone, control) = tone();
+    control.set_gate(true);
+    assert!(control.toggle_mute());
+    assert!(levels(&mut tone, FRAME_SAMPLES).iter().all(|&level| level == 0.0));
+
+    assert!(!control.toggle_mute());
+    assert_eq!(gate_changes(&levels(&mut tone, FRAME_SAMPLES)), [(0, true)]);
+
+    // Without a device, there's never anything to hear
+    assert!(NullBackend.toggle_mute());
+    assert!(NullBackend.toggle_mute());
+}
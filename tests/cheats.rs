@@ -0,0 +1,164 @@
+// RAM search narrowing, cheat targets and files, and cheats freezing their targets every frame.
+
+mod common;
+
+use std::path::Path;
+
+use rusty_8::{Chip8, Cheats, Quirks, RamSearch, SearchFilter};
+use rusty_8::cheats::{cheat_file_name, Cheat, CheatTarget};
+
+fn cheat(name: &str, target: CheatTarget, value: u8) -> Cheat {
+    Cheat { name: name.to_string(), target, value }
+}
+
+#[test]
+fn search_starts_from_all_of_memory() {
+    let chip8 = Chip8::new();
+    let search = RamSearch::new(&chip8);
+
+    assert_eq!(search.candidates().len(), 4096);
+    assert_eq!((search.candidates()[0], search.candidates()[4095]), (0x000, 0xFFF));
+}
+
+#[test]
+fn search_narrows_down_to_the_counter() {
+    // Lives at 0x300 go 3, 2, 1, while a timer at 0x301 counts up and 0x302 flips
+    let mut chip8 = Chip8::new();
+    chip8.memory_mut()[0x300] = 3;
+    let mut search = RamSearch::new(&chip8);
+
+    chip8.memory_mut()[0x300..0x303].copy_from_slice(&[2, 1, 1]);
+    assert_eq!(search.filter(&chip8, SearchFilter::Changed), 3);
+    assert_eq!(search.candidates(), [0x300, 0x301, 0x302]);
+
+    chip8.memory_mut()[0x300..0x303].copy_from_slice(&[2, 2, 0]);
+    assert_eq!(search.filter(&chip8, SearchFilter::Unchanged), 1);
+    assert_eq!(search.candidates(), [0x300]);
+
+    chip8.memory_mut()[0x300] = 1;
+    assert_eq!(search.filter(&chip8, SearchFilter::Increased), 0);
+    assert!(search.candidates().is_empty());
+}
+
+#[test]
+fn search_filters_compare_against_the_last_snapshot() {
+    let mut chip8 = Chip8::new();
+    chip8.memory_mut()[0x300..0x304].copy_from_slice(&[5, 5, 5, 5]);
+    let mut search = RamSearch::new(&chip8);
+
+    chip8.memory_mut()[0x300..0x304].copy_from_slice(&[4, 6, 5, 9]);
+    search.filter(&chip8, SearchFilter::Changed);
+    assert_eq!(search.candidates(), [0x300, 0x301, 0x303]);
+
+    // Snapshotted after each filter: the same memory again is unchanged
+    let mut again = search.clone();
+    assert_eq!(again.filter(&chip8, SearchFilter::Changed), 0);
+
+    let mut decreased = search.clone();
+    chip8.memory_mut()[0x300..0x304].copy_from_slice(&[3, 5, 5, 9]);
+    decreased.filter(&chip8, SearchFilter::Decreased);
+    assert_eq!(decreased.candidates(), [0x300, 0x301]);
+
+    search.filter(&chip8, SearchFilter::Equals(9));
+    assert_eq!(search.candidates(), [0x303]);
+}
+
+#[test]
+fn targets_parse() {
+    assert_eq!(CheatTarget::parse("0x2F0"), Some(CheatTarget::Memory(0x2F0)));
+    assert_eq!(CheatTarget::parse("0xFFF"), Some(CheatTarget::Memory(0xFFF)));
+    assert_eq!(CheatTarget::parse("VE"), Some(CheatTarget::V(0xE)));
+    assert_eq!(CheatTarget::parse("V0"), Some(CheatTarget::V(0)));
+    assert_eq!(CheatTarget::parse("DT"), Some(CheatTarget::DelayTimer));
+    assert_eq!(CheatTarget::parse("ST"), Some(CheatTarget::SoundTimer));
+
+    for invalid in ["0x1000", "VG", "V10", "V", "dt", "I", "lives"] {
+        assert_eq!(CheatTarget::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn targets_display_as_parsed() {
+    for target in ["0x2F0", "0x005", "VE", "DT", "ST"] {
+        assert_eq!(CheatTarget::parse(target).unwrap().to_string(), target);
+    }
+}
+
+#[test]
+fn cheat_files_parse() {
+    let cheats = Cheats::parse("\
+        # BRIX\n\
+        0x2F0 09 Infinite lives\n\
+        \n\
+        VE 5   Max   ammo\n\
+        DT 0\n").unwrap();
+
+    assert_eq!(cheats.cheats(), [
+        cheat("Infinite lives", CheatTarget::Memory(0x2F0), 0x09),
+        cheat("Max ammo", CheatTarget::V(0xE), 0x05),
+        cheat("", CheatTarget::DelayTimer, 0x00),
+    ]);
+    assert_eq!(cheats.to_string(), "0x2F0 09 Infinite lives\nVE 05 Max ammo\nDT 00\n");
+}
+
+#[test]
+fn cheat_file_errors_point_at_the_line() {
+    for invalid in ["VE", "VE 100", "VE GG Lives", "0x1000 05", "I 05", "Lives VE 05"] {
+        let error = Cheats::parse(&format!("# BRIX\n0x2F0 09 Infinite lives\n  {}  \n", invalid)).unwrap_err();
+        assert_eq!(error, format!("line 3: expected '<target> <value> [name]', got '{}'", invalid));
+    }
+}
+
+#[test]
+fn adding_a_cheat_replaces_the_one_on_its_target() {
+    let mut cheats = Cheats::new();
+    cheats.add(cheat("Lives", CheatTarget::V(0xE), 3));
+    cheats.add(cheat("Timer", CheatTarget::DelayTimer, 0));
+    cheats.add(cheat("More lives", CheatTarget::V(0xE), 9));
+
+    assert_eq!(cheats.cheats().len(), 2);
+    assert_eq!(cheats.get(CheatTarget::V(0xE)), Some(&cheat("More lives", CheatTarget::V(0xE), 9)));
+    assert_eq!(cheats.remove(CheatTarget::DelayTimer).map(|cheat| cheat.name), Some("Timer".to_string()));
+    assert_eq!(cheats.remove(CheatTarget::DelayTimer), None);
+    assert_eq!(cheats.get(CheatTarget::DelayTimer), None);
+}
+
+#[test]
+fn cheats_freeze_their_targets_every_frame() {
+    // 7EFF  VE -= 1
+    // F318  ST = V3
+    // 1200  Loop
+    let mut chip8 = common::booted(Quirks::default(), &[0x7E, 0xFF, 0xF3, 0x18, 0x12, 0x00]);
+    let mut cheats = Cheats::new();
+    cheats.add(cheat("Lives", CheatTarget::V(0xE), 9));
+    cheats.add(cheat("Lives", CheatTarget::Memory(0x300), 3));
+    cheats.add(cheat("Beep", CheatTarget::V(3), 0x40));
+    cheats.add(cheat("No waiting", CheatTarget::DelayTimer, 0x20));
+
+    for _ in 0..3 {
+        chip8.run_frame_observed(6, &mut cheats).unwrap();
+
+        // Frozen before each frame, which then runs: VE drops twice, the timers once
+        let regs = chip8.registers();
+        assert_eq!((regs.v[0xE], regs.v[3], regs.dt, regs.st), (7, 0x40, 0x1F, 0x3F));
+        assert_eq!(chip8.memory()[0x300], 3);
+        chip8.memory_mut()[0x300] = 0;
+    }
+}
+
+#[test]
+fn cheat_files_are_named_after_the_rom_hash() {
+    assert_eq!(cheat_file_name(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d.cht");
+}
+
+#[test]
+fn cheat_files_save_and_load() {
+    let mut cheats = Cheats::new();
+    cheats.add(cheat("Infinite lives", CheatTarget::Memory(0x2F0), 9));
+    cheats.add(cheat("", CheatTarget::SoundTimer, 0));
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cheats").join(cheat_file_name(&[0x12, 0x00]));
+    cheats.save(&path).unwrap();
+
+    assert_eq!(Cheats::load(&path).unwrap(), cheats);
+}
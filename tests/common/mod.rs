@@ -0,0 +1,18 @@
+// Helpers shared by the integration tests. Each test crate uses its own part of them.
+#![allow(dead_code)]
+
+use rusty_8::{Chip8, Quirks};
+
+/// A chip8 with `quirks`, `rom` loaded at the program start.
+pub fn booted(quirks: Quirks, rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::with_quirks(quirks);
+    chip8.load_rom_bytes(rom).unwrap();
+    chip8
+}
+
+/// Clock cycles (instructions run) by the next frame, of up to `ticks` instructions.
+pub fn run_frame_cycles(chip8: &mut Chip8, ticks: usize) -> u64 {
+    let before = chip8.cycles();
+    chip8.run_frame(ticks).unwrap();
+    chip8.cycles() - before
+}
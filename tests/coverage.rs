@@ -0,0 +1,112 @@
+// Coverage counts, their file format, and merging runs of the same ROM (and only of it).
+
+mod common;
+
+use std::path::Path;
+
+use rusty_8::{Coverage, Quirks};
+use rusty_8::hash::sha1_hex;
+
+// A300  I = 0x300
+// F065  V0 = [I]
+// A300  I = 0x300, again as the quirks may have moved it
+// F055  [I] = V0
+// 1200  Loop
+const ROM: [u8; 10] = [0xA3, 0x00, 0xF0, 0x65, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x00];
+
+/// Coverage of `frames` frames of `rom`, 4 instructions each.
+fn record(rom: &[u8], frames: usize) -> Coverage {
+    let mut chip8 = common::booted(Quirks::default(), rom);
+    let mut coverage = Coverage::for_rom(rom);
+    for _ in 0..frames {
+        chip8.run_frame_observed(4, &mut coverage).unwrap();
+    }
+    coverage
+}
+
+#[test]
+fn counts_executions_reads_and_writes() {
+    let coverage = record(&ROM, 2);
+
+    // Around the 5 instruction loop once, then 3 more
+    let executed: Vec<u64> = (0x200..0x20C).step_by(2).map(|addr| coverage.executed(addr)).collect();
+    assert_eq!(executed, [2, 2, 2, 1, 1, 0]);
+    assert_eq!(coverage.executed(0x201), 0);
+    assert_eq!((coverage.read(0x300), coverage.written(0x300)), (2, 1));
+    assert_eq!((coverage.read(0x301), coverage.written(0x301)), (0, 0));
+    assert_eq!(coverage.rom(), Some(sha1_hex(&ROM).as_str()));
+}
+
+#[test]
+fn file_lists_the_rom_and_touched_addresses() {
+    let mut file = Vec::new();
+    record(&ROM, 1).write(&mut file).unwrap();
+
+    assert_eq!(String::from_utf8(file).unwrap(), format!("\
+# rusty-8 coverage: address, then times executed, read and written
+rom {}
+200 1 0 0
+202 1 0 0
+204 1 0 0
+206 1 0 0
+300 0 1 1
+", sha1_hex(&ROM)));
+}
+
+#[test]
+fn saves_and_loads() {
+    let coverage = record(&ROM, 3);
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("saved.cov");
+    coverage.save(&path).unwrap();
+
+    assert_eq!(Coverage::load(&path).unwrap(), coverage);
+}
+
+#[test]
+fn merging_adds_up_runs_of_the_same_rom() {
+    let mut coverage = record(&ROM, 1);
+    let file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("merged.cov");
+    coverage.save(&file).unwrap();
+
+    let mut next_run = record(&ROM, 2);
+    next_run.merge(&Coverage::load(&file).unwrap()).unwrap();
+    assert_eq!((next_run.executed(0x202), next_run.read(0x300), next_run.written(0x300)), (3, 3, 2));
+
+    coverage.merge(&record(&ROM, 2)).unwrap();
+    assert_eq!(coverage, next_run);
+}
+
+#[test]
+fn merging_refuses_another_rom() {
+    let mut coverage = record(&ROM, 1);
+    let before = coverage.clone();
+
+    // The same code, followed by a byte of data
+    let mut other_rom = ROM.to_vec();
+    other_rom.push(0xFF);
+    let error = coverage.merge(&record(&other_rom, 1)).unwrap_err();
+    assert_eq!(error, format!(
+        "coverage of ROM {} can't be merged with coverage of ROM {}",
+        sha1_hex(&other_rom), sha1_hex(&ROM),
+    ));
+
+    // Nor coverage that doesn't say which ROM it counts
+    assert!(coverage.merge(&Coverage::parse("200 1 0 0").unwrap()).is_err());
+    assert_eq!(coverage, before);
+}
+
+#[test]
+fn parse_errors_point_at_the_line() {
+    let hash = sha1_hex(&ROM);
+    let invalid_lines = [
+        ("200 1 0", "expected '<address> <executed> <read> <written>', got '200 1 0'"),
+        ("1000 1 0 0", "expected '<address> <executed> <read> <written>', got '1000 1 0 0'"),
+        ("200 1 0 -1", "expected '<address> <executed> <read> <written>', got '200 1 0 -1'"),
+        ("rom abc", "invalid ROM hash 'abc'"),
+    ];
+
+    for (line, error) in invalid_lines {
+        let text = format!("# rusty-8 coverage\nrom {}\n{}\n", hash, line);
+        assert_eq!(Coverage::parse(&text).unwrap_err(), format!("line 3: {}", error));
+    }
+}
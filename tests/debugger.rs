@@ -0,0 +1,242 @@
+// The debugger view without a window: moving around, poking while paused, search results, freezing and stepping.
+// The debugger belongs to the windowed frontend, so its module and the canvas it draws on are built in here.
+
+#[allow(dead_code)]
+#[path = "../src/canvas.rs"]
+mod canvas;
+#[allow(dead_code)]
+#[path = "../src/debugger.rs"]
+mod debugger;
+mod common;
+
+use rusty_8::{Chip8, Cheats, Quirks, SearchFilter};
+use rusty_8::cheats::CheatTarget;
+
+use canvas::{Canvas, CHAR_WIDTH, GLYPH_HEIGHT, LINE_HEIGHT};
+use debugger::{DebugKey, Debugger, DEBUG_WIDTH, DEBUG_HEIGHT};
+
+fn paused_debugger(chip8: &Chip8) -> Debugger {
+    let mut debugger = Debugger::new(chip8);
+    debugger.paused = true;
+    debugger
+}
+
+fn press(debugger: &mut Debugger, chip8: &mut Chip8, keys: &[DebugKey]) {
+    for &key in keys {
+        debugger.key(key, chip8);
+    }
+}
+
+/// What's selected, as the cheat freezing it. None for registers that can't be frozen (I, PC and SP).
+fn selected(debugger: &Debugger, chip8: &Chip8) -> Option<CheatTarget> {
+    let mut cheats = Cheats::new();
+    debugger.toggle_freeze(chip8, &mut cheats).then(|| cheats.cheats()[0].target)
+}
+
+/// Whether the debugger view shows `text` at (x, y), in whatever shade.
+fn shows(view: &[u8], x: usize, y: usize, text: &str) -> bool {
+    let mut expected = Canvas::new(DEBUG_WIDTH, DEBUG_HEIGHT);
+    expected.draw_text(x, y, text, 255);
+
+    (y..y + GLYPH_HEIGHT).all(|y| (x..x + text.len() * CHAR_WIDTH - 1).all(|x| {
+        let pixel = y * DEBUG_WIDTH + x;
+        (view[pixel] != 0) == (expected.pixels[pixel] != 0)
+    }))
+}
+
+#[test]
+fn cursor_starts_at_the_program_and_wraps_around_memory() {
+    let mut chip8 = Chip8::new();
+    let mut debugger = Debugger::new(&chip8);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x200)));
+
+    press(&mut debugger, &mut chip8, &[DebugKey::Right, DebugKey::Down, DebugKey::PageDown]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x311)));
+    press(&mut debugger, &mut chip8, &[DebugKey::Left, DebugKey::Up]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x300)));
+
+    press(&mut debugger, &mut chip8, &[DebugKey::PageUp; 4]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0xF00)));
+    press(&mut debugger, &mut chip8, &[DebugKey::PageDown, DebugKey::Left]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0xFFF)));
+}
+
+#[test]
+fn digits_poke_memory_only_while_paused() {
+    let mut chip8 = common::booted(Quirks::default(), &[0x12, 0x00]);
+    let mut debugger = Debugger::new(&chip8);
+
+    // Running, digits are chip8 keys
+    press(&mut debugger, &mut chip8, &[DebugKey::Digit(0xA)]);
+    assert_eq!(chip8.memory()[0x200], 0x12);
+
+    // Shifted in from the right, the high digit dropping out
+    debugger.paused = true;
+    press(&mut debugger, &mut chip8, &[DebugKey::Digit(0xA)]);
+    assert_eq!(chip8.memory()[0x200], 0x2A);
+    press(&mut debugger, &mut chip8, &[DebugKey::Digit(0xB), DebugKey::Right, DebugKey::Digit(0x5)]);
+    assert_eq!(&chip8.memory()[0x200..0x202], &[0xAB, 0x05]);
+}
+
+#[test]
+fn tab_moves_between_memory_and_registers() {
+    let mut chip8 = Chip8::new();
+    let mut debugger = Debugger::new(&chip8);
+    press(&mut debugger, &mut chip8, &[DebugKey::Right, DebugKey::Tab]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::V(0)));
+
+    // V0 to VF, then I, PC, SP, DT and ST, wrapping around
+    press(&mut debugger, &mut chip8, &[DebugKey::Down; 15]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::V(0xF)));
+    for _ in 0..3 {
+        press(&mut debugger, &mut chip8, &[DebugKey::Right]);
+        assert_eq!(selected(&debugger, &chip8), None);
+    }
+    press(&mut debugger, &mut chip8, &[DebugKey::Down]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::DelayTimer));
+    press(&mut debugger, &mut chip8, &[DebugKey::Down]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::SoundTimer));
+    press(&mut debugger, &mut chip8, &[DebugKey::Down]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::V(0)));
+    press(&mut debugger, &mut chip8, &[DebugKey::Up, DebugKey::Left]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::DelayTimer));
+
+    // Back where the memory cursor was, which registers don't move
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab]);
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x201)));
+}
+
+#[test]
+fn digits_poke_registers() {
+    let mut chip8 = common::booted(Quirks::default(), &[0x12, 0x00]);
+    let mut debugger = paused_debugger(&chip8);
+    let digits = |digits: &[u8]| digits.iter().map(|&digit| DebugKey::Digit(digit)).collect::<Vec<_>>();
+
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab, DebugKey::Down]);
+    press(&mut debugger, &mut chip8, &digits(&[0x1, 0x2, 0x3]));
+    assert_eq!(chip8.registers().v[1], 0x23);
+
+    // I and PC keep 3 digits, SP is a depth of a single one, the timers 2
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab, DebugKey::Tab]);
+    press(&mut debugger, &mut chip8, &[DebugKey::Up; 5]);
+    press(&mut debugger, &mut chip8, &digits(&[0x1, 0x2, 0x3, 0x4]));
+    press(&mut debugger, &mut chip8, &[DebugKey::Right]);
+    press(&mut debugger, &mut chip8, &digits(&[0x3, 0x0, 0x0]));
+    press(&mut debugger, &mut chip8, &[DebugKey::Right]);
+    press(&mut debugger, &mut chip8, &digits(&[0x2, 0x3]));
+    press(&mut debugger, &mut chip8, &[DebugKey::Right]);
+    press(&mut debugger, &mut chip8, &digits(&[0x4, 0x5, 0x6]));
+
+    let regs = chip8.registers();
+    assert_eq!((regs.v[0xF], regs.i, regs.pc, regs.sp, regs.dt), (0x00, 0x234, 0x300, 6, 0x56));
+    assert_eq!(chip8.stack_depth(), 3);
+}
+
+#[test]
+fn register_view_shows_the_registers() {
+    let mut chip8 = common::booted(Quirks::default(), &[0x12, 0x00]);
+    let mut regs = chip8.registers();
+    regs.v[0] = 0xAB;
+    regs.v[0xF] = 0x01;
+    (regs.i, regs.sp, regs.dt, regs.st) = (0x123, 4, 0x3C, 0x05);
+    chip8.set_registers(regs);
+
+    let mut debugger = paused_debugger(&chip8);
+    let view = debugger.render(&chip8, &Cheats::new()).to_vec();
+    assert_eq!(view.len(), DEBUG_WIDTH * DEBUG_HEIGHT);
+
+    // V0 to V7, then V8 to VF, in columns. Values follow their 2 or 3 character names
+    let value_x = |column: usize| column + 3 * CHAR_WIDTH;
+    let line = |line: usize| 4 + line * LINE_HEIGHT;
+    assert!(shows(&view, 140, line(0), "V0"));
+    assert!(shows(&view, value_x(140), line(0), "AB"));
+    assert!(!shows(&view, value_x(140), line(0), "A8"));
+    assert!(shows(&view, value_x(176), line(7), "01"));
+    assert!(shows(&view, value_x(212), line(0), "123"));
+    assert!(shows(&view, value_x(212), line(1), "200"));
+    assert!(shows(&view, value_x(212), line(2), "2"));
+    assert!(shows(&view, value_x(212), line(3), "3C"));
+    assert!(shows(&view, value_x(212), line(4), "05"));
+
+    // The instruction about to run, and the status line
+    assert!(shows(&view, 140, line(9), "200  1200  JP 0x200"));
+    assert!(shows(&view, 4, DEBUG_HEIGHT - LINE_HEIGHT, "PAUSED"));
+}
+
+#[test]
+fn next_candidate_cycles_through_the_search_results() {
+    let mut chip8 = Chip8::new();
+    let mut debugger = Debugger::new(&chip8);
+
+    // Nothing to go to before a search
+    debugger.next_candidate();
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x200)));
+
+    debugger.search(&chip8, None);
+    for addr in [0x800, 0x150, 0x300] {
+        chip8.memory_mut()[addr] = 1;
+    }
+    debugger.search(&chip8, Some(SearchFilter::Changed));
+
+    // From the registers too, the next one after the cursor, then back to the first
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab]);
+    for expected in [0x300, 0x800, 0x150, 0x300] {
+        debugger.next_candidate();
+        assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(expected)));
+    }
+
+    // Nothing left to go to
+    debugger.search(&chip8, Some(SearchFilter::Changed));
+    debugger.next_candidate();
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x300)));
+
+    // A new search starts over from all of memory
+    debugger.search(&chip8, None);
+    debugger.next_candidate();
+    assert_eq!(selected(&debugger, &chip8), Some(CheatTarget::Memory(0x301)));
+}
+
+#[test]
+fn freezing_toggles_a_cheat() {
+    let mut chip8 = common::booted(Quirks::default(), &[0x12, 0x00]);
+    let mut debugger = Debugger::new(&chip8);
+    let mut cheats = Cheats::new();
+
+    assert!(debugger.toggle_freeze(&chip8, &mut cheats));
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab, DebugKey::Up]);
+    assert!(debugger.toggle_freeze(&chip8, &mut cheats));
+    assert_eq!(cheats.to_string(), "0x200 12 Cheat 1\nST 00 Cheat 2\n");
+
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab]);
+    assert!(debugger.toggle_freeze(&chip8, &mut cheats));
+    assert_eq!(cheats.to_string(), "ST 00 Cheat 2\n");
+
+    // PC can't be frozen
+    press(&mut debugger, &mut chip8, &[DebugKey::Tab, DebugKey::Up, DebugKey::Up, DebugKey::Up, DebugKey::Up]);
+    assert!(!debugger.toggle_freeze(&chip8, &mut cheats));
+    assert_eq!(cheats.cheats().len(), 1);
+}
+
+#[test]
+fn step_runs_a_single_instruction() {
+    // 6005  V0 = 5
+    // 7001  V0 += 1
+    let mut chip8 = common::booted(Quirks::default(), &[0x60, 0x05, 0x70, 0x01]);
+
+    chip8.step().unwrap();
+    assert_eq!((chip8.registers().pc, chip8.registers().v[0], chip8.cycles()), (0x202, 5, 1));
+    chip8.step().unwrap();
+    assert_eq!((chip8.registers().pc, chip8.registers().v[0], chip8.cycles()), (0x204, 6, 2));
+}
+
+#[test]
+fn step_doesnt_stop_waiting_for_the_display() {
+    // F029  I = sprite of the digit in V0
+    // D005  Draw it, waiting for the display with the quirk
+    let mut chip8 = common::booted(Quirks { display_wait: true, ..Quirks::default() }, &[0xF0, 0x29, 0xD0, 0x05]);
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.registers().pc, 0x204);
+    assert_eq!(chip8.screen[0], 255);
+}
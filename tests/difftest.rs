@@ -0,0 +1,68 @@
+// Matching a run against a reference trace, including a ROM that ends before the trace does.
+
+mod common;
+
+use rusty_8::{DiffTester, ExitReason, Limits, Quirks, Watchdog};
+use rusty_8::difftest::parse_reference;
+
+// 6005  V0 = 5
+// 7001  V0 += 1, then the empty memory past the ROM
+const ROM: [u8; 4] = [0x60, 0x05, 0x70, 0x01];
+
+/// Run `rom` against `trace` like the headless runner does, until the ROM or the trace ends.
+fn diff(rom: &[u8], trace: &str) -> DiffTester {
+    let mut chip8 = common::booted(Quirks::default(), rom);
+    let mut differ = DiffTester::new(parse_reference(trace).unwrap());
+    let mut watchdog = Watchdog::new(Limits { max_frames: Some(10), stop_on_self_loop: true, ..Limits::default() });
+
+    loop {
+        chip8.run_frame_observed(8, &mut differ).unwrap();
+        let exit_reason = watchdog.check(&chip8);
+        if let Some(ExitReason::Halted | ExitReason::SelfLoop { .. }) = exit_reason {
+            differ.stopped(&chip8);
+        }
+        if differ.is_done() || exit_reason.is_some() {
+            return differ;
+        }
+    }
+}
+
+#[test]
+fn whole_trace_matches() {
+    let differ = diff(&ROM, "PC=200 OP=6005\nPC=202 OP=7001 V0=05\n");
+    assert!(differ.is_done());
+    assert_eq!(differ.divergence(), None);
+    assert_eq!(differ.steps(), 2);
+}
+
+#[test]
+fn differing_step_diverges() {
+    let differ = diff(&ROM, "PC=200 OP=6005\nPC=202 OP=7001 V0=06\n");
+    let divergence = differ.divergence().unwrap();
+
+    assert_eq!((divergence.step, divergence.line, divergence.stopped), (1, 2, false));
+    let differs: Vec<&str> = divergence.rows.iter().filter(|row| row.differs).map(|row| row.name.as_str()).collect();
+    assert_eq!(differs, ["V0"]);
+}
+
+#[test]
+fn rom_halting_before_the_trace_ends_diverges() {
+    // The halting 0000 matches a step leaving out its opcode, but the reference keeps going after it
+    let differ = diff(&ROM, "PC=200 OP=6005\nPC=202 OP=7001\nPC=204 V0=06\nPC=206 OP=1206\n");
+    let divergence = differ.divergence().unwrap();
+
+    assert_eq!((divergence.step, divergence.line, divergence.stopped), (3, 4, true));
+    let op = divergence.rows.iter().find(|row| row.name == "OP").unwrap();
+    assert_eq!((op.ours.as_str(), op.theirs.as_str(), op.differs), ("0000", "1206", true));
+    assert_eq!(ExitReason::Diverged { step: divergence.step }.exit_code(), 4);
+}
+
+#[test]
+fn rom_self_looping_before_the_trace_ends_diverges() {
+    // 1200  Jump to itself
+    let differ = diff(&[0x12, 0x00], &"PC=200 OP=1200\n".repeat(20));
+    let divergence = differ.divergence().unwrap();
+
+    assert!(divergence.stopped);
+    assert_eq!(divergence.step, 8);
+}
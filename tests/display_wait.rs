@@ -0,0 +1,48 @@
+// DXYN under the display wait quirk: at most one sprite per frame, and debugger steps get past it.
+
+mod common;
+
+use common::booted;
+use rusty_8::Quirks;
+
+// 6000  V0 = 0
+// D005  Draw 5 rows at (V0, V0)
+// 7201  V2 += 1, counting the sprites drawn
+// 1202  Back to drawing
+const DRAW_LOOP: [u8; 8] = [0x60, 0x00, 0xD0, 0x05, 0x72, 0x01, 0x12, 0x02];
+
+#[test]
+fn draws_at_most_one_sprite_per_frame() {
+    let mut chip8 = booted(Quirks { display_wait: true, ..Quirks::default() }, &DRAW_LOOP);
+    let mut drawn = 0;
+    for frame in 0..10 {
+        chip8.run_frame(8).unwrap();
+        let drawn_now = chip8.registers().v[2];
+        assert!(drawn_now - drawn <= 1, "{} sprites drawn on frame {}", drawn_now - drawn, frame);
+        drawn = drawn_now;
+    }
+
+    // The first DXYN waits for the second frame, then one is drawn every frame
+    assert_eq!(drawn, 9);
+}
+
+#[test]
+fn draws_freely_without_the_quirk() {
+    let mut chip8 = booted(Quirks { display_wait: false, ..Quirks::default() }, &DRAW_LOOP);
+    chip8.run_frame(8).unwrap();
+    assert!(chip8.registers().v[2] > 1);
+}
+
+#[test]
+fn stepping_gets_past_a_stalled_draw() {
+    // 6000  V0 = 0
+    // F029  I = sprite of the digit in V0
+    // D005  Draw it at (V0, V0)
+    let mut chip8 = booted(Quirks::COSMAC_VIP, &[0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x06]);
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+
+    assert_eq!(chip8.registers().pc, 0x206);
+    assert_eq!(chip8.screen[0], 255);
+}
@@ -0,0 +1,100 @@
+// Where programs run and end: empty memory halts, the PC may wrap, and code written at runtime runs.
+
+mod common;
+
+use common::booted;
+use rusty_8::{Chip8, Chip8Error, Quirks};
+use rusty_8::disasm::disassemble;
+
+#[test]
+fn halts_on_empty_memory() {
+    // 6001  V0 = 1, then the empty memory past the ROM
+    let mut chip8 = booted(Quirks::default(), &[0x60, 0x01]);
+    chip8.run_frame(8).unwrap();
+
+    assert!(chip8.finished_running());
+    assert_eq!(chip8.registers().pc, 0x202);
+    assert_eq!(chip8.cycles(), 2);
+
+    // Halted for good: later frames run nothing
+    chip8.run_frame(8).unwrap();
+    assert_eq!(chip8.cycles(), 2);
+    assert_eq!(chip8.registers().pc, 0x202);
+}
+
+#[test]
+fn exit_is_not_an_instruction() {
+    // 00FD  SUPER-CHIP's EXIT
+    let mut chip8 = booted(Quirks::default(), &[0x00, 0xFD]);
+    assert_eq!(chip8.run_frame(8), Err(Chip8Error::UnknownInstruction { pc: 0x200, instruction: 0x00FD }));
+    assert!(!chip8.finished_running());
+    assert_eq!(disassemble(0x00FD), "SYS 0x0FD");
+}
+
+// 1FFE  Jump to the last instruction of memory, which is
+// 7001  V0 += 1
+// and then 0x000 holds
+// 6142  V1 = 0x42
+fn jump_to_the_end_of_memory(quirks: Quirks) -> Chip8 {
+    let mut chip8 = booted(quirks, &[0x1F, 0xFE]);
+    chip8.memory_mut()[0xFFE..].copy_from_slice(&[0x70, 0x01]);
+    chip8.memory_mut()[0x000..0x002].copy_from_slice(&[0x61, 0x42]);
+    chip8.tick().unwrap();
+    chip8.tick().unwrap();
+    chip8
+}
+
+#[test]
+fn pc_wraps_around_past_0xfff() {
+    let mut chip8 = jump_to_the_end_of_memory(Quirks { wrap_addresses: true, ..Quirks::default() });
+    chip8.tick().unwrap();
+
+    assert_eq!(chip8.registers().v[0], 1);
+    assert_eq!(chip8.registers().v[1], 0x42);
+    assert_eq!(chip8.registers().pc, 0x002);
+}
+
+#[test]
+fn pc_past_0xfff_fails_without_wrapping() {
+    let mut chip8 = jump_to_the_end_of_memory(Quirks { wrap_addresses: false, ..Quirks::default() });
+
+    assert_eq!(chip8.registers().v[0], 1);
+    assert_eq!(chip8.tick(), Err(Chip8Error::PcOutOfBounds { pc: 0x1000 }));
+}
+
+/// A ROM writing `622A 1210` (V2 = 0x2A, jump to the ROM's end) at `address`, then running it.
+fn run_written_code(address: u16) -> Chip8 {
+    let [i_high, i_low] = (0xA000 | address).to_be_bytes();
+    let [jp_high, jp_low] = (0x1000 | address).to_be_bytes();
+    let rom = [
+        0x60, 0x62,     // V0 = 0x62
+        0x61, 0x2A,     // V1 = 0x2A
+        0x62, 0x12,     // V2 = 0x12
+        0x63, 0x10,     // V3 = 0x10
+        i_high, i_low,  // I = address
+        0xF3, 0x55,     // Write V0-V3 at I
+        jp_high, jp_low,    // Jump to address
+    ];
+    let mut chip8 = booted(Quirks::default(), &rom);
+    chip8.run_frame(16).unwrap();
+    chip8
+}
+
+#[test]
+fn runs_code_written_below_0x200() {
+    let chip8 = run_written_code(0x180);
+
+    assert_eq!(&chip8.memory()[0x180..0x184], &[0x62, 0x2A, 0x12, 0x10]);
+    assert_eq!(chip8.registers().v[2], 0x2A);
+    assert!(chip8.finished_running());
+    assert_eq!(chip8.registers().pc, 0x210);
+}
+
+#[test]
+fn runs_code_written_past_the_rom() {
+    let chip8 = run_written_code(0x400);
+
+    assert_eq!(chip8.registers().v[2], 0x2A);
+    assert!(chip8.finished_running());
+    assert_eq!(chip8.registers().pc, 0x210);
+}
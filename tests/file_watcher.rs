@@ -0,0 +1,64 @@
+// The file watcher. Files are changed by size, as some file systems keep modification times to the second.
+
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
+
+use rusty_8::FileWatcher;
+
+const INTERVAL: Duration = Duration::from_millis(20);
+
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Files the watcher reports at its next poll.
+fn poll(watcher: &mut FileWatcher) -> Vec<PathBuf> {
+    sleep(INTERVAL);
+    watcher.changed().into_iter().map(Path::to_path_buf).collect()
+}
+
+#[test]
+fn reports_a_change_once_it_settles() {
+    let path = temp_file("watched.ch8", &[0x12, 0x00]);
+    let mut watcher = FileWatcher::new(INTERVAL);
+    watcher.watch(&path);
+    assert!(poll(&mut watcher).is_empty());
+
+    std::fs::write(&path, [0x60, 0x01, 0x12, 0x02]).unwrap();
+    assert!(poll(&mut watcher).is_empty(), "reported before settling");
+    assert_eq!(poll(&mut watcher), [path.as_path()]);
+    for _ in 0..2 {
+        assert!(poll(&mut watcher).is_empty(), "reported twice");
+    }
+}
+
+#[test]
+fn doesnt_poll_before_the_interval() {
+    let path = temp_file("watched_soon.ch8", &[0x12, 0x00]);
+    let mut watcher = FileWatcher::new(Duration::from_secs(3600));
+    watcher.watch(&path);
+
+    std::fs::write(&path, [0x60, 0x01, 0x12, 0x02]).unwrap();
+    for _ in 0..3 {
+        assert!(watcher.changed().is_empty());
+    }
+}
+
+#[test]
+fn missing_file_is_reported_once_it_comes_back() {
+    let path = temp_file("watched_rebuilt.ch8", &[0x12, 0x00]);
+    let mut watcher = FileWatcher::new(INTERVAL);
+    watcher.watch(&path);
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(poll(&mut watcher).is_empty());
+    assert!(poll(&mut watcher).is_empty(), "reported while missing");
+
+    std::fs::write(&path, [0x60, 0x01, 0x12, 0x02]).unwrap();
+    assert!(poll(&mut watcher).is_empty());
+    assert_eq!(poll(&mut watcher), [path.as_path()]);
+    assert!(poll(&mut watcher).is_empty());
+}
@@ -0,0 +1,222 @@
+// Gym environments: config parsing, rewards, episode ends, and the shipped game configs.
+
+mod common;
+
+use rusty_8::{Chip8, Env, EnvConfig, Quirks};
+use rusty_8::gym::Value;
+
+// 6105  V1 = 5
+// E19E  Skip the jump while key V1 is down
+// 1208  Jump over the next instruction
+// 7201  V2 += 1
+// 7001  V0 += 1
+// 1202  Loop: 4 instructions each time around, so once a frame at 4 instructions per frame
+const COUNTS: [u8; 12] = [0x61, 0x05, 0xE1, 0x9E, 0x12, 0x08, 0x72, 0x01, 0x70, 0x01, 0x12, 0x02];
+
+/// Frames are counted in V0, frames with key 5 held in V2.
+fn counting_env(settings: &str) -> Env {
+    let config = EnvConfig::parse(&format!("actions - 5\nipf 4\nreward V2 1\n{}", settings)).unwrap();
+    Env::new(common::booted(Quirks::default(), &COUNTS), config)
+}
+
+/// Play a shipped game with `config`, always taking `action`, until the episode ends.
+/// Returns the rewards of the steps earning some, and the steps it took.
+fn play(rom: &str, config: &str, action: usize) -> (Env, Vec<f64>, u32) {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom(format!("{}/roms/{}", env!("CARGO_MANIFEST_DIR"), rom)).unwrap();
+    let config = EnvConfig::load(format!("{}/gym/{}", env!("CARGO_MANIFEST_DIR"), config)).unwrap();
+    let mut env = Env::new(chip8, config);
+
+    let (mut rewards, mut steps) = (Vec::new(), 0);
+    loop {
+        let (_, reward, done) = env.step(action);
+        steps += 1;
+        if reward != 0.0 {
+            rewards.push(reward);
+        }
+        if done {
+            return (env, rewards, steps);
+        }
+        assert!(steps < 100_000, "{} never ended", rom);
+    }
+}
+
+#[test]
+fn values_parse() {
+    assert_eq!(Value::parse("VE"), Some(Value::Register(0xE)));
+    assert_eq!(Value::parse("V0"), Some(Value::Register(0)));
+    assert_eq!(Value::parse("0x2F0"), Some(Value::Byte(0x2F0)));
+    assert_eq!(Value::parse("752"), Some(Value::Byte(752)));
+    assert_eq!(Value::parse("bcd:0x314:3"), Some(Value::Bcd(0x314, 3)));
+    assert_eq!(Value::parse("bcd:0xFFD:3"), Some(Value::Bcd(0xFFD, 3)));
+
+    for invalid in ["VG", "V10", "V", "0x1000", "score", "bcd:0x314", "bcd:0x314:0", "bcd:0x314:10", "bcd:0xFFE:3"] {
+        assert_eq!(Value::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn values_read() {
+    let mut chip8 = Chip8::new();
+    chip8.memory_mut()[0x314..0x318].copy_from_slice(&[1, 2, 3, 4]);
+
+    assert_eq!(Value::Byte(0x315).read(&chip8), 2);
+    assert_eq!(Value::Bcd(0x314, 3).read(&chip8), 123);
+    assert_eq!(Value::Bcd(0x315, 1).read(&chip8), 2);
+    assert_eq!(Value::Register(0).read(&common::booted(Quirks::default(), &[0x60, 0xFE])), 0);
+}
+
+#[test]
+fn config_parses_every_setting() {
+    let config = EnvConfig::parse("\
+        # BRIX\n\
+        actions - 4 6 46\n\
+        \n\
+        reward V5 1\n\
+        reward bcd:0x314:3 -0.5\n\
+        done VE 0\n\
+        frame_skip 4\n\
+        ipf 12\n\
+        max_frames 36000\n").unwrap();
+
+    assert_eq!(config, EnvConfig {
+        actions: vec![0, 1 << 4, 1 << 6, 1 << 4 | 1 << 6],
+        rewards: vec![(Value::Register(5), 1.0), (Value::Bcd(0x314, 3), -0.5)],
+        done: vec![(Value::Register(0xE), 0)],
+        frame_skip: 4,
+        ticks_per_frame: 12,
+        max_frames: Some(36000),
+    });
+}
+
+#[test]
+fn config_defaults_to_an_action_per_key() {
+    let config = EnvConfig::parse("# Nothing set\n").unwrap();
+
+    assert_eq!(config, EnvConfig::default());
+    assert_eq!(config.actions.len(), 17);
+    assert_eq!((config.actions[0], config.actions[1], config.actions[16]), (0, 1, 0x8000));
+    assert_eq!((config.frame_skip, config.ticks_per_frame, config.max_frames), (1, 8, None));
+}
+
+#[test]
+fn config_errors_point_at_the_line() {
+    for invalid in ["actions", "actions 4G", "reward V5", "reward VX 1", "done VE -1", "frame_skip 0", "ipf fast", "lives VE"] {
+        let error = EnvConfig::parse(&format!("# BRIX\nactions - 4 6\n  {}  \n", invalid)).unwrap_err();
+        assert_eq!(error, format!("line 3: invalid setting '{}'", invalid));
+    }
+}
+
+#[test]
+fn reward_follows_the_held_keys() {
+    let mut env = counting_env("");
+    assert_eq!(env.action_count(), 2);
+
+    assert_eq!(env.step(1).1, 1.0);
+    assert_eq!(env.step(1).1, 1.0);
+    assert_eq!(env.step(0).1, 0.0);
+    assert_eq!(env.step(1).1, 1.0);
+    assert_eq!(env.frames(), 4);
+}
+
+#[test]
+fn frame_skip_holds_the_keys_for_several_frames() {
+    let mut env = counting_env("reward V0 0.5\nframe_skip 3");
+
+    assert_eq!(env.step(1).1, 3.0 + 1.5);
+    assert_eq!(env.step(0).1, 1.5);
+    assert_eq!(env.frames(), 6);
+    assert_eq!(env.chip8().registers().v[0], 6);
+}
+
+#[test]
+fn episode_ends_on_a_done_value() {
+    let mut env = counting_env("done V0 3");
+
+    assert!(!env.step(1).2);
+    assert!(!env.step(1).2);
+    let (_, reward, done) = env.step(1);
+    assert_eq!((reward, done), (1.0, true));
+
+    // Steps do nothing once over
+    assert_eq!(env.step(1).1, 0.0);
+    assert!(env.step(1).2);
+    assert_eq!(env.frames(), 3);
+}
+
+#[test]
+fn episode_ends_after_max_frames() {
+    let mut env = counting_env("frame_skip 2\nmax_frames 5");
+
+    assert!(!env.step(0).2);
+    assert!(!env.step(0).2);
+    assert!(env.step(0).2);
+    assert_eq!(env.frames(), 6);
+}
+
+#[test]
+fn episode_ends_when_the_rom_fails() {
+    // E000  Not an instruction
+    let mut env = Env::new(common::booted(Quirks::default(), &[0xE0, 0x00]), EnvConfig::default());
+
+    assert!(env.step(0).2);
+    assert!(env.error().is_some());
+    assert_eq!(env.frames(), 0);
+}
+
+#[test]
+fn reset_starts_over() {
+    let mut env = counting_env("done V0 2");
+    env.step(1);
+    env.step(1);
+
+    env.reset();
+    assert_eq!((env.frames(), env.error().is_none()), (0, true));
+    assert_eq!(env.chip8().registers().v[2], 0);
+    let (_, reward, done) = env.step(1);
+    assert_eq!((reward, done), (1.0, false));
+}
+
+#[test]
+fn clones_play_on_their_own() {
+    let mut env = counting_env("");
+    env.step(1);
+
+    let mut clone = env.clone();
+    assert_eq!(clone.step(0).1, 0.0);
+    assert_eq!(env.step(1).1, 1.0);
+    assert_eq!(clone.step(1).1, 1.0);
+    assert_eq!((env.chip8().registers().v[2], clone.chip8().registers().v[2]), (2, 2));
+    assert_eq!((env.frames(), clone.frames()), (2, 3));
+
+    // Both restart from where the original started
+    clone.reset();
+    assert_eq!(clone.chip8().registers().v[2], 0);
+}
+
+#[test]
+fn pong2_scores_are_the_digits_of_ve() {
+    // Holding its paddle down, the agent misses most balls but not all. Serves are random, so the
+    // score isn't known, but someone gets to 9. VE keeps the agent's points as tens, the other's as ones
+    let (env, rewards, steps) = play("PONG2", "PONG2.gym", 2);
+    let chip8 = env.chip8();
+    let ve = chip8.registers().v[0xE];
+    let (agent, other) = (Value::Byte(0x2F3).read(chip8), Value::Byte(0x2F4).read(chip8));
+
+    assert_eq!(&chip8.memory()[0x2F2..0x2F5], &[ve / 100, ve / 10 % 10, ve % 10]);
+    assert!(agent == 9 || other == 9);
+    assert!(rewards.iter().all(|&reward| reward == 1.0 || reward == -1.0));
+    assert_eq!(rewards.iter().sum::<f64>(), agent as f64 - other as f64);
+    assert_eq!(env.frames(), steps * 4);
+}
+
+#[test]
+fn brix_idle_agent_runs_out_of_lives() {
+    // Left alone, the paddle hits a few balls at most: the game ends with the score earned
+    let (env, rewards, _) = play("BRIX", "BRIX.gym", 0);
+    let v = env.chip8().registers().v;
+
+    assert_eq!(v[0xE], 0);
+    assert!(rewards.iter().all(|&reward| reward > 0.0));
+    assert_eq!(rewards.iter().sum::<f64>(), v[5] as f64);
+}
@@ -0,0 +1,24 @@
+// Hashes against known answers, from the SHA-1 (FIPS 180) examples and the CRC-32 check value.
+
+use rusty_8::hash::{crc32, sha1, sha1_hex};
+
+#[test]
+fn sha1_known_answers() {
+    assert_eq!(sha1_hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(sha1_hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(sha1_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+    assert_eq!(sha1_hex(&[b'a'; 1_000_000]), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+}
+
+#[test]
+fn sha1_hex_spells_out_the_digest() {
+    let digest = sha1(b"abc");
+    assert_eq!(&digest[..4], &[0xA9, 0x99, 0x3E, 0x36]);
+    assert_eq!(sha1_hex(b"abc"), digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>());
+}
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    assert_eq!(crc32(b""), 0);
+}
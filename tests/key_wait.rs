@@ -0,0 +1,95 @@
+// FX0A answers a key pressed after the wait started: on its press, or its release with the quirk.
+
+mod common;
+
+use rusty_8::{Chip8, Quirks};
+
+// F30A  V3 = next key
+// 1202  Loop forever
+const WAIT_KEY: [u8; 4] = [0xF3, 0x0A, 0x12, 0x02];
+
+fn waiting_chip8(key_wait_release: bool) -> Chip8 {
+    let mut chip8 = common::booted(Quirks { key_wait_release, ..Quirks::default() }, &WAIT_KEY);
+    chip8.run_frame(8).unwrap();
+    assert_eq!(chip8.waiting, Some(3));
+    chip8
+}
+
+#[test]
+fn default_preset_answers_on_press() {
+    let (default, vip) = (Quirks::default(), Quirks::COSMAC_VIP);
+    assert!(!default.key_wait_release);
+    assert!(vip.key_wait_release);
+}
+
+#[test]
+fn press_answers_without_release_quirk() {
+    let mut chip8 = waiting_chip8(false);
+    chip8.press_key(7);
+    chip8.run_frame(8).unwrap();
+
+    assert_eq!(chip8.waiting, None);
+    assert_eq!(chip8.registers().v[3], 7);
+}
+
+#[test]
+fn press_alone_doesnt_answer_with_release_quirk() {
+    let mut chip8 = waiting_chip8(true);
+    chip8.press_key(7);
+    for _ in 0..5 {
+        chip8.run_frame(8).unwrap();
+    }
+
+    assert_eq!(chip8.waiting, Some(3));
+    assert_eq!(chip8.registers().v[3], 0);
+}
+
+#[test]
+fn press_then_release_answers_with_both_quirks() {
+    for key_wait_release in [false, true] {
+        let mut chip8 = waiting_chip8(key_wait_release);
+        chip8.press_key(7);
+        chip8.run_frame(8).unwrap();
+        chip8.release_key(7);
+        chip8.run_frame(8).unwrap();
+
+        assert_eq!(chip8.waiting, None, "key_wait_release: {}", key_wait_release);
+        assert_eq!(chip8.registers().v[3], 7, "key_wait_release: {}", key_wait_release);
+    }
+}
+
+#[test]
+fn key_down_before_the_wait_doesnt_count() {
+    for key_wait_release in [false, true] {
+        let mut chip8 = common::booted(Quirks { key_wait_release, ..Quirks::default() }, &WAIT_KEY);
+        chip8.press_key(7);
+        chip8.run_frame(8).unwrap();
+        chip8.run_frame(8).unwrap();
+        assert_eq!(chip8.waiting, Some(3), "key_wait_release: {}", key_wait_release);
+
+        // Its release is no press, nor the release of a key pressed during the wait
+        chip8.release_key(7);
+        chip8.run_frame(8).unwrap();
+        assert_eq!(chip8.waiting, Some(3), "key_wait_release: {}", key_wait_release);
+
+        chip8.press_key(5);
+        chip8.run_frame(8).unwrap();
+        chip8.release_key(5);
+        chip8.run_frame(8).unwrap();
+        assert_eq!(chip8.registers().v[3], 5, "key_wait_release: {}", key_wait_release);
+    }
+}
+
+#[test]
+fn timers_tick_while_waiting() {
+    // 600A  V0 = 10
+    // F015  DT = V0
+    // F30A  V3 = next key
+    let mut chip8 = common::booted(Quirks::default(), &[0x60, 0x0A, 0xF0, 0x15, 0xF3, 0x0A, 0x12, 0x06]);
+    for _ in 0..4 {
+        chip8.run_frame(8).unwrap();
+    }
+
+    assert_eq!(chip8.waiting, Some(3));
+    assert_eq!(chip8.registers().dt, 6);
+}
@@ -0,0 +1,63 @@
+// Memory map presets, and maps whose font, stack or program overlap or don't fit.
+
+use rusty_8::{Chip8, MemoryMap};
+
+#[test]
+fn presets_are_valid() {
+    for name in ["rusty-8", "standard", "eti660"] {
+        let memory_map = MemoryMap::from_name(name).unwrap();
+        assert_eq!(memory_map.validate(), Ok(()), "{}", name);
+    }
+}
+
+#[test]
+fn presets_load_roms_at_their_program_start() {
+    for (memory_map, program_start) in [(MemoryMap::default(), 0x200), (MemoryMap::STANDARD, 0x200), (MemoryMap::ETI_660, 0x600)] {
+        let mut chip8 = Chip8::new();
+        chip8.set_memory_map(memory_map).unwrap();
+        chip8.load_rom_bytes(&[0x12, 0x34]).unwrap();
+
+        assert_eq!(chip8.registers().pc, program_start);
+        assert_eq!(&chip8.memory()[program_start as usize..program_start as usize + 2], &[0x12, 0x34]);
+    }
+}
+
+#[test]
+fn font_is_copied_to_font_start() {
+    let mut chip8 = Chip8::new();
+    chip8.set_memory_map(MemoryMap::STANDARD).unwrap();
+
+    // The 0 digit, then the 1
+    assert_eq!(&chip8.memory()[0x050..0x05A], &[0xF0, 0x90, 0x90, 0x90, 0xF0, 0x20, 0x60, 0x20, 0x20, 0x70]);
+}
+
+#[test]
+fn overlapping_regions_are_rejected() {
+    let font_on_stack = MemoryMap { font_start: 0x0A0, ..MemoryMap::STANDARD };
+    assert_eq!(font_on_stack.validate(), Err("Font at 0x0A0 overlaps the stack at 0x0A0".to_string()));
+
+    // The stack takes 254 bytes, so it runs into the program
+    let stack_into_program = MemoryMap { stack_start: 0x180, ..MemoryMap::STANDARD };
+    assert!(stack_into_program.validate().is_err());
+
+    let font_in_program = MemoryMap { font_start: 0x300, ..MemoryMap::STANDARD };
+    assert!(font_in_program.validate().is_err());
+
+    let mut chip8 = Chip8::new();
+    assert!(chip8.set_memory_map(font_on_stack).is_err());
+    assert_eq!(chip8.memory_map(), MemoryMap::default());
+}
+
+#[test]
+fn adjacent_regions_are_accepted() {
+    // Font right before the stack, stack right before the program
+    let memory_map = MemoryMap { font_start: 0x052, stack_start: 0x0A2, program_start: 0x1A0 };
+    assert_eq!(memory_map.validate(), Ok(()));
+}
+
+#[test]
+fn regions_past_the_end_of_memory_are_rejected() {
+    assert!(MemoryMap { font_start: 0xFD0, ..MemoryMap::STANDARD }.validate().is_err());
+    assert!(MemoryMap { stack_start: 0xF80, ..MemoryMap::STANDARD }.validate().is_err());
+    assert!(MemoryMap { program_start: 0x1000, ..MemoryMap::STANDARD }.validate().is_err());
+}
@@ -0,0 +1,82 @@
+// The profiler: hot spots, opcode classes, calls and frame load, and its report.
+
+mod common;
+
+use rusty_8::{Profiler, Quirks};
+
+// 2206  Call the subroutine
+// D005  Draw
+// 1200  Loop
+// 7001  Subroutine: V0 += 1
+// 00EE  Return
+const ROM: [u8; 10] = [0x22, 0x06, 0xD0, 0x05, 0x12, 0x00, 0x70, 0x01, 0x00, 0xEE];
+
+/// A profile of frames running these many instructions each.
+fn profile(frames: &[usize]) -> Profiler {
+    let mut chip8 = common::booted(Quirks::default(), &ROM);
+    let mut profiler = Profiler::new();
+    for &ticks in frames {
+        chip8.run_frame_observed(ticks, &mut profiler).unwrap();
+    }
+    profiler
+}
+
+#[test]
+fn counts_executions_per_address() {
+    // 13 instructions: around the 5 instruction loop twice, then 2206, 7001 and 00EE again
+    let profiler = profile(&[6, 6, 1]);
+
+    assert_eq!(profiler.instructions(), 13);
+    let counts: Vec<u64> = (0x200..0x20C).step_by(2).map(|addr| profiler.count_at(addr)).collect();
+    assert_eq!(counts, [3, 2, 2, 3, 3, 0]);
+    assert_eq!(profiler.count_at(0x201), 0);
+    assert_eq!(profiler.count_at(0xFFFF), 0);
+}
+
+#[test]
+fn report_lists_hot_spots_calls_and_frames() {
+    let mut report = Vec::new();
+    profile(&[6, 6, 1]).write_report(&mut report, 3).unwrap();
+
+    assert_eq!(String::from_utf8(report).unwrap(), "\
+13 instructions executed over 3 frames
+
+Top 3 addresses:
+       count       %  addr  opcode  instruction
+           3  23.08%  200   2206    CALL 0x206
+           3  23.08%  206   7001    ADD V0, 0x01
+           3  23.08%  208   00EE    RET
+
+Opcode classes:
+       count       %  class
+           3  23.08%  0XXX
+           2  15.38%  1XXX
+           3  23.08%  2XXX
+           3  23.08%  7XXX
+           2  15.38%  DXXX
+
+Subroutine calls (2NNN):
+       calls  subroutine
+           3  206
+
+Clock cycles per frame:
+      cycles   frames
+           1        1  ####################
+           6        2  ########################################
+
+DXYN per frame:
+       draws   frames
+           0        1  ####################
+           1        2  ########################################
+");
+}
+
+#[test]
+fn empty_report() {
+    let mut report = Vec::new();
+    Profiler::new().write_report(&mut report, 20).unwrap();
+    let report = String::from_utf8(report).unwrap();
+
+    assert!(report.starts_with("0 instructions executed over 0 frames\n\nTop 0 addresses:\n"));
+    assert!(report.ends_with("DXYN per frame:\n       draws   frames\n"));
+}
@@ -0,0 +1,56 @@
+// Property test for the interpreter core: whatever is in memory, running it may fail with a
+// `Chip8Error` but never panics. A cheap version of the cargo-fuzz targets in fuzz/, for stable Rust.
+
+use nanorand::{Rng, WyRand};
+use rusty_8::{Chip8, Quirks, StackConfig, Timing};
+
+const IMAGES: u64 = 300;
+const MAX_FRAMES: usize = 30;
+const TICKS_PER_FRAME: usize = 64;
+
+#[test]
+fn random_images_never_panic() {
+    for seed in 0..IMAGES {
+        let mut rng = WyRand::new_seed(seed);
+
+        for quirks in [Quirks::default(), Quirks::COSMAC_VIP, Quirks::CHIP48] {
+            let mut chip8 = Chip8::with_quirks(quirks);
+            chip8.timing = if seed % 2 == 0 { Timing::Fixed } else { Timing::CosmacVip };
+            chip8.stack_config = if seed % 3 == 0 { StackConfig::COSMAC_VIP } else { StackConfig::default() };
+
+            let mut image = [0u8; 4096 - 0x200];
+            rng.fill(&mut image[..]);
+            chip8.memory_mut()[0x200..].copy_from_slice(&image);
+
+            for frame in 0..MAX_FRAMES {
+                let mut keys = [false; 16];
+                keys[frame % 16] = frame % 3 != 0;
+                chip8.set_keys(&keys);
+
+                match chip8.run_frame(TICKS_PER_FRAME) {
+                    Ok(_) if !chip8.finished_running() => {},
+                    _ => break,
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn dangerous_instructions_fail_cleanly() {
+    // I near the end of memory, then: draw, BCD, store, load, and an unknown instruction
+    let cases: [&[u8]; 5] = [
+        &[0xAF, 0xFE, 0xD0, 0x0F],
+        &[0xAF, 0xFF, 0xF0, 0x33],
+        &[0xAF, 0xF8, 0xFF, 0x55],
+        &[0xAF, 0xF8, 0xFF, 0x65],
+        &[0xFF, 0xFF],
+    ];
+
+    for case in cases.iter() {
+        let mut chip8 = Chip8::new();
+        chip8.memory_mut()[0x200..0x200 + case.len()].copy_from_slice(case);
+
+        assert!(chip8.run_frame(case.len() / 2).is_err(), "{:02X?} should fail", case);
+    }
+}
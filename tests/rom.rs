@@ -0,0 +1,208 @@
+// Reading ROMs from zip archives, which are built here entry by entry.
+
+use rusty_8::{Chip8, MemoryMap, RomError};
+use rusty_8::hash::crc32;
+use rusty_8::rom::{is_zip, read_rom, read_zip_rom, zip_roms};
+
+const CAPACITY: usize = 4096 - 0x200;
+
+// 6005  V0 = 5
+// F029  I = sprite of the digit in V0
+// D015  Draw it
+// 1206  Loop forever
+const ROM: [u8; 8] = [0x60, 0x05, 0xF0, 0x29, 0xD0, 0x15, 0x12, 0x06];
+
+/// A file to put in an archive.
+struct Entry<'a> {
+    name: &'a str,
+    data: Vec<u8>,
+    deflated: bool,
+}
+
+impl<'a> Entry<'a> {
+    fn stored(name: &'a str, data: &[u8]) -> Self {
+        Entry { name, data: data.to_vec(), deflated: false }
+    }
+
+    fn deflated(name: &'a str, data: &[u8]) -> Self {
+        Entry { name, data: data.to_vec(), deflated: true }
+    }
+}
+
+/// A zip archive of `entries`. `crc` gives each entry's CRC, the right one being `crc32`.
+fn zip_with_crc(entries: &[Entry], crc: impl Fn(&[u8]) -> u32) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+
+    for entry in entries {
+        let (method, data) = match entry.deflated {
+            true => (8u16, miniz_oxide::deflate::compress_to_vec(&entry.data, 6)),
+            false => (0u16, entry.data.clone()),
+        };
+        let offset = archive.len() as u32;
+
+        // Fields shared by the local header and the central directory: from the version needed
+        // to extract up to the name's length, followed by an extra field's length of 0
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());      // Flags
+        fields.extend_from_slice(&method.to_le_bytes());
+        fields.extend_from_slice(&[0; 4]);                  // Modification time and date
+        fields.extend_from_slice(&crc(&entry.data).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+
+        archive.extend_from_slice(&0x04034B50u32.to_le_bytes());
+        archive.extend_from_slice(&fields);
+        archive.extend_from_slice(entry.name.as_bytes());
+        archive.extend_from_slice(&data);
+
+        directory.extend_from_slice(&0x02014B50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());  // Version made by
+        directory.extend_from_slice(&fields);
+        directory.extend_from_slice(&[0; 10]);              // Comment length, disk, attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(entry.name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054B50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);                     // Disk numbers
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());        // Comment length
+
+    archive
+}
+
+fn zip(entries: &[Entry]) -> Vec<u8> {
+    zip_with_crc(entries, crc32)
+}
+
+#[test]
+fn reads_a_stored_rom() {
+    let archive = zip(&[Entry::stored("PONG.ch8", &ROM)]);
+    assert!(is_zip(&archive));
+    assert_eq!(read_zip_rom(&archive, None, CAPACITY).unwrap(), ROM);
+}
+
+#[test]
+fn reads_a_deflated_rom() {
+    // Repetitive enough to actually shrink
+    let rom = ROM.repeat(64);
+    let archive = zip(&[Entry::deflated("PONG.ch8", &rom)]);
+    assert!(archive.len() < rom.len());
+    assert_eq!(read_zip_rom(&archive, None, CAPACITY).unwrap(), rom);
+}
+
+#[test]
+fn picks_a_rom_by_name_in_a_multi_rom_archive() {
+    let archive = zip(&[
+        Entry::stored("README.txt", b"Two games"),
+        Entry::stored("games/BRIX.ch8", &ROM),
+        Entry::deflated("games/PONG", &[0x12, 0x00]),
+    ]);
+
+    assert_eq!(zip_roms(&archive).unwrap(), ["games/BRIX.ch8", "games/PONG"]);
+    match read_zip_rom(&archive, None, CAPACITY) {
+        Err(RomError::SeveralRomsInArchive(names)) => assert_eq!(names, ["games/BRIX.ch8", "games/PONG"]),
+        result => panic!("expected several ROMs, got {:?}", result),
+    }
+
+    // By path, file name or file name without extension, ignoring case
+    for name in ["games/BRIX.ch8", "brix.ch8", "Brix"] {
+        assert_eq!(read_zip_rom(&archive, Some(name), CAPACITY).unwrap(), ROM, "{}", name);
+    }
+    assert_eq!(read_zip_rom(&archive, Some("pong"), CAPACITY).unwrap(), [0x12, 0x00]);
+    assert!(matches!(read_zip_rom(&archive, Some("tetris"), CAPACITY), Err(RomError::NotInArchive(name)) if name == "tetris"));
+}
+
+#[test]
+fn archive_without_roms_has_none_to_read() {
+    let archive = zip(&[Entry::stored("README.txt", b"Nothing to run"), Entry::stored("screenshot.png", &[0x89])]);
+    assert!(matches!(read_zip_rom(&archive, None, CAPACITY), Err(RomError::NoRomInArchive)));
+}
+
+#[test]
+fn truncated_archive_is_refused() {
+    let archive = zip(&[Entry::stored("PONG.ch8", &ROM)]);
+    for len in [4, 30, archive.len() - 30, archive.len() - 1] {
+        let result = read_zip_rom(&archive[..len], None, CAPACITY);
+        assert!(matches!(result, Err(RomError::Archive(_))), "{} bytes: {:?}", len, result);
+    }
+}
+
+#[test]
+fn corrupt_archive_is_refused() {
+    let mut archive = zip(&[Entry::deflated("PONG.ch8", &ROM.repeat(64))]);
+
+    // Scramble the compressed data, right after the local header and name
+    let data_start = 30 + "PONG.ch8".len();
+    for byte in &mut archive[data_start..data_start + 8] {
+        *byte ^= 0xFF;
+    }
+    let result = read_zip_rom(&archive, None, CAPACITY);
+    assert!(matches!(result, Err(RomError::Archive(_))), "{:?}", result);
+
+    // A central directory that doesn't start where the end record says
+    let mut archive = zip(&[Entry::stored("PONG.ch8", &ROM)]);
+    let end = archive.len() - 22;
+    archive[end + 16] += 1;
+    assert!(matches!(read_zip_rom(&archive, None, CAPACITY), Err(RomError::Archive(e)) if e == "corrupt central directory"));
+}
+
+#[test]
+fn crc_mismatch_is_refused() {
+    let archive = zip_with_crc(&[Entry::stored("PONG.ch8", &ROM)], |data| crc32(data) ^ 1);
+    match read_zip_rom(&archive, None, CAPACITY) {
+        Err(RomError::Archive(e)) => assert_eq!(e, "PONG.ch8 is corrupt, its CRC doesn't match"),
+        result => panic!("expected a CRC mismatch, got {:?}", result),
+    }
+}
+
+#[test]
+fn only_archives_are_zips() {
+    assert!(!is_zip(&ROM));
+    assert!(!is_zip(&[0x50]));
+    assert!(!is_zip(b"PK"));
+    // Starting with PK isn't enough, the whole signature of a zip header has to follow
+    assert!(!is_zip(&[0x50, 0x4B, 0x12, 0x00]));
+    assert!(is_zip(&zip(&[Entry::stored("PONG.ch8", &ROM)])));
+}
+
+#[test]
+fn rom_larger_than_capacity_isnt_inflated() {
+    let rom = vec![0x12; CAPACITY + 1];
+    let archive = zip(&[Entry::deflated("BIG.ch8", &rom)]);
+    assert!(matches!(read_zip_rom(&archive, None, CAPACITY), Err(RomError::TooLarge { size, capacity })
+        if size == CAPACITY + 1 && capacity == CAPACITY));
+
+    // Capacity is what's left after the program start, not the whole memory
+    let rom = vec![0x12; 3000];
+    let archive = zip(&[Entry::stored("BIG.ch8", &rom)]);
+    let eti_capacity = MemoryMap::ETI_660.rom_capacity();
+    assert_eq!(eti_capacity, 4096 - 0x600);
+    assert!(read_zip_rom(&archive, None, CAPACITY).is_ok());
+    assert!(matches!(read_zip_rom(&archive, None, eti_capacity), Err(RomError::TooLarge { size: 3000, capacity })
+        if capacity == eti_capacity));
+}
+
+#[test]
+fn chip8_loads_zipped_roms_that_fit_its_memory_map() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("rom_test_big.zip");
+    std::fs::write(&path, zip(&[Entry::stored("BIG.ch8", &[0x12; 3000])])).unwrap();
+
+    let mut chip8 = Chip8::new();
+    chip8.load_rom(&path).unwrap();
+    assert_eq!(chip8.rom_range(), 0x200..0x200 + 3000);
+
+    let mut chip8 = Chip8::new();
+    chip8.set_memory_map(MemoryMap::ETI_660).unwrap();
+    assert!(matches!(chip8.load_rom(&path), Err(RomError::TooLarge { size: 3000, capacity: 2560 })));
+    assert_eq!(read_rom(&path, Some("big"), 4096).unwrap().len(), 3000);
+}
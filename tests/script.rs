@@ -0,0 +1,166 @@
+// Rhai scripts: the chip8 API, its range errors, and the headless runner's exit codes for scripts.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rusty_8::{Chip8, Quirks, Script};
+
+// 6005  V0 = 5
+// A300  I = 0x300
+// F055  [I] = V0
+// 1206  Loop
+const ROM: [u8; 8] = [0x60, 0x05, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06];
+
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Run `source` against ROM for `frames` frames, or until it fails or stops.
+fn run(source: &str, frames: usize) -> (Chip8, Script) {
+    let mut chip8 = common::booted(Quirks::default(), &ROM);
+    let mut script = Script::new(source).unwrap();
+    for _ in 0..frames {
+        chip8.run_frame_observed(8, &mut script).unwrap();
+        if script.error().is_some() || script.is_stopped() {
+            break;
+        }
+    }
+    (chip8, script)
+}
+
+/// Why `source` failed, on the first frame.
+fn error(source: &str) -> String {
+    let (_, script) = run(source, 1);
+    script.error().expect(source).to_string()
+}
+
+#[test]
+fn registers_read_and_write() {
+    // Set before the first frame, which then starts past 6005
+    let (chip8, script) = run(r#"
+        set_reg("V0", 0x12);
+        set_reg("VF", 0xFF);
+        set_reg("I", 0x1234);
+        set_reg("SP", 1);
+        set_reg("DT", 60);
+        set_reg("ST", 3);
+        if reg("V0") != 0x12 || reg("VF") != 0xFF { throw "V registers"; }
+        if reg("I") != 0x234 || reg("PC") != 0x200 || reg("SP") != 1 { throw "I, PC or SP"; }
+        if reg("DT") != 60 || reg("ST") != 3 { throw "timers"; }
+        set_reg("PC", 0x202);
+    "#, 1);
+    assert_eq!(script.error(), None);
+
+    let regs = chip8.registers();
+    assert_eq!((regs.v[0], regs.v[0xF], regs.dt, regs.st), (0x12, 0xFF, 59, 2));
+    assert_eq!((chip8.memory()[0x300], chip8.stack_depth()), (0x12, 1));
+}
+
+#[test]
+fn unknown_registers_fail() {
+    for name in ["V10", "VG", "V", "X", "v0", "pc"] {
+        assert!(error(&format!(r#"reg("{}")"#, name)).contains(&format!("Unknown register '{}'", name)), "{}", name);
+        assert!(error(&format!(r#"set_reg("{}", 1)"#, name)).contains(&format!("Unknown register '{}'", name)), "{}", name);
+    }
+    assert!(error(r#"set_reg("V0", 256)"#).contains("256 doesn't fit in a byte"));
+    assert!(error(r#"set_reg("DT", -1)"#).contains("-1 doesn't fit in a byte"));
+}
+
+#[test]
+fn memory_reads_and_writes() {
+    let (chip8, script) = run("poke(0x400, peek(0x201) + 1); poke(0xFFF, 9);", 1);
+    assert_eq!(script.error(), None);
+    assert_eq!((chip8.memory()[0x400], chip8.memory()[0xFFF]), (6, 9));
+
+    assert!(error("peek(0x1000)").contains("Address 0x1000 is out of memory"));
+    assert!(error("poke(-1, 0)").contains("is out of memory"));
+    assert!(error("poke(0x400, 0x100)").contains("256 doesn't fit in a byte"));
+}
+
+#[test]
+fn keys_press_and_release() {
+    let (chip8, script) = run(r#"
+        press(5);
+        press(0xF);
+        release(0xF);
+        if !key(5) || key(0xF) || key(0) { throw "keys"; }
+    "#, 1);
+    assert_eq!(script.error(), None);
+    assert!(chip8.keyboard[5]);
+    assert!(!chip8.keyboard[0xF]);
+
+    for call in ["press(16)", "release(16)", "key(16)", "press(-1)"] {
+        let key = call.trim_start_matches(|c: char| c.is_alphabetic()).trim_matches(|c| c == '(' || c == ')');
+        assert!(error(call).contains(&format!("No key {}, keys go from 0 to 0xF", key)), "{}", call);
+    }
+}
+
+#[test]
+fn hooks_run_around_frames_instructions_and_writes() {
+    let (chip8, script) = run(r#"
+        on_pc(0x204, |pc| poke(0x400, peek(0x400) + 1));
+        fn on_frame_start() { poke(0x401, frame()); }
+        fn on_frame_end() { if frame() == 3 { stop(); } }
+        fn on_write(addr, value) { if addr == 0x300 { poke(0x402, value); } }
+    "#, 10);
+
+    assert_eq!(script.error(), None);
+    assert!(script.is_stopped());
+    // 3 frames: F055 ran once, the rest of them jumped to itself
+    assert_eq!(&chip8.memory()[0x400..0x403], &[1, 2, 5]);
+    assert_eq!(chip8.cycles(), 24);
+}
+
+#[test]
+fn screenshots_are_pgm_images() {
+    // F029  I = sprite of the digit in V0, a 0
+    // D005  Draw it at (0, 0)
+    // 1204  Loop
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("screenshot.pgm");
+    let mut chip8 = common::booted(Quirks::default(), &[0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04]);
+    let mut script = Script::new(&format!("fn on_frame_end() {{ screenshot({:?}); }}", path)).unwrap();
+    chip8.run_frame_observed(8, &mut script).unwrap();
+    assert_eq!(script.error(), None);
+
+    let image = std::fs::read(&path).unwrap();
+    let header = b"P5\n64 32\n255\n";
+    assert_eq!(&image[..header.len()], header);
+    let pixels = &image[header.len()..];
+    assert_eq!(pixels.len(), 64 * 32);
+    assert_eq!(&pixels[..5], &[255, 255, 255, 255, 0]);
+    assert!(pixels.iter().all(|&pixel| pixel == 0 || pixel == 255));
+}
+
+#[test]
+fn headless_run_exits_as_the_script_says() {
+    let rom = temp_file("script.ch8", &ROM);
+    let run_headless = |name: &str, source: &str| {
+        let script = temp_file(name, source.as_bytes());
+        Command::new(env!("CARGO_BIN_EXE_rusty-8-headless"))
+            .arg("--script").arg(script).arg("--ignore-self-loop").arg(&rom)
+            .output().unwrap()
+    };
+
+    let passing = run_headless("passing.rhai", r#"
+        fn on_frame_end() {
+            if frame() == 2 {
+                if peek(0x300) != 5 { throw "expected 5 at 0x300"; }
+                stop();
+            }
+        }
+    "#);
+    assert_eq!(passing.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&passing.stdout).contains("stopped by the script"));
+
+    let failing = run_headless("failing.rhai", r#"
+        fn on_frame_end() {
+            if frame() == 2 && peek(0x300) != 6 { throw "expected 6 at 0x300"; }
+        }
+    "#);
+    assert_eq!(failing.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&failing.stdout).contains("expected 6 at 0x300"));
+}
@@ -0,0 +1,76 @@
+// CALL and RET: overflow past the maximum depth, underflow, and the stack kept in memory.
+
+mod common;
+
+use rusty_8::{Chip8, Chip8Error, Quirks, StackConfig};
+
+fn chip8_with_stack(stack_config: StackConfig, rom: &[u8]) -> Chip8 {
+    let mut chip8 = common::booted(Quirks::default(), rom);
+    chip8.stack_config = stack_config;
+    chip8
+}
+
+#[test]
+fn call_past_max_depth_overflows() {
+    // 2200  Call itself, forever
+    let mut chip8 = chip8_with_stack(StackConfig::COSMAC_VIP, &[0x22, 0x00]);
+    for _ in 0..12 {
+        chip8.tick().unwrap();
+    }
+
+    assert_eq!(chip8.stack_depth(), 12);
+    assert_eq!(chip8.tick(), Err(Chip8Error::StackOverflow { pc: 0x200, stack: vec![0x202; 12] }));
+}
+
+#[test]
+fn unlimited_depth_overflows_when_the_stack_is_full() {
+    let mut chip8 = chip8_with_stack(StackConfig { max_depth: None, in_memory: false }, &[0x22, 0x00]);
+    let error = loop {
+        if let Err(error) = chip8.tick() {
+            break error;
+        }
+    };
+
+    assert!(matches!(error, Chip8Error::StackOverflow { pc: 0x200, .. }));
+    assert_eq!(chip8.stack_depth(), 127);
+}
+
+#[test]
+fn ret_on_empty_stack_underflows() {
+    // 00EE  Return, from nowhere
+    for stack_config in [StackConfig::COSMAC_VIP, StackConfig::CHIP48] {
+        let mut chip8 = chip8_with_stack(stack_config, &[0x00, 0xEE]);
+        assert_eq!(chip8.tick(), Err(Chip8Error::StackUnderflow { pc: 0x200 }));
+    }
+}
+
+// 2204  Call 0x204
+// 0000
+// 2208  Call 0x208
+// 0000
+// 1208  Loop forever
+const TWO_CALLS: [u8; 10] = [0x22, 0x04, 0x00, 0x00, 0x22, 0x08, 0x00, 0x00, 0x12, 0x08];
+
+#[test]
+fn in_memory_stack_holds_return_addresses_at_stack_start() {
+    let mut chip8 = chip8_with_stack(StackConfig::COSMAC_VIP, &TWO_CALLS);
+    chip8.tick().unwrap();
+    chip8.tick().unwrap();
+
+    // Little-endian return addresses, outermost call first
+    let stack_start = chip8.memory_map().stack_start as usize;
+    assert_eq!(&chip8.memory()[stack_start..stack_start + 6], &[0x02, 0x02, 0x06, 0x02, 0x00, 0x00]);
+    assert_eq!(chip8.registers().sp, 4);
+    assert_eq!(chip8.call_stack(), vec![0x202, 0x206]);
+}
+
+#[test]
+fn separate_stack_leaves_memory_alone() {
+    let mut chip8 = chip8_with_stack(StackConfig::CHIP48, &TWO_CALLS);
+    let memory = chip8.memory().to_vec();
+    chip8.tick().unwrap();
+    chip8.tick().unwrap();
+
+    assert_eq!(chip8.memory(), &memory[..]);
+    assert_eq!(chip8.call_stack(), vec![0x202, 0x206]);
+}
@@ -0,0 +1,127 @@
+// Traces: the binary format round-trips and converts to the text one, and filters parse as documented.
+
+mod common;
+
+use std::io::{self, Cursor};
+
+use rusty_8::{Quirks, TraceFilter, TraceFormat, Tracer};
+use rusty_8::trace::{convert_to_text, TraceRecord};
+
+// 6005  V0 = 5
+// A300  I = 0x300
+// F055  [I] = V0
+// 1206  Loop
+const ROM: [u8; 8] = [0x60, 0x05, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06];
+
+fn trace(format: TraceFormat, filter: TraceFilter) -> Vec<u8> {
+    let mut chip8 = common::booted(Quirks::default(), &ROM);
+    let mut tracer = Tracer::new(Vec::new(), format, filter).unwrap();
+    chip8.run_frame_observed(4, &mut tracer).unwrap();
+    tracer.finish().unwrap()
+}
+
+fn record() -> TraceRecord {
+    TraceRecord {
+        cycle: 0x1_0000_0002,
+        pc: 0x2A4,
+        instruction: 0xFA55,
+        registers: vec![(0xA, 0x05), (0x10, 0x301)],
+        memory_writes: vec![(0x300, 0x01), (0x301, 0xFF)],
+    }
+}
+
+#[test]
+fn binary_record_round_trips() {
+    let mut binary = Vec::new();
+    record().write_binary(&mut binary).unwrap();
+    record().write_binary(&mut binary).unwrap();
+
+    let mut input = Cursor::new(binary);
+    assert_eq!(TraceRecord::read_binary(&mut input).unwrap(), Some(record()));
+    assert_eq!(TraceRecord::read_binary(&mut input).unwrap(), Some(record()));
+    assert_eq!(TraceRecord::read_binary(&mut input).unwrap(), None);
+}
+
+#[test]
+fn text_record_lists_changes() {
+    let mut text = Vec::new();
+    record().write_text(&mut text).unwrap();
+
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "4294967298  2A4  FA55  LD [I], VA           VA=0x05 I=0x301 [0x300]=0x01 [0x301]=0xFF\n",
+    );
+}
+
+#[test]
+fn binary_trace_converts_to_the_text_trace() {
+    let binary = trace(TraceFormat::Binary, TraceFilter::default());
+    assert_eq!(&binary[..5], b"R8TR\x01");
+
+    let mut text = Vec::new();
+    assert_eq!(convert_to_text(&mut Cursor::new(binary), &mut text).unwrap(), 4);
+    assert_eq!(text, trace(TraceFormat::Text, TraceFilter::default()));
+}
+
+#[test]
+fn conversion_rejects_other_files() {
+    for input in [&b""[..], b"R8T", b"R8TX\x01", b"PK\x03\x04\x14\x00"] {
+        let error = convert_to_text(&mut Cursor::new(input), &mut Vec::new()).unwrap_err();
+        assert!(matches!(error.kind(), io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof), "{:?}", input);
+    }
+}
+
+#[test]
+fn conversion_rejects_other_versions() {
+    let error = convert_to_text(&mut Cursor::new(b"R8TR\x02"), &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(error.to_string(), "unsupported trace version 2");
+}
+
+#[test]
+fn conversion_rejects_truncated_records() {
+    let binary = trace(TraceFormat::Binary, TraceFilter::default());
+
+    // The last record, 1206, is 14 bytes: cut inside its cycle count, its opcode and its change counts
+    for cut in [10, 3, 1] {
+        let truncated = &binary[..binary.len() - cut];
+        let error = convert_to_text(&mut Cursor::new(truncated), &mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "cut {} bytes", cut);
+    }
+}
+
+#[test]
+fn pc_ranges_parse() {
+    assert_eq!(TraceFilter::parse_pc_range("0x200-0x2FF"), Some((0x200, 0x2FF)));
+    assert_eq!(TraceFilter::parse_pc_range("0x204-0x204"), Some((0x204, 0x204)));
+    assert_eq!(TraceFilter::parse_pc_range("0x2FF-0x200"), None);
+    assert_eq!(TraceFilter::parse_pc_range("0x200"), None);
+    assert_eq!(TraceFilter::parse_pc_range("0x200-"), None);
+    assert_eq!(TraceFilter::parse_pc_range("0x200-end"), None);
+}
+
+#[test]
+fn opcode_classes_parse() {
+    assert_eq!(TraceFilter::parse_opcode_classes("D,F"), Some(1 << 0xD | 1 << 0xF));
+    assert_eq!(TraceFilter::parse_opcode_classes("1, 2 ,b"), Some(0b0000_1000_0000_0110));
+    assert_eq!(TraceFilter::parse_opcode_classes("0"), Some(1));
+    assert_eq!(TraceFilter::parse_opcode_classes("10"), None);
+    assert_eq!(TraceFilter::parse_opcode_classes("G"), None);
+    assert_eq!(TraceFilter::parse_opcode_classes("D,"), None);
+}
+
+#[test]
+fn filter_keeps_the_matching_instructions() {
+    let filter = TraceFilter {
+        pc_range: Some((0x202, 0x206)),
+        opcode_classes: TraceFilter::parse_opcode_classes("A,F").unwrap(),
+        max_cycles: Some(3),
+    };
+    let mut text = Vec::new();
+    let records = convert_to_text(&mut Cursor::new(trace(TraceFormat::Binary, filter)), &mut text).unwrap();
+
+    // 6005 is out of range, 1206 of the classes (and past the cycles)
+    assert_eq!(records, 2);
+    let pcs: Vec<&str> = std::str::from_utf8(&text).unwrap().lines().map(|line| &line[12..15]).collect();
+    assert_eq!(pcs, ["202", "204"]);
+}
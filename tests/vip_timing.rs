@@ -0,0 +1,48 @@
+// `Timing::CosmacVip` runs what fits 3668 machine cycles a frame, less 1024 for the display and 46
+// for its interrupt.
+
+mod common;
+
+use common::{booted, run_frame_cycles};
+use rusty_8::{Chip8, Quirks, Timing};
+use rusty_8::timing::VIP_FRAME_BUDGET;
+
+fn vip_chip8(rom: &[u8]) -> Chip8 {
+    let mut chip8 = booted(Quirks::default(), rom);
+    chip8.timing = Timing::CosmacVip;
+    chip8
+}
+
+#[test]
+fn frame_budget_is_what_the_display_leaves() {
+    assert_eq!(VIP_FRAME_BUDGET, 3668 - 1024 - 46);
+}
+
+#[test]
+fn register_mix_fits_45_instructions() {
+    // 6105  V1 = 5           46 cycles
+    // 7201  V2 += 1          50 cycles
+    // 8124  V1 += V2         84 cycles
+    // 1200  Loop             52 cycles
+    let mut chip8 = vip_chip8(&[0x61, 0x05, 0x72, 0x01, 0x81, 0x24, 0x12, 0x00]);
+
+    // 11 loops take 2552 cycles, the 46 left run one more V1 = 5, which uses them up
+    assert_eq!(run_frame_cycles(&mut chip8, 0), 45);
+    assert_eq!(chip8.registers().v[2], 11);
+    assert_eq!(chip8.registers().pc, 0x202);
+}
+
+#[test]
+fn overspent_cycles_come_out_of_the_next_frame() {
+    // 6001  V0 = 1           46 cycles
+    // D00F  Draw 15 rows     666 cycles, as the sprite isn't byte-aligned at x = 1
+    // 1202  Loop             52 cycles
+    let mut chip8 = vip_chip8(&[0x60, 0x01, 0xD0, 0x0F, 0x12, 0x02]);
+
+    // V0 = 1 and 3 loops leave 398 cycles, the draw that starts then overspends by 268
+    assert_eq!(run_frame_cycles(&mut chip8, 0), 8);
+    // With 2330 cycles: a jump and 3 loops, leaving 124 for one more draw
+    assert_eq!(run_frame_cycles(&mut chip8, 0), 8);
+    // Overspent by 542, 2056 cycles: a jump, 2 loops and a draw
+    assert_eq!(run_frame_cycles(&mut chip8, 0), 6);
+}
@@ -0,0 +1,118 @@
+// Why headless runs stop, and the exit code CI scripts see for each reason.
+
+mod common;
+
+use common::booted;
+use rusty_8::{Chip8, Chip8Error, ExitReason, Limits, Quirks, Watchdog};
+
+// 6001  V0 = 1, then the empty memory past the ROM
+const HALTS: [u8; 2] = [0x60, 0x01];
+// 1200  Jump to itself
+const SELF_LOOP: [u8; 2] = [0x12, 0x00];
+// 00E0  Clear the screen
+// F129  I = sprite of the digit in V1, a 0
+// D005  Draw it at (V0, V0), V0 moving on each time around
+// 7001  V0 += 1
+// 1200  Loop
+const ANIMATES: [u8; 10] = [0x00, 0xE0, 0xF1, 0x29, 0xD0, 0x05, 0x70, 0x01, 0x12, 0x00];
+
+/// Run frames of 8 instructions until the watchdog stops the run, returning why and after how many frames.
+fn run(chip8: &mut Chip8, limits: Limits) -> (ExitReason, u32) {
+    let mut watchdog = Watchdog::new(limits);
+    loop {
+        if let Err(e) = chip8.run_frame(8) {
+            return (ExitReason::Error(e), watchdog.frames());
+        }
+        if let Some(reason) = watchdog.check(chip8) {
+            return (reason, watchdog.frames());
+        }
+        assert!(watchdog.frames() < 1000, "never stopped");
+    }
+}
+
+fn limits() -> Limits {
+    Limits { stop_on_self_loop: true, ..Limits::default() }
+}
+
+#[test]
+fn halting_ends_the_run() {
+    let (reason, frames) = run(&mut booted(Quirks::default(), &HALTS), limits());
+    assert_eq!((reason.clone(), frames), (ExitReason::Halted, 1));
+    assert_eq!(reason.exit_code(), 0);
+}
+
+#[test]
+fn self_loop_ends_the_run() {
+    let (reason, frames) = run(&mut booted(Quirks::default(), &SELF_LOOP), limits());
+    assert_eq!((reason.clone(), frames), (ExitReason::SelfLoop { pc: 0x200 }, 1));
+    assert_eq!(reason.exit_code(), 0);
+}
+
+#[test]
+fn self_loop_can_be_ignored() {
+    let limits = Limits { stop_on_self_loop: false, max_frames: Some(5), ..Limits::default() };
+    let (reason, frames) = run(&mut booted(Quirks::default(), &SELF_LOOP), limits);
+    assert_eq!((reason, frames), (ExitReason::BudgetExhausted, 5));
+}
+
+#[test]
+fn unchanged_screen_is_idle() {
+    let limits = Limits { stop_on_self_loop: false, idle_frames: Some(3), ..Limits::default() };
+    let (reason, frames) = run(&mut booted(Quirks::default(), &SELF_LOOP), limits);
+
+    // The first frame's screen is the one the next frames are compared to
+    assert_eq!((reason.clone(), frames), (ExitReason::Idle { frames: 3 }, 4));
+    assert_eq!(reason.exit_code(), 2);
+}
+
+#[test]
+fn changing_screen_isnt_idle() {
+    let limits = Limits { idle_frames: Some(3), max_frames: Some(20), ..limits() };
+    let (reason, _) = run(&mut booted(Quirks::default(), &ANIMATES), limits);
+    assert_eq!(reason, ExitReason::BudgetExhausted);
+}
+
+#[test]
+fn frame_budget_runs_out() {
+    let limits = Limits { max_frames: Some(10), ..limits() };
+    let (reason, frames) = run(&mut booted(Quirks::default(), &ANIMATES), limits);
+    assert_eq!((reason.clone(), frames), (ExitReason::BudgetExhausted, 10));
+    assert_eq!(reason.exit_code(), 3);
+}
+
+#[test]
+fn cycle_budget_is_checked_after_each_frame() {
+    let mut chip8 = booted(Quirks::default(), &ANIMATES);
+    let limits = Limits { max_cycles: Some(20), ..limits() };
+    let (reason, frames) = run(&mut chip8, limits);
+
+    // The third frame runs the 20th cycle, and runs to its end
+    assert_eq!((reason, frames), (ExitReason::BudgetExhausted, 3));
+    assert_eq!(chip8.cycles(), 24);
+}
+
+#[test]
+fn error_stops_the_run() {
+    // E000  Not an instruction
+    let (reason, _) = run(&mut booted(Quirks::default(), &[0xE0, 0x00]), limits());
+    assert_eq!(reason, ExitReason::Error(Chip8Error::UnknownInstruction { pc: 0x200, instruction: 0xE000 }));
+    assert_eq!(reason.exit_code(), 1);
+}
+
+#[test]
+fn exit_codes() {
+    let codes = [
+        (ExitReason::Halted, 0),
+        (ExitReason::SelfLoop { pc: 0x200 }, 0),
+        (ExitReason::Matched { steps: 10 }, 0),
+        (ExitReason::Stopped, 0),
+        (ExitReason::Error(Chip8Error::StackUnderflow { pc: 0x200 }), 1),
+        (ExitReason::Idle { frames: 60 }, 2),
+        (ExitReason::BudgetExhausted, 3),
+        (ExitReason::Diverged { step: 3 }, 4),
+        (ExitReason::ScriptFailed("assertion failed".to_string()), 5),
+    ];
+    for (reason, code) in codes {
+        assert_eq!(reason.exit_code(), code, "{}", reason);
+    }
+}
@@ -0,0 +1,77 @@
+// The WAV recorder, which renders the beep without an audio device.
+
+mod common;
+
+use rusty_8::Quirks;
+use rusty_8::wav::WavRecorder;
+
+const SAMPLE_RATE: u32 = 44100;
+const FRAME_SAMPLES: usize = SAMPLE_RATE as usize / 60;
+const FRAMES: usize = 40;
+
+// 601E  V0 = 30
+// F018  ST = V0
+// 1204  Loop forever
+const BEEP_30_FRAMES: [u8; 6] = [0x60, 0x1E, 0xF0, 0x18, 0x12, 0x04];
+
+fn record(rom: &[u8]) -> (WavRecorder, Vec<u8>) {
+    let mut chip8 = common::booted(Quirks::default(), rom);
+
+    let mut recorder = WavRecorder::new(SAMPLE_RATE, 815.0);
+    for _ in 0..FRAMES {
+        recorder.record_frame(chip8.run_frame(8).unwrap());
+    }
+
+    let mut wav = Vec::new();
+    recorder.write(&mut wav).unwrap();
+    (recorder, wav)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+#[test]
+fn header_describes_16_bit_mono_pcm() {
+    let (_, wav) = record(&BEEP_30_FRAMES);
+    let data_len = FRAMES * FRAME_SAMPLES * 2;
+
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(&wav, 4) as usize, 36 + data_len);
+    assert_eq!(&wav[8..12], b"WAVE");
+    assert_eq!(&wav[12..16], b"fmt ");
+    assert_eq!(u32_at(&wav, 16), 16);               // fmt chunk length
+    assert_eq!(u16_at(&wav, 20), 1);                // PCM
+    assert_eq!(u16_at(&wav, 22), 1);                // Mono
+    assert_eq!(u32_at(&wav, 24), SAMPLE_RATE);
+    assert_eq!(u32_at(&wav, 28), SAMPLE_RATE * 2);  // Byte rate
+    assert_eq!(u16_at(&wav, 32), 2);                // Block align
+    assert_eq!(u16_at(&wav, 34), 16);               // Bits per sample
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(&wav, 40) as usize, data_len);
+    assert_eq!(wav.len(), 44 + data_len);
+}
+
+#[test]
+fn sound_timer_of_30_beeps_for_30_frames() {
+    let (recorder, wav) = record(&BEEP_30_FRAMES);
+    assert_eq!(recorder.beeps(), &[(0, 30)]);
+
+    // The tone is a square wave, so no sample is silent while it sounds
+    let samples: Vec<i16> = wav[44..].chunks(2).map(|sample| i16::from_le_bytes([sample[0], sample[1]])).collect();
+    assert_eq!(samples.len(), FRAMES * FRAME_SAMPLES);
+    let beep_end = 30 * FRAME_SAMPLES;
+    assert!(samples[..beep_end].iter().all(|&sample| sample != 0));
+    assert!(samples[beep_end..].iter().all(|&sample| sample == 0));
+}
+
+#[test]
+fn silent_rom_records_silence() {
+    let (recorder, wav) = record(&[0x12, 0x00]);
+    assert!(recorder.beeps().is_empty());
+    assert!(wav[44..].iter().all(|&byte| byte == 0));
+}